@@ -1,11 +1,14 @@
+use std::fmt;
 use std::path::PathBuf;
 
 use clap::{
   Parser,
-  Subcommand
+  Subcommand,
+  ValueEnum
 };
 
 use crate::chunk::ChunkStrategy;
+use crate::config::Ranking;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -14,6 +17,17 @@ use crate::chunk::ChunkStrategy;
            toolbox"
 )]
 pub struct Cli {
+  /// Seed for every random choice
+  /// (chunk/document IDs, …) so runs
+  /// are reproducible; overrides
+  /// `stage1.seed`
+  #[arg(long, global = true)]
+  pub seed:    Option<u64>,
+  /// Config file to load; overrides
+  /// `OXBED_CONFIG` and the default
+  /// `oxbed-config.toml`
+  #[arg(long, global = true)]
+  pub config:  Option<PathBuf>,
   #[command(subcommand)]
   pub command: Command
 }
@@ -25,7 +39,16 @@ pub enum Command {
   Ingest {
     /// Path to a file or directory to
     /// ingest
-    path:            PathBuf,
+    #[arg(
+      required_unless_present = "from_manifest",
+      conflicts_with = "from_manifest"
+    )]
+    path:            Option<PathBuf>,
+    /// Ingest exactly the files
+    /// listed, one path per line,
+    /// in this file
+    #[arg(long)]
+    from_manifest:   Option<PathBuf>,
     /// Chunking strategy to apply
     /// (default: structured)
     #[arg(long, default_value_t = ChunkStrategy::Structured)]
@@ -43,17 +66,294 @@ pub enum Command {
   /// string
   Search {
     /// Query text
-    query: String,
+    query:             String,
+    /// Number of results to return
+    #[arg(long)]
+    top_k:             Option<usize>,
+    /// Only return chunks detected as
+    /// this language (ISO 639-3 code)
+    #[arg(long)]
+    language:          Option<String>,
+    /// Only return chunks from these
+    /// document IDs (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    in_docs: Option<Vec<String>>,
+    /// Output format for the results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format:            OutputFormat,
+    /// Shorthand for `--format json`:
+    /// a JSON array of hits with
+    /// `rank`, `score`,
+    /// `document_path`, `chunk_id`,
+    /// and `chunk_text`
+    #[arg(
+      long,
+      conflicts_with = "format"
+    )]
+    json:              bool,
+    /// Group results under their
+    /// document, ordered by each
+    /// document's best score
+    #[arg(long)]
+    group_by_doc:      bool,
+    /// Write the query's scoring trace
+    /// (query vector, candidate
+    /// scores, threshold, hits) as
+    /// JSON to this file
+    #[arg(long)]
+    trace:             Option<PathBuf>,
+    /// Return the whole Markdown
+    /// section of each hit that falls
+    /// under a heading
+    #[arg(long)]
+    expand_section:    bool,
+    /// Rank documents by their
+    /// aggregated chunk scores instead
+    /// of returning chunks
+    #[arg(
+      long,
+      conflicts_with = "group_by_doc"
+    )]
+    by_document:       bool,
+    /// Only print results scoring at
+    /// least this; retrieval and rank
+    /// numbers are unaffected
+    #[arg(long)]
+    min_display_score: Option<f32>,
+    /// Only print results scoring at
+    /// most this; retrieval and rank
+    /// numbers are unaffected
+    #[arg(long)]
+    max_display_score: Option<f32>,
+    /// Attach each hit's stored vector
+    /// to its JSON object (`--format
+    /// json` chunk lists only)
+    #[arg(long)]
+    include_vectors:   bool,
+    /// Push down hits about this text
+    /// (weighted by
+    /// `stage1.search.
+    /// negative_weight`)
+    #[arg(long = "not")]
+    not:               Option<String>,
+    /// Collapse hits whose vectors are
+    /// alike (single linkage above
+    /// `stage1.search.
+    /// cluster_similarity`)
+    /// into one result per cluster
+    #[arg(
+      long,
+      conflicts_with_all = [
+        "group_by_doc",
+        "by_document",
+        "include_vectors"
+      ]
+    )]
+    cluster_results:   bool,
+    /// Chunk scoring for this search
+    /// (default:
+    /// `stage1.search.ranking`)
+    #[arg(long, value_enum)]
+    ranking:           Option<Ranking>
+  },
+  /// Search several corpora, each in
+  /// its own data directory, and merge
+  /// the hits by score
+  SearchFederated {
+    /// Query text
+    query:     String,
+    /// Data directories holding each
+    /// corpus's state file
+    #[arg(required = true)]
+    data_dirs: Vec<PathBuf>,
+    /// Number of merged results to
+    /// return
+    #[arg(long)]
+    top_k:     Option<usize>
+  },
+  /// Find chunks similar to an
+  /// already indexed chunk
+  Similar {
+    /// Identifier of the source chunk
+    chunk_id: String,
     /// Number of results to return
     #[arg(long)]
+    top_k:    Option<usize>
+  },
+  /// Explain why a chunk did or did
+  /// not appear for a query
+  Diagnose {
+    /// Query text
+    query:    String,
+    /// Identifier of the expected
+    /// chunk
+    chunk_id: String
+  },
+  /// Watch a directory and ingest,
+  /// update, or remove documents as
+  /// files change
+  Watch {
+    /// Directory (or file) to watch
+    path:     PathBuf,
+    /// Chunking strategy to apply
+    /// (default: structured)
+    #[arg(long, default_value_t = ChunkStrategy::Structured)]
+    strategy: ChunkStrategy
+  },
+  /// Rebuild index vectors for every
+  /// stored chunk with the configured
+  /// embedder
+  Reindex {
+    /// Re-embed every chunk, even
+    /// those `skip_unchanged` would
+    /// keep
+    #[arg(long)]
+    force: bool
+  },
+  /// Re-split already ingested
+  /// documents with the current chunk
+  /// settings, rebuilding their text
+  /// from the stored chunks
+  Rechunk {
+    /// Chunking strategy to apply
+    /// (default: structured)
+    #[arg(long, default_value_t = ChunkStrategy::Structured)]
+    strategy: ChunkStrategy
+  },
+  /// Write every stored chunk to a
+  /// JSON lines file
+  Export {
+    /// Destination file
+    path:   PathBuf,
+    /// Skip chunks whose text was
+    /// already written, recording them
+    /// in a `.duplicates.jsonl`
+    /// sidecar
+    #[arg(long)]
+    dedupe: bool
+  },
+  /// Write the corpus to one Markdown
+  /// file, a section per chunk under a
+  /// heading per document
+  ExportMarkdown {
+    /// Destination file
+    path: PathBuf
+  },
+  /// Report documents that nearly
+  /// duplicate an earlier one
+  /// (MinHash over word shingles)
+  Dedupe {
+    /// Remove the reported documents
+    /// from the corpus
+    #[arg(long)]
+    remove: bool
+  },
+  /// Remove a document, its chunks, and
+  /// its index entries
+  Delete {
+    /// ID of the document to remove
+    #[arg(
+      long,
+      required_unless_present = "path",
+      conflicts_with = "path"
+    )]
+    doc_id: Option<String>,
+    /// Path the document was ingested
+    /// from (the file may be gone)
+    #[arg(long)]
+    path:   Option<PathBuf>
+  },
+  /// Remove the documents and chunks
+  /// added by the latest ingest run
+  Undo,
+  /// Run a search and save its chunk
+  /// IDs as a named collection
+  SaveCollection {
+    /// Collection name (replaced if it
+    /// exists)
+    name:  String,
+    /// Query text
+    query: String,
+    /// Number of results to save
+    #[arg(long)]
     top_k: Option<usize>
   },
+  /// Print a saved collection's chunks
+  ShowCollection {
+    /// Collection name
+    name: String
+  },
+  /// Print the configured embedder's
+  /// vector for a text as JSON
+  Embed {
+    /// Text to embed (read from stdin
+    /// when omitted)
+    text: Option<String>
+  },
+  /// List stored documents or chunks
+  List {
+    /// What to list
+    #[arg(value_enum)]
+    target:       ListTarget,
+    /// Only list this document or its
+    /// chunks, ordered by offset
+    #[arg(long)]
+    doc_id:       Option<String>,
+    /// Print the byte range and text
+    /// each chunk shares with the next
+    /// (requires `--doc-id`)
+    #[arg(long, requires = "doc_id")]
+    show_overlap: bool
+  },
   /// Show corpus status (documents,
   /// chunks)
   Status,
 
+  /// Print the corpus vocabulary with
+  /// document and total frequencies
+  Vocab {
+    /// Skip terms found in fewer
+    /// documents
+    #[arg(long, default_value_t = 1)]
+    min_df: usize,
+    /// Output format for the terms
+    #[arg(long, value_enum, default_value_t = VocabFormat::Csv)]
+    format: VocabFormat
+  },
+
   /// Run the Stage 2 evaluation harness
-  Evaluate,
+  Evaluate {
+    /// Also list the documents no
+    /// query retrieved
+    #[arg(long)]
+    coverage: bool
+  },
+
+  /// Rank the logged evaluation runs by
+  /// a metric
+  Leaderboard {
+    /// Metric to rank by
+    #[arg(long, value_enum, default_value_t = LeaderboardMetric::Recall)]
+    metric: LeaderboardMetric,
+    /// Number of runs to show
+    #[arg(long, default_value_t = 10)]
+    top:    usize,
+    /// Output format for the runs
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat
+  },
+
+  /// Sweep the `stage2.tuning` chunking
+  /// grid against the evaluation
+  /// queries and report the best
+  /// settings
+  Tune {
+    /// Also write the best max_tokens
+    /// and overlap into this config
+    /// file
+    #[arg(long)]
+    write: Option<PathBuf>
+  },
 
   /// Train a Stage 4 custom embedder
   Train {
@@ -72,10 +372,112 @@ pub enum Command {
   /// Run the Stage 3 RAG workflow
   Rag {
     /// Query text
-    query: String,
+    query:    String,
     /// Limit on retrieval hits
     /// (default per stage1 search)
     #[arg(long)]
-    top_k: Option<usize>
+    top_k:    Option<usize>,
+    /// Only retrieve chunks detected
+    /// as this language (ISO 639-3
+    /// code)
+    #[arg(long)]
+    language: Option<String>,
+    /// Only retrieve chunks from these
+    /// document IDs (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    in_docs:  Option<Vec<String>>,
+    /// Output format for the results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format:   OutputFormat
   }
 }
+
+/// How `vocab` writes the vocabulary.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  PartialEq,
+  Eq,
+  ValueEnum,
+)]
+pub enum VocabFormat {
+  /// `term,document_frequency,
+  /// total_frequency` rows
+  Csv,
+  /// JSON array of terms
+  Json
+}
+
+/// What `leaderboard` ranks runs by.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  PartialEq,
+  Eq,
+  ValueEnum,
+)]
+pub enum LeaderboardMetric {
+  Recall,
+  Mrr,
+  Ndcg,
+  /// Mean query latency, lowest first
+  Latency
+}
+
+impl fmt::Display
+  for LeaderboardMetric
+{
+  fn fmt(
+    &self,
+    f: &mut fmt::Formatter<'_>
+  ) -> fmt::Result {
+    f.write_str(match self {
+      | LeaderboardMetric::Recall => {
+        "recall"
+      }
+      | LeaderboardMetric::Mrr => "mrr",
+      | LeaderboardMetric::Ndcg => {
+        "ndcg"
+      }
+      | LeaderboardMetric::Latency => {
+        "latency"
+      }
+    })
+  }
+}
+
+/// What `list` prints.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  PartialEq,
+  Eq,
+  ValueEnum,
+)]
+pub enum ListTarget {
+  Documents,
+  Chunks
+}
+
+/// How `search` and `rag` render their
+/// results.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  PartialEq,
+  Eq,
+  ValueEnum,
+)]
+pub enum OutputFormat {
+  /// Plain console listing
+  Text,
+  /// Markdown list with fenced chunk
+  /// text
+  Markdown,
+  /// JSON array of hits
+  Json
+}
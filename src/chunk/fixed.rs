@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+  Chunk,
+  ChunkStrategy,
+  Chunker,
+  TokenBoundary,
+  token_positions
+};
+
+/// A snapped window end may move by up
+/// to `max_tokens /
+/// SENTENCE_SNAP_DIVISOR` tokens either
+/// way.
+const SENTENCE_SNAP_DIVISOR: usize = 4;
+
+/// Byte offsets where sentences end,
+/// excluding trailing whitespace.
+fn sentence_ends(
+  input: &str
+) -> HashSet<usize> {
+  input
+    .split_sentence_bound_indices()
+    .map(|(start, sentence)| {
+      start + sentence.trim_end().len()
+    })
+    .collect()
+}
+
+impl Chunker {
+  pub(super) fn fixed(
+    &self,
+    doc_id: &str,
+    input: &str
+  ) -> Vec<Chunk> {
+    let tokens = token_positions(input);
+    if tokens.is_empty() {
+      return Vec::new();
+    }
+    let mut results = Vec::new();
+    let mut seen =
+      if self.dedupe_segments {
+        Some(HashSet::new())
+      } else {
+        None
+      };
+    let step = (self
+      .max_tokens
+      .saturating_sub(self.overlap))
+    .max(1);
+    let sentence_ends =
+      if self.snap_to_sentence {
+        Some(sentence_ends(input))
+      } else {
+        None
+      };
+    let mut cursor = 0;
+    while cursor < tokens.len() {
+      let mut end = (cursor
+        + self.max_tokens)
+        .min(tokens.len());
+      if let Some(ends) = &sentence_ends
+      {
+        end = self.snap_end(
+          &tokens, ends, cursor, end
+        );
+      }
+      let start_pos =
+        tokens[cursor].start;
+      let end_pos = tokens[end - 1].end;
+      let candidate =
+        &input[start_pos..end_pos];
+      if let Some(chunk) = self.segment(
+        start_pos,
+        doc_id,
+        candidate,
+        ChunkStrategy::Fixed,
+        seen.as_mut()
+      ) {
+        results.push(chunk);
+      }
+      if end == tokens.len() {
+        break;
+      }
+      cursor = if sentence_ends
+        .is_some()
+      {
+        end
+          .saturating_sub(self.overlap)
+          .max(cursor + 1)
+      } else {
+        cursor + step
+      };
+    }
+    results
+  }
+
+  /// Returns the token index closest to
+  /// `end` (exclusive) whose preceding
+  /// token closes a sentence, searching
+  /// within the snap tolerance and
+  /// never producing an empty window.
+  fn snap_end(
+    &self,
+    tokens: &[TokenBoundary],
+    sentence_ends: &HashSet<usize>,
+    cursor: usize,
+    end: usize
+  ) -> usize {
+    if end == tokens.len() {
+      return end;
+    }
+    let tolerance = (self.max_tokens
+      / SENTENCE_SNAP_DIVISOR)
+      .max(1);
+    let low =
+      end.saturating_sub(tolerance);
+    let high = (end + tolerance)
+      .min(tokens.len());
+    (low.max(cursor + 1)..=high)
+      .filter(|&candidate| {
+        sentence_ends.contains(
+          &tokens[candidate - 1].end
+        )
+      })
+      .min_by_key(|&candidate| {
+        candidate.abs_diff(end)
+      })
+      .unwrap_or(end)
+  }
+}
@@ -0,0 +1,107 @@
+/// Whether `line` opens a Markdown list
+/// item: `-`, `*` or `+` bullets and
+/// `1.`/`1)` numbers, followed by a
+/// space.
+fn is_list_item(line: &str) -> bool {
+  let line = line.trim_start();
+  if let Some(rest) =
+    line.strip_prefix(['-', '*', '+'])
+  {
+    return rest.starts_with(' ');
+  }
+  let digits = line
+    .bytes()
+    .take_while(u8::is_ascii_digit)
+    .count();
+  digits > 0
+    && matches!(
+      line
+        .as_bytes()
+        .get(digits..digits + 2),
+      Some([b'.' | b')', b' '])
+    )
+}
+
+/// Whether `segment` ends inside a
+/// list: its last line is an item or an
+/// indented continuation of one.
+pub(super) fn ends_in_list(
+  segment: &str
+) -> bool {
+  let mut lines =
+    segment.lines().rev().filter(
+      |line| !line.trim().is_empty()
+    );
+  let Some(last) = lines.next() else {
+    return false;
+  };
+  is_list_item(last)
+    || (last.starts_with([' ', '\t'])
+      && std::iter::once(last)
+        .chain(lines)
+        .any(is_list_item))
+}
+
+/// Whether `text` (the start of the
+/// next segment, indentation intact)
+/// carries on a list: it opens with an
+/// item or an indented continuation
+/// line.
+pub(super) fn continues_list(
+  text: &str
+) -> bool {
+  let first = text
+    .trim_start_matches(['\r', '\n'])
+    .lines()
+    .next()
+    .unwrap_or_default();
+  !first.trim().is_empty()
+    && (is_list_item(first)
+      || first.starts_with([' ', '\t']))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn list_markers_are_recognized() {
+    for item in [
+      "- milk",
+      "* eggs",
+      "+ flour",
+      "12. step",
+      "3) step",
+      "  - nested"
+    ] {
+      assert!(
+        is_list_item(item),
+        "{}",
+        item
+      );
+    }
+    for text in [
+      "-dash", "2024 was", "1.5 cups",
+      "plain"
+    ] {
+      assert!(
+        !is_list_item(text),
+        "{}",
+        text
+      );
+    }
+    assert!(ends_in_list(
+      "Shopping:\n- milk\n  whole, \
+       not skim"
+    ));
+    assert!(!ends_in_list(
+      "- milk\nThanks"
+    ));
+    assert!(continues_list(
+      "\n  more detail"
+    ));
+    assert!(!continues_list(
+      "Next paragraph"
+    ));
+  }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::{
+  Deserialize,
+  Serialize
+};
+
+use crate::config::Summarizer;
+use crate::normalization::Normalizer;
+use crate::tokenizer::Tokenizer;
+use crate::{
+  rng,
+  summary
+};
+
+mod fixed;
+mod lists;
+mod structured;
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Serialize,
+  Deserialize,
+  ValueEnum,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkStrategy {
+  Structured,
+  Fixed
+}
+
+impl fmt::Display for ChunkStrategy {
+  fn fmt(
+    &self,
+    f: &mut fmt::Formatter<'_>
+  ) -> fmt::Result {
+    match self {
+      | ChunkStrategy::Structured => {
+        f.write_str("structured")
+      }
+      | ChunkStrategy::Fixed => {
+        f.write_str("fixed")
+      }
+    }
+  }
+}
+
+#[derive(
+  Clone, Debug, Serialize, Deserialize,
+)]
+pub struct Chunk {
+  pub id:        String,
+  pub doc_id:    String,
+  pub text:      String,
+  /// Byte offset into the document's
+  /// *normalized* text
+  pub start:     usize,
+  /// Byte offset into the document's
+  /// *normalized* text
+  pub end:       usize,
+  pub strategy:  ChunkStrategy,
+  #[serde(default)]
+  pub language:  Option<String>,
+  /// Byte offset into the raw source
+  /// file, when known
+  #[serde(default)]
+  pub raw_start: Option<usize>,
+  /// Byte offset into the raw source
+  /// file, when known
+  #[serde(default)]
+  pub raw_end:   Option<usize>,
+  /// Short summary of `text`, when a
+  /// summarizer is configured
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub summary:   Option<String>,
+  /// Nearest Markdown heading at or
+  /// before the chunk's start
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub heading:   Option<String>,
+  /// Ingest run that created the
+  /// chunk; 0 for chunks stored
+  /// before runs were recorded
+  #[serde(default)]
+  pub run_id:    u64
+}
+
+pub struct Chunker {
+  strategy: ChunkStrategy,
+  max_tokens:              usize,
+  overlap:                 usize,
+  split_on_double_newline: bool,
+  dedupe_segments:         bool,
+  chunk_separators:        Vec<String>,
+  min_unique_ratio:        f32,
+  snap_to_sentence:        bool,
+  keep_lists_together:     bool,
+  normalizer:              Normalizer,
+  summarizer:              Summarizer,
+  tokenizer:               Tokenizer
+}
+
+impl Chunker {
+  pub fn with_config(
+    strategy: ChunkStrategy,
+    max_tokens: usize,
+    overlap: usize,
+    split_on_double_newline: bool,
+    dedupe_segments: bool,
+    chunk_separators: Vec<String>
+  ) -> Self {
+    Self {
+      strategy,
+      max_tokens,
+      overlap,
+      split_on_double_newline,
+      dedupe_segments,
+      chunk_separators,
+      min_unique_ratio: 0.0,
+      snap_to_sentence: false,
+      keep_lists_together: false,
+      normalizer: Normalizer::default(),
+      summarizer: Summarizer::None,
+      tokenizer: Tokenizer::default()
+    }
+  }
+
+  /// Drops segments whose unique token
+  /// count divided by their total token
+  /// count is below `ratio` (0
+  /// disables).
+  pub fn with_min_unique_ratio(
+    mut self,
+    ratio: f32
+  ) -> Self {
+    self.min_unique_ratio = ratio;
+    self
+  }
+
+  /// Fixed strategy only: moves each
+  /// window's end to the nearest
+  /// sentence end within
+  /// `max_tokens /
+  /// SENTENCE_SNAP_DIVISOR`
+  /// tokens, keeping the window as is
+  /// when none is in reach.
+  pub fn with_snap_to_sentence(
+    mut self,
+    snap: bool
+  ) -> Self {
+    self.snap_to_sentence = snap;
+    self
+  }
+
+  /// Structured strategy only: skips
+  /// separators between Markdown list
+  /// items (and their indented
+  /// continuations) while the list
+  /// stays within `max_tokens`, so a
+  /// list is split only once it is
+  /// oversized.
+  pub fn with_keep_lists_together(
+    mut self,
+    keep: bool
+  ) -> Self {
+    self.keep_lists_together = keep;
+    self
+  }
+
+  pub fn with_normalizer(
+    mut self,
+    normalizer: Normalizer
+  ) -> Self {
+    self.normalizer = normalizer;
+    self
+  }
+
+  pub fn with_summarizer(
+    mut self,
+    summarizer: Summarizer
+  ) -> Self {
+    self.summarizer = summarizer;
+    self
+  }
+
+  pub fn with_tokenizer(
+    mut self,
+    tokenizer: Tokenizer
+  ) -> Self {
+    self.tokenizer = tokenizer;
+    self
+  }
+
+  /// Splits `input` with the configured
+  /// strategy. Input with any
+  /// non-whitespace text always yields
+  /// at least one chunk: when every
+  /// segment is filtered out (e.g. as
+  /// degenerate), the whole trimmed
+  /// input becomes a single chunk.
+  pub fn chunk(
+    &self,
+    doc_id: &str,
+    input: &str
+  ) -> Vec<Chunk> {
+    let chunks = match self.strategy {
+      | ChunkStrategy::Structured => {
+        self.structured(doc_id, input)
+      }
+      | ChunkStrategy::Fixed => {
+        self.fixed(doc_id, input)
+      }
+    };
+    if !chunks.is_empty()
+      || input.trim().is_empty()
+    {
+      return chunks;
+    }
+    vec![self.build_chunk(
+      0,
+      doc_id,
+      input,
+      self.strategy
+    )]
+  }
+
+  fn segment(
+    &self,
+    absolute_start: usize,
+    doc_id: &str,
+    segment: &str,
+    strategy: ChunkStrategy,
+    seen: Option<&mut HashSet<String>>
+  ) -> Option<Chunk> {
+    let trimmed = segment.trim();
+    if trimmed.is_empty()
+      || self.is_degenerate(trimmed)
+    {
+      return None;
+    }
+    if let Some(seen) = seen
+      && !seen
+        .insert(trimmed.to_string())
+    {
+      return None;
+    }
+    Some(self.build_chunk(
+      absolute_start,
+      doc_id,
+      segment,
+      strategy
+    ))
+  }
+
+  fn build_chunk(
+    &self,
+    absolute_start: usize,
+    doc_id: &str,
+    segment: &str,
+    strategy: ChunkStrategy
+  ) -> Chunk {
+    let trimmed = segment.trim();
+    let trimmed_start = segment.len()
+      - segment.trim_start().len();
+    let trimmed_end =
+      segment.trim_end().len();
+    let start =
+      absolute_start + trimmed_start;
+    let end =
+      absolute_start + trimmed_end;
+    let text = self
+      .normalizer
+      .normalize(trimmed);
+    let summary = summary::summarize(
+      self.summarizer,
+      &text,
+      &self.tokenizer
+    );
+    Chunk {
+      id: rng::uuid().to_string(),
+      doc_id: doc_id.to_string(),
+      text,
+      start,
+      end,
+      strategy,
+      language: None,
+      raw_start: None,
+      raw_end: None,
+      summary,
+      heading: None,
+      run_id: 0
+    }
+  }
+
+  fn is_degenerate(
+    &self,
+    text: &str
+  ) -> bool {
+    if self.min_unique_ratio <= 0.0 {
+      return false;
+    }
+    let tokens =
+      self.tokenizer.tokenize(text);
+    if tokens.is_empty() {
+      return true;
+    }
+    let unique = tokens
+      .iter()
+      .collect::<HashSet<_>>()
+      .len();
+    (unique as f32
+      / tokens.len() as f32)
+      < self.min_unique_ratio
+  }
+}
+
+struct TokenBoundary {
+  start: usize,
+  end:   usize
+}
+
+fn token_positions(
+  input: &str
+) -> Vec<TokenBoundary> {
+  let mut positions = Vec::new();
+  let mut start = None;
+  for (idx, ch) in input.char_indices()
+  {
+    if ch.is_whitespace() {
+      if let Some(s) = start.take() {
+        positions.push(TokenBoundary {
+          start: s,
+          end:   idx
+        });
+      }
+    } else if start.is_none() {
+      start = Some(idx);
+    }
+  }
+  if let Some(s) = start {
+    positions.push(TokenBoundary {
+      start: s,
+      end:   input.len()
+    });
+  }
+  positions
+}
+
+#[cfg(test)]
+mod tests;
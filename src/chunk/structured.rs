@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+
+use super::{
+  Chunk,
+  ChunkStrategy,
+  Chunker,
+  lists,
+  token_positions
+};
+
+impl Chunker {
+  pub(super) fn structured(
+    &self,
+    doc_id: &str,
+    input: &str
+  ) -> Vec<Chunk> {
+    let mut cursor = 0;
+    let mut results = Vec::new();
+    let mut seen =
+      if self.dedupe_segments {
+        Some(HashSet::new())
+      } else {
+        None
+      };
+    while cursor < input.len() {
+      let remaining = &input[cursor..];
+      let (split_len, sep_len) = if self
+        .split_on_double_newline
+      {
+        self.structured_split(remaining)
+      } else {
+        (remaining.len(), 0)
+      };
+      let segment = &input
+        [cursor..cursor + split_len];
+      if let Some(chunk) = self.segment(
+        cursor,
+        doc_id,
+        segment,
+        ChunkStrategy::Structured,
+        seen.as_mut()
+      ) {
+        results.push(chunk);
+      }
+      if split_len == remaining.len() {
+        break;
+      }
+      cursor += split_len;
+      cursor += sep_len;
+      cursor +=
+        skip_newlines(&input[cursor..]);
+    }
+    results
+  }
+
+  /// The next structured split of
+  /// `remaining`, as `(segment length,
+  /// separator length)`, extended past
+  /// list-internal separators when
+  /// `keep_lists_together` is set.
+  fn structured_split(
+    &self,
+    remaining: &str
+  ) -> (usize, usize) {
+    let (mut split_len, mut sep_len) =
+      find_split_length(
+        remaining,
+        &self.chunk_separators
+      );
+    if !self.keep_lists_together {
+      return (split_len, sep_len);
+    }
+    while split_len < remaining.len() {
+      let after = split_len + sep_len;
+      if !lists::ends_in_list(
+        &remaining[..split_len]
+      ) || !lists::continues_list(
+        &remaining[after..]
+      ) {
+        break;
+      }
+      let next = after
+        + skip_newlines(
+          &remaining[after..]
+        );
+      let (more, more_sep) =
+        find_split_length(
+          &remaining[next..],
+          &self.chunk_separators
+        );
+      if token_positions(
+        &remaining[..next + more]
+      )
+      .len()
+        > self.max_tokens
+      {
+        break;
+      }
+      split_len = next + more;
+      sep_len = more_sep;
+    }
+    (split_len, sep_len)
+  }
+}
+
+fn skip_newlines(
+  remainder: &str
+) -> usize {
+  remainder
+    .chars()
+    .take_while(|c| {
+      c.is_ascii_whitespace()
+    })
+    .map(|c| c.len_utf8())
+    .sum()
+}
+
+fn find_split_length(
+  remaining: &str,
+  separators: &[String]
+) -> (usize, usize) {
+  let mut best = None;
+  for sep in separators {
+    if sep.is_empty() {
+      continue;
+    }
+    if let Some(idx) =
+      remaining.find(sep)
+    {
+      match best {
+        | Some((best_idx, _))
+          if best_idx <= idx => {}
+        | _ => {
+          best = Some((idx, sep.len()))
+        }
+      }
+    }
+  }
+  best.unwrap_or((remaining.len(), 0))
+}
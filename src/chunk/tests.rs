@@ -0,0 +1,175 @@
+use super::*;
+
+#[test]
+fn structured_chunks_split_paragraphs_and_dedup()
+ {
+  let chunker = Chunker::with_config(
+    ChunkStrategy::Structured,
+    200,
+    32,
+    true,
+    true,
+    vec!["\n\n".into()]
+  );
+  let input = "alpha\n\nbeta\n\nalpha";
+  let chunks =
+    chunker.chunk("doc", input);
+  assert_eq!(chunks.len(), 2);
+  assert!(chunks.iter().any(|c| {
+    c.text.contains("alpha")
+  }));
+  assert!(chunks.iter().any(|c| {
+    c.text.contains("beta")
+  }));
+}
+
+#[test]
+fn lists_stay_in_one_chunk_until_oversized()
+ {
+  let input =
+    "Packing:\n\n- tent\n\n  with \
+     poles\n\n- stove\n\n1. \
+     fuel\n\nAfterwards we left.";
+  let texts = |max_tokens, keep| {
+    Chunker::with_config(
+      ChunkStrategy::Structured,
+      max_tokens,
+      0,
+      true,
+      false,
+      vec!["\n\n".into()]
+    )
+    .with_keep_lists_together(keep)
+    .chunk("doc", input)
+    .into_iter()
+    .map(|chunk| chunk.text)
+    .collect::<Vec<_>>()
+  };
+  assert_eq!(
+    texts(200, false).len(),
+    6
+  );
+  let kept = texts(200, true);
+  assert_eq!(kept.len(), 3);
+  assert!(
+    kept[1].starts_with("- tent")
+  );
+  assert!(kept[1].ends_with("fuel"));
+  let capped = texts(6, true);
+  assert_eq!(capped.len(), 4);
+  assert!(
+    capped[1].ends_with("- stove")
+  );
+  assert_eq!(capped[2], "1. fuel");
+}
+
+#[test]
+fn filtered_short_input_keeps_one_chunk()
+ {
+  let chunker = Chunker::with_config(
+    ChunkStrategy::Fixed,
+    200,
+    32,
+    true,
+    true,
+    vec!["\n\n".into()]
+  )
+  .with_min_unique_ratio(0.5);
+  let chunks =
+    chunker.chunk("doc", "  ...  ");
+  assert_eq!(chunks.len(), 1);
+  assert_eq!(chunks[0].text, "...");
+  assert_eq!(
+    (chunks[0].start, chunks[0].end),
+    (2, 5)
+  );
+  assert!(
+    chunker
+      .chunk("doc", " \n\t ")
+      .is_empty()
+  );
+}
+
+#[test]
+fn fixed_chunks_obey_overlap_and_max() {
+  let chunker = Chunker::with_config(
+    ChunkStrategy::Fixed,
+    200,
+    32,
+    true,
+    true,
+    vec!["\n\n".into()]
+  );
+  let input = "word ".repeat(500);
+  let chunks =
+    chunker.chunk("doc", &input);
+  assert!(chunks.len() >= 2);
+  for chunk in &chunks {
+    assert!(!chunk.text.is_empty());
+  }
+  let start_positions: Vec<_> = chunks
+    .iter()
+    .map(|c| c.start)
+    .collect();
+  assert!(
+    start_positions
+      .windows(2)
+      .all(|w| w[1] > w[0])
+  );
+}
+
+#[test]
+fn min_unique_ratio_drops_repetitive_chunks()
+ {
+  let chunker = Chunker::with_config(
+    ChunkStrategy::Structured,
+    200,
+    32,
+    true,
+    true,
+    vec!["\n\n".into()]
+  )
+  .with_min_unique_ratio(0.5);
+  let chunks = chunker.chunk(
+    "doc",
+    "a a a a a\n\nthe whale swam on"
+  );
+  let texts: Vec<_> = chunks
+    .iter()
+    .map(|c| c.text.as_str())
+    .collect();
+  assert_eq!(texts, vec![
+    "the whale swam on"
+  ]);
+}
+
+#[test]
+fn snapped_fixed_chunks_end_at_sentences()
+ {
+  let chunker = Chunker::with_config(
+    ChunkStrategy::Fixed,
+    8,
+    0,
+    true,
+    false,
+    vec!["\n\n".into()]
+  )
+  .with_snap_to_sentence(true);
+  let input =
+    "The whale swam far out to sea. \
+     It dove deep under the hull of \
+     the ship. The crew watched the \
+     water in complete silence. Night \
+     fell over the quiet sea.";
+  let chunks =
+    chunker.chunk("doc", input);
+  assert_eq!(chunks.len(), 4);
+  for chunk in &chunks {
+    assert!(chunk.text.ends_with('.'));
+    let tokens = chunk
+      .text
+      .split_whitespace()
+      .count();
+    assert!((6..=10).contains(&tokens));
+  }
+}
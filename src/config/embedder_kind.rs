@@ -0,0 +1,119 @@
+use std::fmt;
+
+use serde::{
+  Deserialize,
+  Deserializer
+};
+
+#[derive(Clone, Debug)]
+pub enum EmbedderKind {
+  Tf,
+  /// TF weighted by corpus-wide IDF
+  TfIdf,
+  BagOfWords,
+  Custom {
+    name:    String,
+    version: Option<String>
+  }
+}
+
+impl<'de> Deserialize<'de>
+  for EmbedderKind
+{
+  fn deserialize<D>(
+    deserializer: D
+  ) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>
+  {
+    struct EmbedderKindVisitor;
+
+    impl<'de> serde::de::Visitor<'de>
+      for EmbedderKindVisitor
+    {
+      type Value = EmbedderKind;
+
+      fn expecting(
+        &self,
+        formatter: &mut fmt::Formatter<
+          '_
+        >
+      ) -> fmt::Result {
+        formatter.write_str(
+          "tf, tfidf, bag-of-words, \
+           or custom:<name>[:\
+           <version>]"
+        )
+      }
+
+      fn visit_str<E>(
+        self,
+        value: &str
+      ) -> Result<Self::Value, E>
+      where
+        E: serde::de::Error
+      {
+        let normalized =
+          value.trim().to_lowercase();
+        match normalized.as_str() {
+          | "tf" => {
+            Ok(EmbedderKind::Tf)
+          }
+          | "tfidf" | "tf-idf" => {
+            Ok(EmbedderKind::TfIdf)
+          }
+          | "bag-of-words" => {
+            Ok(EmbedderKind::BagOfWords)
+          }
+          | _ if normalized
+            .starts_with("custom:") =>
+          {
+            let parts: Vec<_> =
+              normalized
+                .splitn(3, ':')
+                .collect();
+            let name = parts
+              .get(1)
+              .cloned()
+              .unwrap_or_default();
+            if name.is_empty() {
+              return Err(
+                serde::de::Error::custom(
+                  "custom embedder needs a name"
+                )
+              );
+            }
+            let version = parts
+              .get(2)
+              .and_then(|v| {
+                if v.is_empty() {
+                  None
+                } else {
+                  Some(v.to_string())
+                }
+              });
+            Ok(EmbedderKind::Custom {
+              name: name.to_string(),
+              version
+            })
+          }
+          | _ => {
+            Err(
+              serde::de::Error::custom(
+                format!(
+                  "unknown embedder \
+                   kind '{}'",
+                  value
+                )
+              )
+            )
+          }
+        }
+      }
+    }
+
+    deserializer.deserialize_str(
+      EmbedderKindVisitor
+    )
+  }
+}
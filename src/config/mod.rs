@@ -0,0 +1,264 @@
+#![allow(dead_code)]
+
+mod embedder_kind;
+mod stage1;
+mod stage2;
+mod stage3;
+mod stage4;
+
+use std::ffi::OsString;
+use std::path::{
+  Path,
+  PathBuf
+};
+use std::{
+  env,
+  fs
+};
+
+use anyhow::{
+  Context,
+  Result,
+  bail
+};
+use serde::Deserialize;
+
+pub use self::embedder_kind::EmbedderKind;
+pub use self::stage1::*;
+pub use self::stage2::*;
+pub use self::stage3::*;
+pub use self::stage4::*;
+
+/// Config file read when neither
+/// `--config` nor `OXBED_CONFIG` names
+/// one.
+pub const DEFAULT_CONFIG_FILE: &str =
+  "oxbed-config.toml";
+
+#[derive(
+  Clone, Debug, Default, Deserialize,
+)]
+pub struct Config {
+  #[serde(default)]
+  pub stage1: Stage1Config,
+  #[serde(default)]
+  pub stage2: Stage2Config,
+  #[serde(default)]
+  pub stage3: Stage3Config,
+  #[serde(default)]
+  pub stage4: Stage4Config
+}
+
+impl Config {
+  pub fn load<
+    P: AsRef<std::path::Path>
+  >(
+    path: P
+  ) -> Result<Self> {
+    let path_ref = path.as_ref();
+    if path_ref.exists() {
+      let contents =
+        fs::read_to_string(path_ref)
+          .with_context(|| {
+            format!(
+              "read config {:?}",
+              path_ref
+            )
+          })?;
+      let mut config: Self =
+        toml::from_str(&contents)
+          .with_context(|| {
+            format!(
+              "parse config {:?}",
+              path_ref
+            )
+          })?;
+      config
+        .stage1
+        .ingest
+        .migrate_deprecated();
+      Ok(config)
+    } else {
+      Ok(Self::default())
+    }
+  }
+
+  /// Loads the config named by
+  /// `explicit` (`--config`), else by
+  /// the `OXBED_CONFIG` environment
+  /// variable, else
+  /// [`DEFAULT_CONFIG_FILE`]. A named
+  /// file must exist and parse; a
+  /// missing or unreadable default file
+  /// leaves the default config.
+  pub fn resolve(
+    explicit: Option<&Path>
+  ) -> Result<Self> {
+    Self::resolve_from(
+      explicit,
+      env::var_os("OXBED_CONFIG")
+    )
+  }
+
+  /// [`Config::resolve`] with the
+  /// `OXBED_CONFIG` value passed in;
+  /// an empty value counts as unset.
+  pub fn resolve_from(
+    explicit: Option<&Path>,
+    env_value: Option<OsString>
+  ) -> Result<Self> {
+    let named = explicit
+      .map(Path::to_path_buf)
+      .or_else(|| {
+        env_value
+          .filter(|path| {
+            !path.is_empty()
+          })
+          .map(PathBuf::from)
+      });
+    match named {
+      | Some(path) => {
+        if !path.exists() {
+          bail!(
+            "config file {:?} not \
+             found",
+            path
+          );
+        }
+        Self::load(path)
+      }
+      | None => {
+        Ok(
+          Self::load(
+            DEFAULT_CONFIG_FILE
+          )
+          .unwrap_or_default()
+        )
+      }
+    }
+  }
+}
+
+pub(crate) fn default_true() -> bool {
+  true
+}
+
+pub(crate) fn default_false() -> bool {
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use tempfile::TempDir;
+
+  use super::*;
+
+  #[test]
+  fn env_config_path_is_honored()
+  -> Result<()> {
+    let temp = TempDir::new()?;
+    let from_env =
+      temp.path().join("env.toml");
+    fs::write(
+      &from_env,
+      "[stage1.search]\ntop_k = 17\n"
+    )?;
+    let from_flag =
+      temp.path().join("flag.toml");
+    fs::write(
+      &from_flag,
+      "[stage1.search]\ntop_k = 3\n"
+    )?;
+    let env_config =
+      Config::resolve_from(
+        None,
+        Some(from_env.clone().into())
+      );
+    let flag_config =
+      Config::resolve_from(
+        Some(&from_flag),
+        Some(from_env.into())
+      );
+    let missing = Config::resolve_from(
+      Some(
+        &temp
+          .path()
+          .join("missing.toml")
+      ),
+      None
+    );
+    let empty_env =
+      Config::resolve_from(
+        None,
+        Some(OsString::new())
+      );
+    assert_eq!(
+      env_config?.stage1.search.top_k,
+      17
+    );
+    assert_eq!(
+      flag_config?.stage1.search.top_k,
+      3
+    );
+    assert!(missing.is_err());
+    assert!(empty_env.is_ok());
+    Ok(())
+  }
+
+  #[test]
+  fn skip_duplicates_false_loads_as_add_full()
+  -> Result<()> {
+    let temp = TempDir::new()?;
+    let load = |name: &str,
+                ingest: &str|
+     -> Result<
+      DuplicateContent
+    > {
+      let path = temp.path().join(name);
+      fs::write(
+        &path,
+        format!(
+          "[stage1.ingest]\n{ingest}\n"
+        )
+      )?;
+      let config = Config::load(path)?;
+      assert_eq!(
+        config
+          .stage1
+          .ingest
+          .skip_duplicates,
+        None
+      );
+      Ok(
+        config
+          .stage1
+          .ingest
+          .duplicate_content
+      )
+    };
+    assert_eq!(
+      load(
+        "off.toml",
+        "skip_duplicates = false"
+      )?,
+      DuplicateContent::AddFull
+    );
+    assert_eq!(
+      load(
+        "on.toml",
+        "skip_duplicates = true"
+      )?,
+      DuplicateContent::Skip
+    );
+    assert_eq!(
+      load(
+        "both.toml",
+        "skip_duplicates = \
+         false\nduplicate_content = \
+         \"add-path-only\""
+      )?,
+      DuplicateContent::AddPathOnly
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,110 @@
+use serde::Deserialize;
+
+use crate::config::{
+  default_false,
+  default_true
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage1Chunk {
+  #[serde(
+    default = "default_max_tokens"
+  )]
+  pub max_tokens:              usize,
+  #[serde(default = "default_overlap")]
+  pub overlap:                 usize,
+  #[serde(default = "default_true")]
+  pub split_on_double_newline: bool,
+  #[serde(default = "default_true")]
+  pub dedupe_segments:         bool,
+  #[serde(
+    default = "default_chunk_separators"
+  )]
+  pub chunk_separators: Vec<String>,
+  #[serde(default)]
+  pub min_unique_ratio:        f32,
+  #[serde(default = "default_false")]
+  pub snap_to_sentence:        bool,
+  #[serde(default = "default_false")]
+  pub keep_lists_together:     bool,
+  #[serde(default)]
+  pub summarizer: Summarizer,
+  #[serde(default)]
+  pub max_chunks_per_doc: Option<usize>,
+  #[serde(default)]
+  pub chunk_cap_sampling:
+    ChunkCapSampling
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChunkCapSampling {
+  #[default]
+  First,
+  Even
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Summarizer {
+  #[default]
+  None,
+  Extractive
+}
+
+impl Default for Stage1Chunk {
+  fn default() -> Self {
+    Self {
+      max_tokens:
+        default_max_tokens(),
+      overlap:
+        default_overlap(),
+      split_on_double_newline: true,
+      dedupe_segments:         true,
+      chunk_separators:
+        default_chunk_separators(),
+      min_unique_ratio:        0.0,
+      snap_to_sentence:        false,
+      keep_lists_together:     false,
+      summarizer:
+        Summarizer::None,
+      max_chunks_per_doc:      None,
+      chunk_cap_sampling:
+        ChunkCapSampling::First
+    }
+  }
+}
+
+fn default_max_tokens() -> usize {
+  200
+}
+
+fn default_overlap() -> usize {
+  32
+}
+
+fn default_chunk_separators()
+-> Vec<String> {
+  vec![
+    "\n\n".into(),
+    "\r\n\r\n".into(),
+    "\n-\n".into(),
+    "\n*\n".into(),
+  ]
+}
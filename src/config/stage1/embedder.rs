@@ -0,0 +1,206 @@
+use serde::Deserialize;
+
+use crate::config::{
+  EmbedderKind,
+  default_false,
+  default_true
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage1Embedder {
+  #[serde(
+    default = "default_min_freq"
+  )]
+  pub tfidf_min_freq:    usize,
+  #[serde(default = "default_true")]
+  pub normalize_query:   bool,
+  #[serde(
+    default = "default_embedder_kind"
+  )]
+  pub kind:              EmbedderKind,
+  #[serde(default)]
+  pub fallback: Option<EmbedderKind>,
+  #[serde(
+    default = "default_idf_smoothing"
+  )]
+  pub idf_smoothing:     IdfSmoothing,
+  #[serde(default)]
+  pub tf_scheme:         TfScheme,
+  #[serde(default = "default_false")]
+  pub number_bucketing:  bool,
+  #[serde(default = "default_true")]
+  pub lowercase_tokens:  bool,
+  #[serde(default = "default_true")]
+  pub skip_unchanged:    bool,
+  #[serde(default)]
+  pub path_token_weight: f32,
+  #[serde(default = "default_false")]
+  pub frozen_vocab:      bool,
+  #[serde(default)]
+  pub stemmer:           Stemmer
+}
+
+impl Default for Stage1Embedder {
+  fn default() -> Self {
+    Self {
+      tfidf_min_freq:
+        default_min_freq(),
+      normalize_query:   true,
+      kind:
+        default_embedder_kind(),
+      fallback:          None,
+      idf_smoothing:
+        default_idf_smoothing(),
+      tf_scheme:         TfScheme::Raw,
+      number_bucketing:  false,
+      lowercase_tokens:  true,
+      skip_unchanged:    true,
+      path_token_weight: 0.0,
+      frozen_vocab:      false,
+      stemmer:           Stemmer::None
+    }
+  }
+}
+
+/// How tokens are reduced to a base
+/// form before embedding.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stemmer {
+  /// Keep tokens as they are
+  #[default]
+  None,
+  /// Map inflected English words to
+  /// their dictionary lemma
+  Lemmatize
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdfSmoothing {
+  None,
+  PlusOne,
+  Bm25
+}
+
+impl IdfSmoothing {
+  /// Inverse document frequency of a
+  /// term seen in `df` of `documents`
+  /// documents.
+  ///
+  /// `None` is the textbook
+  /// `ln(N / df)`, which is zero for
+  /// terms present everywhere and
+  /// undefined for unseen terms (0 is
+  /// returned). `PlusOne` computes
+  /// `ln((N + 1) / (df + 1)) + 1` and
+  /// `Bm25` computes
+  /// `ln(1 + (N - df + 0.5) / (df +
+  /// 0.5))`; both stay positive and
+  /// finite on tiny corpora.
+  pub fn idf(
+    self,
+    documents: usize,
+    df: usize
+  ) -> f32 {
+    let n = documents as f32;
+    let df_f = df.min(documents) as f32;
+    match self {
+      | IdfSmoothing::None => {
+        if df == 0 || documents == 0 {
+          0.0
+        } else {
+          (n / df_f).ln()
+        }
+      }
+      | IdfSmoothing::PlusOne => {
+        ((n + 1.0) / (df_f + 1.0)).ln()
+          + 1.0
+      }
+      | IdfSmoothing::Bm25 => {
+        (1.0
+          + (n - df_f + 0.5)
+            / (df_f + 0.5))
+          .ln()
+      }
+    }
+  }
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TfScheme {
+  #[default]
+  Raw,
+  Log,
+  Binary,
+  Augmented
+}
+
+impl TfScheme {
+  /// Weight of a term occurring `tf`
+  /// times in a text whose most
+  /// frequent term occurs `max_tf`
+  /// times: `tf` (`Raw`), `1 + ln(tf)`
+  /// (`Log`), `1` (`Binary`), or `0.5 +
+  /// 0.5 * tf / max_tf` (`Augmented`).
+  pub fn weight(
+    self,
+    tf: usize,
+    max_tf: usize
+  ) -> f32 {
+    if tf == 0 {
+      return 0.0;
+    }
+    let tf_f = tf as f32;
+    match self {
+      | TfScheme::Raw => tf_f,
+      | TfScheme::Log => {
+        1.0 + tf_f.ln()
+      }
+      | TfScheme::Binary => 1.0,
+      | TfScheme::Augmented => {
+        0.5
+          + 0.5 * tf_f
+            / max_tf.max(tf) as f32
+      }
+    }
+  }
+}
+
+fn default_embedder_kind()
+-> EmbedderKind {
+  EmbedderKind::Tf
+}
+
+fn default_idf_smoothing()
+-> IdfSmoothing {
+  IdfSmoothing::None
+}
+
+fn default_min_freq() -> usize {
+  1
+}
@@ -0,0 +1,210 @@
+use serde::Deserialize;
+
+use crate::config::{
+  default_false,
+  default_true
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage1Ingest {
+  #[serde(
+    default = "default_extensions"
+  )]
+  pub extensions: Vec<String>,
+  #[serde(default)]
+  pub duplicate_content:
+    DuplicateContent,
+  #[serde(default)]
+  pub skip_duplicates: Option<bool>,
+  #[serde(default = "default_true")]
+  pub verbose_documents:        bool,
+  #[serde(
+    default = "default_ingest_on_error"
+  )]
+  pub on_error: IngestErrorPolicy,
+  #[serde(default)]
+  pub max_file_bytes: Option<u64>,
+  #[serde(default = "default_false")]
+  pub truncate_large:           bool,
+  #[serde(default = "default_false")]
+  pub mmap:                     bool,
+  #[serde(default = "default_false")]
+  pub detect_language:          bool,
+  #[serde(
+    default = "default_watch_debounce_ms"
+  )]
+  pub watch_debounce_ms:        u64,
+  #[serde(default = "default_false")]
+  pub parse_frontmatter:        bool,
+  #[serde(default = "default_false")]
+  pub skip_near_duplicates:     bool,
+  #[serde(
+    default = "default_near_duplicate_threshold"
+  )]
+  pub near_duplicate_threshold: f32,
+  #[serde(
+    default = "default_minhash_bands"
+  )]
+  pub minhash_bands:            usize,
+  #[serde(
+    default = "default_minhash_rows"
+  )]
+  pub minhash_rows:             usize
+}
+
+impl Default for Stage1Ingest {
+  fn default() -> Self {
+    Self {
+      extensions:
+        default_extensions(),
+      duplicate_content:
+        DuplicateContent::default(),
+      skip_duplicates:          None,
+      verbose_documents:        true,
+      on_error:
+        default_ingest_on_error(),
+      max_file_bytes:           None,
+      truncate_large:           false,
+      mmap:                     false,
+      detect_language:          false,
+      watch_debounce_ms:
+        default_watch_debounce_ms(),
+      parse_frontmatter:        false,
+      skip_near_duplicates:     false,
+      near_duplicate_threshold:
+        default_near_duplicate_threshold(
+        ),
+      minhash_bands:
+        default_minhash_bands(),
+      minhash_rows:
+        default_minhash_rows()
+    }
+  }
+}
+
+impl Stage1Ingest {
+  /// Folds the deprecated
+  /// `skip_duplicates` flag into
+  /// `duplicate_content`: `false`
+  /// becomes `add-full` unless
+  /// `duplicate_content` already asks
+  /// for something other than `skip`.
+  pub(crate) fn migrate_deprecated(
+    &mut self
+  ) {
+    let Some(skip) =
+      self.skip_duplicates.take()
+    else {
+      return;
+    };
+    eprintln!(
+      "Warning: stage1.ingest.\
+       skip_duplicates is deprecated; \
+       use duplicate_content"
+    );
+    if !skip
+      && self.duplicate_content
+        == DuplicateContent::Skip
+    {
+      self.duplicate_content =
+        DuplicateContent::AddFull;
+    }
+  }
+}
+
+#[derive(
+  Clone, Debug, Default, Deserialize,
+)]
+pub struct Stage1Normalization {
+  #[serde(default)]
+  pub join_hyphenated_linebreaks: bool,
+  #[serde(default)]
+  pub keep_linebreak_hyphen:      bool,
+  #[serde(default)]
+  pub url_policy: PatternPolicy,
+  #[serde(default)]
+  pub email_policy: PatternPolicy,
+  #[serde(default)]
+  pub preserve_code_blocks:       bool
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum PatternPolicy {
+  #[default]
+  Keep,
+  Drop,
+  Token
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum IngestErrorPolicy {
+  Abort,
+  Skip
+}
+
+/// What ingest does with a file whose
+/// normalized content matches a stored
+/// document's.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateContent {
+  /// Ignore the file
+  #[default]
+  Skip,
+  /// Record the file's path as an alias
+  /// of the stored document
+  AddPathOnly,
+  /// Ingest it as a separate document
+  AddFull
+}
+
+fn default_extensions() -> Vec<String> {
+  vec!["txt".into(), "md".into()]
+}
+
+fn default_ingest_on_error()
+-> IngestErrorPolicy {
+  IngestErrorPolicy::Abort
+}
+
+fn default_watch_debounce_ms() -> u64 {
+  500
+}
+
+fn default_near_duplicate_threshold()
+-> f32 {
+  0.8
+}
+
+fn default_minhash_bands() -> usize {
+  16
+}
+
+fn default_minhash_rows() -> usize {
+  4
+}
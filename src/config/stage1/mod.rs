@@ -0,0 +1,56 @@
+mod chunk;
+mod embedder;
+mod ingest;
+mod search;
+mod storage;
+
+use serde::Deserialize;
+
+pub use self::chunk::*;
+pub use self::embedder::*;
+pub use self::ingest::*;
+pub use self::search::*;
+pub use self::storage::*;
+use crate::config::default_true;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage1Config {
+  #[serde(default = "default_true")]
+  pub enabled:       bool,
+  #[serde(default)]
+  pub seed:          Option<u64>,
+  #[serde(default)]
+  pub ingest:        Stage1Ingest,
+  #[serde(default)]
+  pub normalization:
+    Stage1Normalization,
+  #[serde(default)]
+  pub chunk:         Stage1Chunk,
+  #[serde(default)]
+  pub embedder:      Stage1Embedder,
+  #[serde(default)]
+  pub search:        Stage1Search,
+  #[serde(default)]
+  pub storage:       Stage1Storage
+}
+
+impl Default for Stage1Config {
+  fn default() -> Self {
+    Self {
+      enabled:       true,
+      seed:          None,
+      ingest:
+        Stage1Ingest::default(),
+      normalization:
+        Stage1Normalization::default(),
+      chunk:
+        Stage1Chunk::default(),
+      embedder:
+        Stage1Embedder::default(),
+      search:
+        Stage1Search::default(),
+      storage:
+        Stage1Storage::default()
+    }
+  }
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::{
+  Deserialize,
+  Serialize
+};
+
+use crate::chunk::ChunkStrategy;
+use crate::config::{
+  EmbedderKind,
+  default_false
+};
+use crate::index::{
+  BM25_B,
+  BM25_K1
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage1Search {
+  #[serde(default = "default_top_k")]
+  pub top_k:                usize,
+  #[serde(default)]
+  pub score_threshold:      f32,
+  #[serde(default = "default_false")]
+  pub rerank_enabled:       bool,
+  #[serde(default)]
+  pub mmr_lambda:           Option<f32>,
+  #[serde(default)]
+  pub dedup_similarity:     Option<f32>,
+  #[serde(default)]
+  pub length_penalty:       f32,
+  #[serde(default)]
+  pub position_boost:       f32,
+  #[serde(default)]
+  pub pinned_doc_ids:       Vec<String>,
+  #[serde(default = "default_false")]
+  pub two_stage:            bool,
+  #[serde(default = "default_false")]
+  pub search_summaries:     bool,
+  #[serde(default)]
+  pub prefer_strategy:
+    Option<ChunkStrategy>,
+  #[serde(default = "default_false")]
+  pub grep_fallback:        bool,
+  #[serde(default)]
+  pub timeout_ms:           Option<u64>,
+  #[serde(default)]
+  pub blend: Vec<(EmbedderKind, f32)>,
+  #[serde(default)]
+  pub query_log: Option<PathBuf>,
+  #[serde(default)]
+  pub threshold_mode: ThresholdMode,
+  #[serde(default = "default_false")]
+  pub parse_term_weights:   bool,
+  #[serde(default)]
+  pub proximity_boost:      f32,
+  #[serde(default)]
+  pub document_score: DocumentScore,
+  #[serde(default = "default_false")]
+  pub spell_correct:        bool,
+  #[serde(default)]
+  pub min_doc_tokens:       usize,
+  #[serde(default = "default_false")]
+  pub high_precision:       bool,
+  #[serde(default = "default_false")]
+  pub federated_normalize:  bool,
+  #[serde(default)]
+  pub ranking:              Ranking,
+  #[serde(default = "default_bm25_k1")]
+  pub bm25_k1:              f32,
+  #[serde(default = "default_bm25_b")]
+  pub bm25_b:               f32,
+  #[serde(
+    default = "default_max_hit_vector_terms"
+  )]
+  pub max_hit_vector_terms: usize,
+  #[serde(
+    default = "default_negative_weight"
+  )]
+  pub negative_weight:      f32,
+  #[serde(
+    default = "default_cluster_similarity"
+  )]
+  pub cluster_similarity:   f32
+}
+
+/// How `score_threshold` is applied.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThresholdMode {
+  /// Keep hits scoring at least the
+  /// threshold
+  #[default]
+  Absolute,
+  /// Keep hits scoring above the
+  /// threshold's percentile (0–1) of
+  /// the query's candidate scores
+  Percentile
+}
+
+/// How chunk candidates are scored
+/// against the query vector.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  Serialize,
+  ValueEnum,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ranking {
+  /// Cosine similarity of the vectors
+  #[default]
+  Cosine,
+  /// Okapi BM25 over the query's terms
+  Bm25
+}
+
+impl fmt::Display for Ranking {
+  fn fmt(
+    &self,
+    f: &mut fmt::Formatter<'_>
+  ) -> fmt::Result {
+    match self {
+      | Ranking::Cosine => {
+        f.write_str("cosine")
+      }
+      | Ranking::Bm25 => {
+        f.write_str("bm25")
+      }
+    }
+  }
+}
+
+/// How `search --by-document` combines
+/// a document's chunk scores.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum DocumentScore {
+  /// The best chunk score
+  #[default]
+  Max,
+  /// The sum of the matched chunks'
+  /// scores
+  Sum
+}
+
+impl Default for Stage1Search {
+  fn default() -> Self {
+    Self {
+      top_k:
+        default_top_k(),
+      score_threshold:      0.0,
+      rerank_enabled:       false,
+      mmr_lambda:           None,
+      dedup_similarity:     None,
+      length_penalty:       0.0,
+      position_boost:       0.0,
+      pinned_doc_ids:       Vec::new(),
+      two_stage:            false,
+      search_summaries:     false,
+      prefer_strategy:      None,
+      grep_fallback:        false,
+      timeout_ms:           None,
+      blend:                Vec::new(),
+      query_log:            None,
+      threshold_mode:
+        ThresholdMode::Absolute,
+      parse_term_weights:   false,
+      proximity_boost:      0.0,
+      document_score:
+        DocumentScore::default(),
+      spell_correct:        false,
+      min_doc_tokens:       0,
+      high_precision:       false,
+      federated_normalize:  false,
+      ranking:
+        Ranking::default(),
+      bm25_k1:
+        default_bm25_k1(),
+      bm25_b:
+        default_bm25_b(),
+      max_hit_vector_terms:
+        default_max_hit_vector_terms(),
+      negative_weight:
+        default_negative_weight(),
+      cluster_similarity:
+        default_cluster_similarity()
+    }
+  }
+}
+
+fn default_bm25_k1() -> f32 {
+  BM25_K1
+}
+
+fn default_bm25_b() -> f32 {
+  BM25_B
+}
+
+fn default_max_hit_vector_terms()
+-> usize {
+  256
+}
+
+fn default_negative_weight() -> f32 {
+  0.5
+}
+
+fn default_cluster_similarity() -> f32 {
+  0.9
+}
+
+fn default_top_k() -> usize {
+  5
+}
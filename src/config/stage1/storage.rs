@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use serde::Deserialize;
+
+use crate::config::default_false;
+use crate::storage::storage_path;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage1Storage {
+  #[serde(
+    default = "default_state_file"
+  )]
+  pub state_file:      String,
+  #[serde(
+    default = "default_chunks_file"
+  )]
+  pub chunks_file:     String,
+  #[serde(
+    default = "default_artifact_dir"
+  )]
+  pub artifact_dir:    String,
+  #[serde(default = "default_false")]
+  pub compress:        bool,
+  #[serde(default = "default_false")]
+  pub keep_normalized: bool,
+  #[serde(default)]
+  pub path_mode:       PathMode,
+  /// Root that `relative-to` paths are
+  /// stored relative to
+  #[serde(
+    default = "default_path_root"
+  )]
+  pub path_root:       String
+}
+
+/// How [`Document`] paths are stored.
+///
+/// [`Document`]: crate::state::Document
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Default,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathMode {
+  /// Canonical absolute paths
+  #[default]
+  Absolute,
+  /// Paths relative to `path_root`, so
+  /// the state stays valid when the
+  /// corpus moves; files outside the
+  /// root keep absolute paths
+  RelativeTo
+}
+
+impl Stage1Storage {
+  /// The form of the canonical
+  /// `absolute` path to store under
+  /// `path_mode`.
+  pub fn stored_path(
+    &self,
+    absolute: &str
+  ) -> String {
+    if self.path_mode
+      == PathMode::Absolute
+    {
+      return absolute.to_string();
+    }
+    fs::canonicalize(&self.path_root)
+      .ok()
+      .and_then(|root| {
+        Path::new(absolute)
+          .strip_prefix(root)
+          .ok()
+          .map(|relative| {
+            relative
+              .to_string_lossy()
+              .into_owned()
+          })
+      })
+      .unwrap_or_else(|| {
+        absolute.to_string()
+      })
+  }
+
+  /// Rebuilds a usable path from a
+  /// stored one: relative paths resolve
+  /// against `path_root`.
+  pub fn resolve_path(
+    &self,
+    stored: &str
+  ) -> PathBuf {
+    let path = Path::new(stored);
+    if path.is_absolute() {
+      path.to_path_buf()
+    } else {
+      Path::new(&self.path_root)
+        .join(path)
+    }
+  }
+
+  /// Where the state is written:
+  /// `state_file`, plus `.gz` when
+  /// `compress` is set.
+  pub fn state_path(&self) -> PathBuf {
+    storage_path(
+      &self.state_file,
+      self.compress
+    )
+  }
+
+  /// Where the chunks JSONL is
+  /// written: `chunks_file`, plus `.gz`
+  /// when `compress` is set.
+  pub fn chunks_path(&self) -> PathBuf {
+    storage_path(
+      &self.chunks_file,
+      self.compress
+    )
+  }
+}
+
+impl Default for Stage1Storage {
+  fn default() -> Self {
+    Self {
+      state_file:
+        default_state_file(),
+      chunks_file:
+        default_chunks_file(),
+      artifact_dir:
+        default_artifact_dir(),
+      compress:        false,
+      keep_normalized: false,
+      path_mode:
+        PathMode::Absolute,
+      path_root:
+        default_path_root()
+    }
+  }
+}
+
+fn default_path_root() -> String {
+  ".".into()
+}
+
+fn default_state_file() -> String {
+  "data/state.json".into()
+}
+
+fn default_chunks_file() -> String {
+  "data/chunks.jsonl".into()
+}
+
+fn default_artifact_dir() -> String {
+  "data".into()
+}
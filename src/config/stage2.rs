@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use super::{
+  EmbedderKind,
+  Ranking,
+  default_false,
+  default_true
+};
+use crate::chunk::ChunkStrategy;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage2Config {
+  #[serde(default = "default_false")]
+  pub enabled:                bool,
+  #[serde(default = "default_true")]
+  pub log_evaluation:         bool,
+  #[serde(default = "default_true")]
+  pub run_baselines:          bool,
+  #[serde(
+    default = "default_stage2_runs_dir"
+  )]
+  pub runs_dir:               String,
+  #[serde(
+    default = "default_stage2_embedder_kinds"
+  )]
+  pub embedder_kinds: Vec<EmbedderKind>,
+  #[serde(default)]
+  pub chunk_strategies:
+    Vec<ChunkStrategy>,
+  #[serde(default)]
+  pub rankings: Vec<Ranking>,
+  #[serde(default = "default_false")]
+  pub parallel_queries:       bool,
+  #[serde(default)]
+  pub threshold_sweep:        Vec<f32>,
+  #[serde(default)]
+  pub sqlite_db: Option<PathBuf>,
+  #[serde(default = "default_false")]
+  pub verbose_queries:        bool,
+  /// Leave queries without expected
+  /// terms out of the relevance
+  /// averages
+  #[serde(default = "default_false")]
+  pub skip_unlabeled_queries: bool,
+  /// Synonyms that also satisfy an
+  /// expected term, keyed by term
+  #[serde(default)]
+  pub synonyms:
+    BTreeMap<String, Vec<String>>,
+  #[serde(default)]
+  pub evaluation: Stage2Evaluation,
+  #[serde(default)]
+  pub tuning: Stage2Tuning
+}
+
+impl Default for Stage2Config {
+  fn default() -> Self {
+    Self {
+      enabled:                false,
+      log_evaluation:         true,
+      run_baselines:          true,
+      runs_dir:
+        default_stage2_runs_dir(),
+      embedder_kinds:
+        default_stage2_embedder_kinds(),
+      chunk_strategies:       Vec::new(
+      ),
+      rankings:               Vec::new(
+      ),
+      parallel_queries:       false,
+      threshold_sweep:        Vec::new(
+      ),
+      sqlite_db:              None,
+      verbose_queries:        false,
+      skip_unlabeled_queries: false,
+      synonyms:
+        BTreeMap::new(),
+      evaluation:
+        Stage2Evaluation::default(),
+      tuning:
+        Stage2Tuning::default()
+    }
+  }
+}
+
+#[derive(
+  Clone, Debug, Default, Deserialize,
+)]
+pub struct Stage2Evaluation {
+  #[serde(default)]
+  pub queries: Vec<EvaluationQuery>
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EvaluationQuery {
+  pub name:           String,
+  pub query:          String,
+  #[serde(default)]
+  pub expected_terms: Vec<String>,
+  #[serde(default)]
+  pub top_k:          Option<usize>
+}
+
+/// The chunking grid `oxbed tune`
+/// searches.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage2Tuning {
+  #[serde(
+    default = "default_tuning_max_tokens"
+  )]
+  pub max_tokens:       Vec<usize>,
+  #[serde(
+    default = "default_tuning_overlap"
+  )]
+  pub overlap:          Vec<usize>,
+  #[serde(
+    default = "default_tuning_strategies"
+  )]
+  pub strategies: Vec<ChunkStrategy>,
+  /// Refuse grids larger than this, as
+  /// every combination rechunks and
+  /// re-embeds the corpus
+  #[serde(
+    default = "default_tuning_max_combinations"
+  )]
+  pub max_combinations: usize
+}
+
+impl Default for Stage2Tuning {
+  fn default() -> Self {
+    Self {
+      max_tokens:
+        default_tuning_max_tokens(),
+      overlap:
+        default_tuning_overlap(),
+      strategies:
+        default_tuning_strategies(),
+      max_combinations:
+        default_tuning_max_combinations(
+        )
+    }
+  }
+}
+
+fn default_tuning_max_tokens()
+-> Vec<usize> {
+  vec![100, 200, 400]
+}
+
+fn default_tuning_overlap() -> Vec<usize>
+{
+  vec![0, 32]
+}
+
+fn default_tuning_strategies()
+-> Vec<ChunkStrategy> {
+  vec![ChunkStrategy::Structured]
+}
+
+fn default_tuning_max_combinations()
+-> usize {
+  24
+}
+
+fn default_stage2_runs_dir() -> String {
+  "runs".into()
+}
+
+fn default_stage2_embedder_kinds()
+-> Vec<EmbedderKind> {
+  vec![
+    EmbedderKind::Tf,
+    EmbedderKind::BagOfWords,
+  ]
+}
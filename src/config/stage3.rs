@@ -0,0 +1,202 @@
+use serde::Deserialize;
+
+use super::default_false;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage3Config {
+  #[serde(default = "default_false")]
+  pub enabled:          bool,
+  #[serde(
+    default = "default_context_budget"
+  )]
+  pub context_budget:   usize,
+  #[serde(
+    default = "default_stage3_prompt_template"
+  )]
+  pub prompt_template:  String,
+  #[serde(default = "default_false")]
+  pub strict_template:  bool,
+  #[serde(default)]
+  pub max_prompt_chars: Option<usize>,
+  #[serde(default)]
+  pub reranker: Stage3RerankerConfig,
+  #[serde(default)]
+  pub timeout_ms:       Option<u64>
+}
+
+impl Default for Stage3Config {
+  fn default() -> Self {
+    Self {
+      enabled:          false,
+      context_budget:
+        default_context_budget(),
+      prompt_template:
+        default_stage3_prompt_template(),
+      strict_template:  false,
+      max_prompt_chars: None,
+      reranker:
+        Stage3RerankerConfig::default(),
+      timeout_ms:       None
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage3RerankerConfig {
+  #[serde(
+    default = "default_stage3_strategies"
+  )]
+  pub strategies:
+    Vec<Stage3RerankerStrategyConfig>,
+  #[serde(
+    default = "default_stage3_ensemble"
+  )]
+  pub ensemble:   Stage3Ensemble,
+  #[serde(
+    default = "default_stage3_rrf_k"
+  )]
+  pub rrf_k:      f32
+}
+
+impl Default for Stage3RerankerConfig {
+  fn default() -> Self {
+    Self {
+      strategies:
+        default_stage3_strategies(),
+      ensemble:
+        default_stage3_ensemble(),
+      rrf_k:      default_stage3_rrf_k(
+      )
+    }
+  }
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Deserialize,
+  PartialEq,
+  Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage3Ensemble {
+  None,
+  WeightedSum,
+  Rrf
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage3RerankerStrategyConfig
+{
+  pub name:              String,
+  #[serde(
+    default = "default_stage3_rerank_mode"
+  )]
+  pub mode: Stage3RerankMode,
+  #[serde(default)]
+  pub boost_terms:       Vec<String>,
+  #[serde(
+    default = "default_stage3_boost_factor"
+  )]
+  pub boost_factor:      f32,
+  #[serde(
+    default = "default_stage3_threshold"
+  )]
+  pub threshold:         f32,
+  #[serde(
+    default = "default_stage3_hybrid_weight"
+  )]
+  pub hybrid_weight:     f32,
+  #[serde(
+    default = "default_stage3_strategy_weight"
+  )]
+  pub weight:            f32,
+  /// Only the top this-many hits by
+  /// base score receive boosts;
+  /// unlimited when unset
+  #[serde(default)]
+  pub boost_rank_cutoff: Option<usize>
+}
+
+impl Default
+  for Stage3RerankerStrategyConfig
+{
+  fn default() -> Self {
+    Self {
+      name:
+        "embedding-only".into(),
+      mode:
+        default_stage3_rerank_mode(),
+      boost_terms:       Vec::new(),
+      boost_factor:
+        default_stage3_boost_factor(),
+      threshold:
+        default_stage3_threshold(),
+      hybrid_weight:
+        default_stage3_hybrid_weight(),
+      weight:
+        default_stage3_strategy_weight(),
+      boost_rank_cutoff: None
+    }
+  }
+}
+
+#[derive(
+  Clone, Copy, Debug, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage3RerankMode {
+  None,
+  TermOverlap,
+  Hybrid
+}
+
+fn default_stage3_prompt_template()
+-> String {
+  "Question: {query}\nContext:\\
+   n{context}\nAnswer:"
+    .into()
+}
+
+fn default_stage3_strategies()
+-> Vec<Stage3RerankerStrategyConfig> {
+  vec![Stage3RerankerStrategyConfig::default()]
+}
+
+fn default_stage3_ensemble()
+-> Stage3Ensemble {
+  Stage3Ensemble::None
+}
+
+fn default_stage3_rrf_k() -> f32 {
+  60.0
+}
+
+fn default_stage3_strategy_weight()
+-> f32 {
+  1.0
+}
+
+fn default_stage3_rerank_mode()
+-> Stage3RerankMode {
+  Stage3RerankMode::None
+}
+
+fn default_stage3_boost_factor() -> f32
+{
+  1.0
+}
+
+fn default_stage3_threshold() -> f32 {
+  0.0
+}
+
+fn default_stage3_hybrid_weight() -> f32
+{
+  0.5
+}
+
+fn default_context_budget() -> usize {
+  1024
+}
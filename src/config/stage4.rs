@@ -0,0 +1,80 @@
+use serde::Deserialize;
+
+use super::default_false;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage4Config {
+  #[serde(default = "default_false")]
+  pub enabled:    bool,
+  #[serde(
+    default = "default_stage4_models_dir"
+  )]
+  pub models_dir: String,
+  #[serde(default)]
+  pub training:   Stage4TrainingConfig
+}
+
+impl Default for Stage4Config {
+  fn default() -> Self {
+    Self {
+      enabled:    false,
+      models_dir:
+        default_stage4_models_dir(),
+      training:
+        Stage4TrainingConfig::default()
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stage4TrainingConfig {
+  #[serde(
+    default = "default_stage4_context_budget"
+  )]
+  pub context_budget: usize,
+  #[serde(
+    default = "default_stage4_sample_limit"
+  )]
+  pub sample_limit:   usize,
+  #[serde(
+    default = "default_stage4_weight_tag"
+  )]
+  pub weight_tag:     String
+}
+
+impl Default for Stage4TrainingConfig {
+  fn default() -> Self {
+    Self {
+      context_budget:
+        default_stage4_context_budget(),
+      sample_limit:
+        default_stage4_sample_limit(),
+      weight_tag:
+        default_stage4_weight_tag()
+    }
+  }
+}
+
+fn default_stage4_models_dir() -> String
+{
+  "models".into()
+}
+
+fn default_stage4_context_budget()
+-> usize {
+  512
+}
+
+fn default_stage4_sample_limit() -> usize
+{
+  10_000
+}
+
+fn default_stage4_weight_tag() -> String
+{
+  "train_weight".into()
+}
+
+fn default_checkpoint_dir() -> String {
+  "models/".into()
+}
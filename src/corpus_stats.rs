@@ -0,0 +1,156 @@
+use std::collections::{
+  BTreeMap,
+  BTreeSet
+};
+
+use serde::{
+  Deserialize,
+  Serialize
+};
+
+use crate::chunk::Chunk;
+use crate::state::Document;
+use crate::tokenizer::Tokenizer;
+
+/// Document frequencies and length
+/// totals over the corpus, maintained
+/// as documents are added and removed.
+/// A document's terms are the tokens
+/// of its stored chunks.
+#[derive(
+  Clone,
+  Debug,
+  Default,
+  PartialEq,
+  Serialize,
+  Deserialize,
+)]
+pub struct CorpusStats {
+  pub documents:          usize,
+  pub total_tokens:       usize,
+  pub document_frequency:
+    BTreeMap<String, usize>
+}
+
+impl CorpusStats {
+  /// Builds the stats from scratch.
+  pub fn recompute(
+    documents: &[Document],
+    chunks: &[Chunk],
+    tokenizer: &Tokenizer
+  ) -> Self {
+    let mut stats = Self::default();
+    for document in documents {
+      stats.add_document(
+        document, chunks, tokenizer
+      );
+    }
+    stats
+  }
+
+  /// Counts `document` and the terms of
+  /// its chunks among `chunks`.
+  pub fn add_document(
+    &mut self,
+    document: &Document,
+    chunks: &[Chunk],
+    tokenizer: &Tokenizer
+  ) {
+    self.add_document_where(
+      document,
+      chunks,
+      tokenizer,
+      |_| true
+    );
+  }
+
+  /// Like [`Self::add_document`], but
+  /// only counts the terms `keep`
+  /// accepts.
+  pub fn add_document_where(
+    &mut self,
+    document: &Document,
+    chunks: &[Chunk],
+    tokenizer: &Tokenizer,
+    keep: impl Fn(&str) -> bool
+  ) {
+    self.documents += 1;
+    self.total_tokens +=
+      document.token_count;
+    for term in document_terms(
+      &document.id,
+      chunks,
+      tokenizer
+    )
+    .into_iter()
+    .filter(|term| keep(term))
+    {
+      *self
+        .document_frequency
+        .entry(term)
+        .or_insert(0) += 1;
+    }
+  }
+
+  /// Reverses [`Self::add_document`];
+  /// call before the document's chunks
+  /// are dropped.
+  pub fn remove_document(
+    &mut self,
+    document: &Document,
+    chunks: &[Chunk],
+    tokenizer: &Tokenizer
+  ) {
+    self.documents =
+      self.documents.saturating_sub(1);
+    self.total_tokens =
+      self.total_tokens.saturating_sub(
+        document.token_count
+      );
+    for term in document_terms(
+      &document.id,
+      chunks,
+      tokenizer
+    ) {
+      if let Some(df) = self
+        .document_frequency
+        .get_mut(&term)
+      {
+        *df -= 1;
+        if *df == 0 {
+          self
+            .document_frequency
+            .remove(&term);
+        }
+      }
+    }
+  }
+
+  pub fn avg_length(&self) -> f32 {
+    if self.documents == 0 {
+      return 0.0;
+    }
+    self.total_tokens as f32
+      / self.documents as f32
+  }
+}
+
+fn document_terms(
+  doc_id: &str,
+  chunks: &[Chunk],
+  tokenizer: &Tokenizer
+) -> BTreeSet<String> {
+  chunks
+    .iter()
+    .filter(|chunk| {
+      chunk.doc_id == doc_id
+    })
+    .flat_map(|chunk| {
+      tokenizer
+        .for_language(
+          chunk.language.as_deref()
+        )
+        .tokenize(&chunk.text)
+    })
+    .collect()
+}
@@ -0,0 +1,63 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+  Embedder,
+  SparseVector,
+  count_tokens,
+  counts_vector,
+  normalize_counts
+};
+use crate::config::TfScheme;
+use crate::tokenizer::Tokenizer;
+
+#[derive(Clone, Default)]
+pub struct BagOfWordsEmbedder {
+  pub(super) tokenizer: Tokenizer,
+  pub(super) tf_scheme: TfScheme
+}
+
+impl Embedder for BagOfWordsEmbedder {
+  fn name(&self) -> String {
+    "bag-of-words".into()
+  }
+
+  fn embed(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    normalize_counts(
+      count_tokens(
+        self.tokenizer.tokenize(text)
+      ),
+      self.tf_scheme
+    )
+  }
+
+  fn term_counts(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    counts_vector(count_tokens(
+      self.tokenizer.tokenize(text)
+    ))
+  }
+
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize {
+    text.unicode_words().count()
+  }
+
+  fn for_language(
+    &self,
+    language: &str
+  ) -> Option<Box<dyn Embedder>> {
+    Some(Box::new(Self {
+      tokenizer: self
+        .tokenizer
+        .for_language(Some(language)),
+      ..self.clone()
+    }))
+  }
+}
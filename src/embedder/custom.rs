@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use anyhow::{
+  Context,
+  Result
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+  Embedder,
+  SparseVector,
+  count_tokens,
+  counts_vector
+};
+use crate::stage4::ModelManifest;
+use crate::tokenizer::Tokenizer;
+
+pub struct CustomEmbedder {
+  weights:   HashMap<String, f32>,
+  name:      String,
+  version:   String,
+  tokenizer: Tokenizer
+}
+
+impl CustomEmbedder {
+  pub fn load(
+    models_dir: &Path,
+    name: &str,
+    version: Option<&str>
+  ) -> Result<Self> {
+    let base = models_dir.join(name);
+    let target =
+      if let Some(ver) = version {
+        base.join(ver)
+      } else {
+        find_latest_model(&base)?
+      };
+    let manifest_path =
+      target.join("manifest.json");
+    let manifest: ModelManifest =
+      serde_json::from_reader(
+        BufReader::new(
+          File::open(&manifest_path)
+            .context("open manifest")?
+        )
+      )
+      .context("parse manifest")?;
+    Ok(Self {
+      weights:   manifest
+        .token_weights
+        .clone(),
+      name:      manifest.name,
+      version:   manifest.version,
+      tokenizer: Tokenizer::default()
+    })
+  }
+
+  pub fn with_tokenizer(
+    mut self,
+    tokenizer: Tokenizer
+  ) -> Self {
+    self.tokenizer = tokenizer;
+    self
+  }
+}
+
+impl Embedder for CustomEmbedder {
+  fn name(&self) -> String {
+    format!(
+      "custom:{}:{}",
+      self.name, self.version
+    )
+  }
+
+  fn embed(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    let mut vector =
+      SparseVector::new();
+    for token in
+      self.tokenizer.tokenize(text)
+    {
+      if let Some(weight) =
+        self.weights.get(&token)
+      {
+        vector.insert(token, *weight);
+      }
+    }
+    vector
+  }
+
+  fn term_counts(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    counts_vector(count_tokens(
+      self
+        .tokenizer
+        .tokenize(text)
+        .into_iter()
+        .filter(|token| {
+          self
+            .weights
+            .contains_key(token)
+        })
+    ))
+  }
+
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize {
+    text.unicode_words().count()
+  }
+}
+
+fn find_latest_model(
+  base: &Path
+) -> Result<PathBuf> {
+  let mut candidates: Vec<_> =
+    std::fs::read_dir(base)?
+      .filter_map(Result::ok)
+      .filter(|entry| {
+        entry.path().is_dir()
+      })
+      .collect();
+  candidates
+    .sort_by_key(|entry| entry.path());
+  let entry = candidates
+    .into_iter()
+    .last()
+    .context(
+      "no custom models found"
+    )?;
+  Ok(entry.path())
+}
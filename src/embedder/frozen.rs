@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::{
+  Embedder,
+  SparseVector
+};
+
+/// Wraps another embedder and drops
+/// every term outside a fixed
+/// vocabulary from its vectors.
+pub struct FrozenVocabEmbedder {
+  inner:      Box<dyn Embedder>,
+  vocabulary: Arc<HashSet<String>>
+}
+
+impl FrozenVocabEmbedder {
+  pub fn new(
+    inner: Box<dyn Embedder>,
+    vocabulary: HashSet<String>
+  ) -> Self {
+    Self {
+      inner,
+      vocabulary: Arc::new(vocabulary)
+    }
+  }
+
+  fn freeze(
+    &self,
+    mut vector: SparseVector
+  ) -> SparseVector {
+    vector.retain(|term, _| {
+      self.vocabulary.contains(term)
+    });
+    vector
+  }
+}
+
+impl Embedder for FrozenVocabEmbedder {
+  fn name(&self) -> String {
+    self.inner.name()
+  }
+
+  fn fingerprint(&self) -> String {
+    self.inner.fingerprint()
+  }
+
+  fn embed(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self.freeze(self.inner.embed(text))
+  }
+
+  fn embed_query(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self.freeze(
+      self.inner.embed_query(text)
+    )
+  }
+
+  fn embed_document(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self.freeze(
+      self.inner.embed_document(text)
+    )
+  }
+
+  fn embed_batch(
+    &self,
+    texts: &[&str]
+  ) -> Vec<SparseVector> {
+    self
+      .inner
+      .embed_batch(texts)
+      .into_iter()
+      .map(|vector| self.freeze(vector))
+      .collect()
+  }
+
+  fn term_counts(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self.freeze(
+      self.inner.term_counts(text)
+    )
+  }
+
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize {
+    self.inner.token_count(text)
+  }
+
+  fn for_language(
+    &self,
+    language: &str
+  ) -> Option<Box<dyn Embedder>> {
+    let inner = self
+      .inner
+      .for_language(language)?;
+    Some(Box::new(Self {
+      inner,
+      vocabulary: self
+        .vocabulary
+        .clone()
+    }))
+  }
+}
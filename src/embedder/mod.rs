@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{
+  Context,
+  Result
+};
+
+pub use self::bag_of_words::BagOfWordsEmbedder;
+pub use self::custom::CustomEmbedder;
+pub use self::frozen::FrozenVocabEmbedder;
+pub use self::tf::TfEmbedder;
+pub use self::tfidf::TfIdfEmbedder;
+use crate::config::{
+  EmbedderKind,
+  TfScheme
+};
+use crate::corpus_stats::CorpusStats;
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+mod bag_of_words;
+mod custom;
+mod frozen;
+mod tf;
+mod tfidf;
+
+pub type SparseVector =
+  HashMap<String, f32>;
+
+pub trait Embedder:
+  Send + Sync
+{
+  fn name(&self) -> String;
+  /// Identifies everything besides the
+  /// text and the embedder settings
+  /// that shapes the vectors, such as
+  /// corpus statistics, so reindexing
+  /// re-embeds chunks when it changes.
+  /// Defaults to `name`.
+  fn fingerprint(&self) -> String {
+    self.name()
+  }
+  fn embed(
+    &self,
+    text: &str
+  ) -> SparseVector;
+  /// Embeds a search query. Override
+  /// together with `embed_document`
+  /// for schemes that weight query and
+  /// document terms differently.
+  fn embed_query(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self.embed(text)
+  }
+  /// Embeds indexed text (chunks and
+  /// file names).
+  fn embed_document(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self.embed(text)
+  }
+  /// Embeds several documents at once.
+  /// Model-backed embedders can
+  /// override this to amortize
+  /// per-call setup; the default calls
+  /// `embed_document` on each text.
+  fn embed_batch(
+    &self,
+    texts: &[&str]
+  ) -> Vec<SparseVector> {
+    texts
+      .iter()
+      .map(|text| {
+        self.embed_document(text)
+      })
+      .collect()
+  }
+  /// The raw count of each term the
+  /// embedder keeps from `text`, for
+  /// scoring schemes such as BM25 that
+  /// need frequencies rather than
+  /// weights. Defaults to counting
+  /// each term of `embed_document`
+  /// once.
+  fn term_counts(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self
+      .embed_document(text)
+      .into_keys()
+      .map(|term| (term, 1.0))
+      .collect()
+  }
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize;
+  /// A copy that tokenizes text in
+  /// `language` (an ISO 639-3 code)
+  /// with that language's stopwords
+  /// and stemming, or `None` when the
+  /// embedder does not route by
+  /// language.
+  fn for_language(
+    &self,
+    _language: &str
+  ) -> Option<Box<dyn Embedder>> {
+    None
+  }
+}
+
+/// Builds `stage1.embedder.kind`,
+/// switching to
+/// `stage1.embedder.fallback` with a
+/// warning when that fails.
+pub fn build_configured_embedder(
+  config: &crate::config::Config
+) -> Result<Box<dyn Embedder>> {
+  let embedder_cfg =
+    &config.stage1.embedder;
+  let err = match build_embedder(
+    embedder_cfg.kind.clone(),
+    config
+  ) {
+    | Ok(embedder) => {
+      return Ok(embedder)
+    }
+    | Err(err) => err
+  };
+  let Some(fallback) =
+    embedder_cfg.fallback.clone()
+  else {
+    return Err(err);
+  };
+  let embedder =
+    build_embedder(fallback, config)
+      .context(
+        "build fallback embedder"
+      )?;
+  eprintln!(
+    "Warning: could not build \
+     embedder {:?} ({:#}); falling \
+     back to {}",
+    embedder_cfg.kind,
+    err,
+    embedder.name()
+  );
+  Ok(embedder)
+}
+
+pub fn build_embedder(
+  kind: EmbedderKind,
+  config: &crate::config::Config
+) -> Result<Box<dyn Embedder>> {
+  let tokenizer =
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    );
+  let tf_scheme =
+    config.stage1.embedder.tf_scheme;
+  let tf = |tokenizer| {
+    TfEmbedder::new(
+      config
+        .stage1
+        .embedder
+        .tfidf_min_freq
+    )
+    .with_tokenizer(tokenizer)
+    .with_tf_scheme(tf_scheme)
+  };
+  match kind {
+    | EmbedderKind::Tf => {
+      Ok(Box::new(tf(tokenizer)))
+    }
+    | EmbedderKind::TfIdf => {
+      let state = State::load_from(
+        config
+          .stage1
+          .storage
+          .state_path()
+      )?;
+      let stats =
+        if state.stats.documents
+          == state.documents.len()
+        {
+          state.stats
+        } else {
+          CorpusStats::recompute(
+            &state.documents,
+            &state.chunks,
+            &tokenizer
+          )
+        };
+      Ok(Box::new(TfIdfEmbedder::new(
+        tf(tokenizer),
+        &stats,
+        config
+          .stage1
+          .embedder
+          .idf_smoothing
+      )))
+    }
+    | EmbedderKind::BagOfWords => {
+      Ok(Box::new(BagOfWordsEmbedder {
+        tokenizer,
+        tf_scheme
+      }))
+    }
+    | EmbedderKind::Custom {
+      name,
+      version
+    } => {
+      let dir = Path::new(
+        &config.stage4.models_dir
+      );
+      Ok(Box::new(
+        CustomEmbedder::load(
+          dir,
+          &name,
+          version.as_deref()
+        )?
+        .with_tokenizer(tokenizer)
+      ))
+    }
+  }
+}
+
+fn count_tokens(
+  tokens: impl IntoIterator<Item = String>
+) -> HashMap<String, usize> {
+  let mut counts = HashMap::new();
+  for token in tokens {
+    *counts
+      .entry(token)
+      .or_insert(0) += 1;
+  }
+  counts
+}
+
+fn counts_vector(
+  counts: HashMap<String, usize>
+) -> SparseVector {
+  counts
+    .into_iter()
+    .map(|(token, count)| {
+      (token, count as f32)
+    })
+    .collect()
+}
+
+/// Weights each count with `scheme`
+/// and scales the weights to sum to 1.
+fn normalize_counts(
+  counts: HashMap<String, usize>,
+  scheme: TfScheme
+) -> SparseVector {
+  let max_tf = counts
+    .values()
+    .copied()
+    .max()
+    .unwrap_or(0);
+  let mut vector: SparseVector = counts
+    .into_iter()
+    .map(|(token, count)| {
+      (
+        token,
+        scheme.weight(count, max_tf)
+      )
+    })
+    .collect();
+  let total: f32 =
+    vector.values().sum();
+  if total == 0.0 {
+    return SparseVector::new();
+  }
+  for weight in vector.values_mut() {
+    *weight /= total;
+  }
+  vector
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use super::*;
+use crate::config::{
+  Config,
+  Stemmer
+};
+
+#[test]
+fn embed_batch_matches_single_embeds() {
+  let config = Config::default();
+  let texts = [
+    "whale ship whale",
+    "",
+    "Call me Ishmael."
+  ];
+  let builtins: Vec<Box<dyn Embedder>> = vec![
+    build_embedder(
+      EmbedderKind::Tf,
+      &config
+    )
+    .unwrap(),
+    build_embedder(
+      EmbedderKind::BagOfWords,
+      &config
+    )
+    .unwrap(),
+    Box::new(FrozenVocabEmbedder::new(
+      Box::new(TfEmbedder::new(1)),
+      HashSet::from(["whale".into()])
+    )),
+  ];
+  for embedder in builtins {
+    let single: Vec<SparseVector> =
+      texts
+        .iter()
+        .map(|text| {
+          embedder.embed(text)
+        })
+        .collect();
+    assert_eq!(
+      embedder.embed_batch(&texts),
+      single
+    );
+  }
+}
+
+#[test]
+fn number_bucketing_ignores_specific_numbers()
+ {
+  let mut config = Config::default();
+  config
+    .stage1
+    .embedder
+    .number_bucketing = true;
+  let embedder = build_embedder(
+    EmbedderKind::Tf,
+    &config
+  )
+  .unwrap();
+  assert_eq!(
+    embedder.embed("version 3 ships"),
+    embedder.embed("version 7 ships")
+  );
+  let plain = build_embedder(
+    EmbedderKind::Tf,
+    &Config::default()
+  )
+  .unwrap();
+  assert_ne!(
+    plain.embed("version 3 ships"),
+    plain.embed("version 7 ships")
+  );
+}
+
+#[test]
+fn lemmatized_queries_match_base_forms()
+{
+  let mut config = Config::default();
+  config.stage1.embedder.stemmer =
+    Stemmer::Lemmatize;
+  let embedder = build_embedder(
+    EmbedderKind::Tf,
+    &config
+  )
+  .unwrap();
+  assert_eq!(
+    embedder.embed_query("Mice"),
+    embedder.embed("mouse")
+  );
+  assert_eq!(
+    embedder.embed("better whales"),
+    embedder.embed("good whale")
+  );
+}
+
+#[test]
+fn case_is_kept_when_lowercasing_is_off()
+ {
+  let mut config = Config::default();
+  let folded = build_embedder(
+    EmbedderKind::Tf,
+    &config
+  )
+  .unwrap();
+  assert_eq!(
+    folded.embed("FooBar"),
+    folded.embed("foobar")
+  );
+  config
+    .stage1
+    .embedder
+    .lowercase_tokens = false;
+  let exact = build_embedder(
+    EmbedderKind::Tf,
+    &config
+  )
+  .unwrap();
+  let vector = exact.embed("FooBar");
+  assert!(
+    vector.contains_key("FooBar")
+  );
+  assert_ne!(
+    vector,
+    exact.embed("foobar")
+  );
+}
+
+#[test]
+fn tf_schemes_weight_repeated_terms() {
+  let ratio = |scheme| {
+    let vector = TfEmbedder::new(1)
+      .with_tf_scheme(scheme)
+      .embed(
+        "whale whale whale whale ship"
+      );
+    vector["whale"] / vector["ship"]
+  };
+  let expected = [
+    (TfScheme::Raw, 4.0),
+    (TfScheme::Log, 1.0 + 4f32.ln()),
+    (TfScheme::Binary, 1.0),
+    (TfScheme::Augmented, 1.0 / 0.625)
+  ];
+  for (scheme, want) in expected {
+    let got = ratio(scheme);
+    assert!(
+      (got - want).abs() < 1e-5,
+      "{:?}: {} != {}",
+      scheme,
+      got,
+      want
+    );
+  }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+  Embedder,
+  SparseVector,
+  count_tokens,
+  counts_vector,
+  normalize_counts
+};
+use crate::config::TfScheme;
+use crate::tokenizer::Tokenizer;
+
+#[derive(Clone)]
+pub struct TfEmbedder {
+  min_freq:             usize,
+  pub(super) tokenizer: Tokenizer,
+  tf_scheme:            TfScheme
+}
+
+impl TfEmbedder {
+  pub fn new(min_freq: usize) -> Self {
+    Self {
+      min_freq:  min_freq.max(1),
+      tokenizer: Tokenizer::default(),
+      tf_scheme: TfScheme::Raw
+    }
+  }
+
+  pub fn with_tf_scheme(
+    mut self,
+    tf_scheme: TfScheme
+  ) -> Self {
+    self.tf_scheme = tf_scheme;
+    self
+  }
+
+  pub fn with_tokenizer(
+    mut self,
+    tokenizer: Tokenizer
+  ) -> Self {
+    self.tokenizer = tokenizer;
+    self
+  }
+
+  pub fn token_count(
+    text: &str
+  ) -> usize {
+    text.unicode_words().count()
+  }
+
+  /// Token counts of `text`, without
+  /// tokens seen fewer than `min_freq`
+  /// times.
+  fn counts(
+    &self,
+    text: &str
+  ) -> HashMap<String, usize> {
+    let mut counts = count_tokens(
+      self.tokenizer.tokenize(text)
+    );
+    counts.retain(|_, &mut count| {
+      count >= self.min_freq
+    });
+    counts
+  }
+}
+
+impl Embedder for TfEmbedder {
+  fn name(&self) -> String {
+    "tf".into()
+  }
+
+  fn embed(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    normalize_counts(
+      self.counts(text),
+      self.tf_scheme
+    )
+  }
+
+  fn term_counts(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    counts_vector(self.counts(text))
+  }
+
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize {
+    Self::token_count(text)
+  }
+
+  fn for_language(
+    &self,
+    language: &str
+  ) -> Option<Box<dyn Embedder>> {
+    Some(Box::new(
+      self.clone().with_tokenizer(
+        self
+          .tokenizer
+          .for_language(Some(language))
+      )
+    ))
+  }
+}
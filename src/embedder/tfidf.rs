@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use sha2::{
+  Digest,
+  Sha256
+};
+
+use super::{
+  Embedder,
+  SparseVector,
+  TfEmbedder
+};
+use crate::config::IdfSmoothing;
+use crate::corpus_stats::CorpusStats;
+
+/// Term frequencies weighted by each
+/// term's inverse document frequency in
+/// the corpus, so terms found in every
+/// document count for less than rare
+/// ones. The IDF table is fixed when
+/// the embedder is built; vectors
+/// stored before the corpus changed
+/// keep their old weights until
+/// `oxbed reindex`, which sees the new
+/// table through the fingerprint.
+#[derive(Clone)]
+pub struct TfIdfEmbedder {
+  tf:          TfEmbedder,
+  /// `None` for an empty corpus, which
+  /// leaves plain TF vectors
+  idf:
+    Option<Arc<HashMap<String, f32>>>,
+  /// IDF of terms no document contains
+  unseen:      f32,
+  /// Hash of the statistics the IDF
+  /// table was built from
+  fingerprint: String
+}
+
+impl TfIdfEmbedder {
+  pub fn new(
+    tf: TfEmbedder,
+    stats: &CorpusStats,
+    smoothing: IdfSmoothing
+  ) -> Self {
+    let idf = (stats.documents > 0)
+      .then(|| {
+        stats
+          .document_frequency
+          .iter()
+          .map(|(term, &df)| {
+            (
+              term.clone(),
+              smoothing.idf(
+                stats.documents,
+                df
+              )
+            )
+          })
+          .collect()
+      })
+      .map(Arc::new);
+    let mut hasher = Sha256::new();
+    hasher.update(
+      stats.documents.to_le_bytes()
+    );
+    for (term, df) in
+      &stats.document_frequency
+    {
+      hasher.update(term.as_bytes());
+      hasher.update(df.to_le_bytes());
+    }
+    Self {
+      tf,
+      idf,
+      unseen: smoothing
+        .idf(stats.documents, 0),
+      fingerprint: format!(
+        "tfidf|{:x}",
+        hasher.finalize()
+      )
+    }
+  }
+}
+
+impl Embedder for TfIdfEmbedder {
+  fn name(&self) -> String {
+    "tfidf".into()
+  }
+
+  fn fingerprint(&self) -> String {
+    self.fingerprint.clone()
+  }
+
+  fn embed(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    let mut vector =
+      self.tf.embed(text);
+    let Some(idf) = &self.idf else {
+      return vector;
+    };
+    for (term, weight) in &mut vector {
+      *weight *= idf
+        .get(term)
+        .copied()
+        .unwrap_or(self.unseen);
+    }
+    vector.retain(|_, weight| {
+      *weight > 0.0
+    });
+    let total: f32 =
+      vector.values().sum();
+    for weight in vector.values_mut() {
+      *weight /= total;
+    }
+    vector
+  }
+
+  fn term_counts(
+    &self,
+    text: &str
+  ) -> SparseVector {
+    self.tf.term_counts(text)
+  }
+
+  fn for_language(
+    &self,
+    language: &str
+  ) -> Option<Box<dyn Embedder>> {
+    Some(Box::new(Self {
+      tf: self
+        .tf
+        .clone()
+        .with_tokenizer(
+          self
+            .tf
+            .tokenizer
+            .for_language(Some(
+              language
+            ))
+        ),
+      ..self.clone()
+    }))
+  }
+
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize {
+    TfEmbedder::token_count(text)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::*;
+
+  #[test]
+  fn rare_terms_outweigh_common_ones() {
+    let stats = CorpusStats {
+      documents:          4,
+      total_tokens:       8,
+      document_frequency:
+        BTreeMap::from([
+          ("whale".to_string(), 4),
+          ("ship".to_string(), 2),
+          ("ahab".to_string(), 1)
+        ])
+    };
+    let embedder = TfIdfEmbedder::new(
+      TfEmbedder::new(1),
+      &stats,
+      IdfSmoothing::None
+    );
+    let vector =
+      embedder.embed("whale ship ahab");
+    assert!(
+      !vector.contains_key("whale")
+    );
+    assert!(
+      vector["ahab"] > vector["ship"]
+    );
+    assert!(
+      (vector.values().sum::<f32>()
+        - 1.0)
+        .abs()
+        < 1e-6
+    );
+    let empty = TfIdfEmbedder::new(
+      TfEmbedder::new(1),
+      &CorpusStats::default(),
+      IdfSmoothing::None
+    );
+    assert_eq!(
+      empty.embed("whale ship"),
+      TfEmbedder::new(1)
+        .embed("whale ship")
+    );
+  }
+}
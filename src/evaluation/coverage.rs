@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use super::metrics::QueryReport;
+use crate::state::{
+  Document,
+  State
+};
+
+/// Which stored documents any
+/// evaluation query retrieved, by path
+/// in ingest order.
+pub(super) struct Coverage {
+  pub(super) retrieved: Vec<String>,
+  pub(super) never_retrieved:
+    Vec<String>
+}
+
+/// Splits the documents in `state` by
+/// whether they appear among the hits
+/// of any of `reports`.
+pub(super) fn coverage(
+  state: &State,
+  reports: &[QueryReport]
+) -> Coverage {
+  let hit_ids: HashSet<&str> = reports
+    .iter()
+    .flat_map(|report| {
+      report.doc_ids.iter()
+    })
+    .map(String::as_str)
+    .collect();
+  let (retrieved, never_retrieved): (
+    Vec<_>,
+    Vec<_>
+  ) = state.documents.iter().partition(
+    |doc| {
+      hit_ids.contains(doc.id.as_str())
+    }
+  );
+  let paths = |docs: Vec<&Document>| {
+    docs
+      .into_iter()
+      .map(|doc| doc.path.clone())
+      .collect()
+  };
+  Coverage {
+    retrieved:       paths(retrieved),
+    never_retrieved: paths(
+      never_retrieved
+    )
+  }
+}
+
+/// The console report: a retrieved
+/// count, then every never-retrieved
+/// document.
+pub(super) fn coverage_lines(
+  label: &str,
+  coverage: &Coverage
+) -> Vec<String> {
+  let total = coverage.retrieved.len()
+    + coverage.never_retrieved.len();
+  let mut lines = vec![format!(
+    "Coverage {} → {} of {} documents \
+     retrieved",
+    label,
+    coverage.retrieved.len(),
+    total
+  )];
+  lines.extend(
+    coverage
+      .never_retrieved
+      .iter()
+      .map(|path| {
+        format!(
+          "  never retrieved: {}",
+          path
+        )
+      })
+  );
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn document(id: &str) -> Document {
+    Document {
+      id:          id.into(),
+      path:        format!(
+        "{}.txt",
+        id
+      ),
+      hash:        id.into(),
+      token_count: 0,
+      tags:        Default::default(),
+      aliases:     Vec::new(),
+      normalized:  None
+    }
+  }
+
+  fn report(
+    doc_ids: &[&str]
+  ) -> QueryReport {
+    QueryReport {
+      name:       "query".into(),
+      top_k:      2,
+      recall:     0.0,
+      precision:  0.0,
+      mrr:        0.0,
+      ndcg:       0.0,
+      hits:       doc_ids.len(),
+      expected:   0,
+      latency_ms: 0.0,
+      doc_ids:    doc_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect()
+    }
+  }
+
+  #[test]
+  fn unretrieved_documents_are_listed()
+  {
+    let state = State {
+      documents: vec![
+        document("a"),
+        document("b"),
+        document("c"),
+      ],
+      ..State::default()
+    };
+    let coverage = coverage(&state, &[
+      report(&["c"]),
+      report(&["a", "c"])
+    ]);
+    assert_eq!(
+      coverage.retrieved,
+      vec!["a.txt", "c.txt"]
+    );
+    assert_eq!(
+      coverage.never_retrieved,
+      vec!["b.txt"]
+    );
+    assert_eq!(
+      coverage_lines("tf", &coverage),
+      vec![
+        "Coverage tf → 2 of 3 \
+         documents retrieved",
+        "  never retrieved: b.txt"
+      ]
+    );
+  }
+}
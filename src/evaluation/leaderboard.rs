@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use anyhow::Result;
+use serde::{
+  Deserialize,
+  Serialize
+};
+use walkdir::WalkDir;
+
+use super::metrics::AggregatedMetrics;
+use crate::args::{
+  LeaderboardMetric,
+  OutputFormat
+};
+use crate::chunk::ChunkStrategy;
+use crate::config::Config;
+
+/// The parts of a persisted evaluation
+/// run a leaderboard shows.
+#[derive(
+  Clone, Deserialize, Serialize,
+)]
+pub(super) struct RankedRun {
+  timestamp:      String,
+  embedder:       String,
+  #[serde(default)]
+  chunk_strategy: Option<ChunkStrategy>,
+  metrics:        AggregatedMetrics,
+  #[serde(skip_deserializing)]
+  path:           PathBuf
+}
+
+impl RankedRun {
+  fn metric(
+    &self,
+    metric: LeaderboardMetric
+  ) -> f32 {
+    match metric {
+      | LeaderboardMetric::Recall => {
+        self.metrics.recall
+      }
+      | LeaderboardMetric::Mrr => {
+        self.metrics.mrr
+      }
+      | LeaderboardMetric::Ndcg => {
+        self.metrics.ndcg
+      }
+      | LeaderboardMetric::Latency => {
+        self.metrics.avg_latency_ms
+      }
+    }
+  }
+}
+
+/// Prints the `top` runs under
+/// `stage2.runs_dir` ranked by
+/// `metric`.
+pub fn print_leaderboard(
+  config: &Config,
+  metric: LeaderboardMetric,
+  top: usize,
+  format: OutputFormat
+) -> Result<()> {
+  let runs = leaderboard(
+    Path::new(&config.stage2.runs_dir),
+    metric,
+    top
+  )?;
+  match format {
+    | OutputFormat::Json => {
+      println!(
+        "{}",
+        serde_json::to_string_pretty(
+          &runs
+        )?
+      );
+    }
+    | OutputFormat::Text
+    | OutputFormat::Markdown => {
+      if runs.is_empty() {
+        println!(
+          "No evaluation runs found \
+           in {}.",
+          config.stage2.runs_dir
+        );
+      }
+      let markdown = format
+        == OutputFormat::Markdown;
+      if markdown {
+        println!(
+          "| Rank | {} | Embedder | \
+           Strategy | Timestamp \
+           |\n|---|---|---|---|---|",
+          metric
+        );
+      }
+      for (rank, run) in
+        runs.iter().enumerate()
+      {
+        let strategy = run
+          .chunk_strategy
+          .map(|strategy| {
+            strategy.to_string()
+          })
+          .unwrap_or_else(|| {
+            "-".into()
+          });
+        if markdown {
+          println!(
+            "| {} | {:.4} | {} | {} | \
+             {} |",
+            rank + 1,
+            run.metric(metric),
+            run.embedder,
+            strategy,
+            run.timestamp
+          );
+        } else {
+          println!(
+            "{}. {} {:.4}  {} ({})  {}",
+            rank + 1,
+            metric,
+            run.metric(metric),
+            run.embedder,
+            strategy,
+            run.timestamp
+          );
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Every `run-*.json` file under
+/// `runs_dir`, best `metric` first.
+/// Unreadable files are skipped with a
+/// warning.
+pub(super) fn leaderboard(
+  runs_dir: &Path,
+  metric: LeaderboardMetric,
+  top: usize
+) -> Result<Vec<RankedRun>> {
+  let mut runs = Vec::new();
+  for entry in WalkDir::new(runs_dir)
+    .into_iter()
+    .filter_map(|entry| entry.ok())
+  {
+    let path = entry.path();
+    let is_run = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .is_some_and(|name| {
+        name.starts_with("run-")
+          && name.ends_with(".json")
+      });
+    if !is_run {
+      continue;
+    }
+    match fs::read(path)
+      .map_err(anyhow::Error::from)
+      .and_then(|bytes| {
+        Ok(serde_json::from_slice::<
+          RankedRun
+        >(&bytes)?)
+      }) {
+      | Ok(mut run) => {
+        run.path = path.to_path_buf();
+        runs.push(run);
+      }
+      | Err(err) => {
+        eprintln!(
+          "Warning: skipping run file \
+           {:?}: {:#}",
+          path, err
+        );
+      }
+    }
+  }
+  let ascending = metric
+    == LeaderboardMetric::Latency;
+  runs.sort_by(|a, b| {
+    let order = a
+      .metric(metric)
+      .partial_cmp(&b.metric(metric))
+      .unwrap_or(Ordering::Equal);
+    if ascending {
+      order
+    } else {
+      order.reverse()
+    }
+  });
+  runs.truncate(top);
+  Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+  use clap::ValueEnum;
+  use tempfile::TempDir;
+
+  use super::*;
+
+  #[test]
+  fn runs_are_ordered_by_recall() {
+    let dir = TempDir::new().unwrap();
+    let day =
+      dir.path().join("2026-01-01");
+    fs::create_dir_all(&day).unwrap();
+    for (name, recall) in [
+      ("tf", 0.5),
+      ("bag-of-words", 0.9),
+      ("tfidf", 0.7)
+    ] {
+      let run = serde_json::json!({
+        "timestamp": "2026-01-01T00:00:00Z",
+        "embedder": name,
+        "metrics": {
+          "recall": recall,
+          "mrr": 0.1,
+          "ndcg": 0.1,
+          "avg_latency_ms": 1.0,
+          "index_size": 3
+        },
+        "queries": []
+      });
+      fs::write(
+        day.join(format!(
+          "run-20260101T000000Z-{}.\
+           json",
+          name
+        )),
+        run.to_string()
+      )
+      .unwrap();
+    }
+    fs::write(
+      day.join("sweep-x.csv"),
+      "ignored"
+    )
+    .unwrap();
+    let runs = leaderboard(
+      dir.path(),
+      LeaderboardMetric::Recall,
+      2
+    )
+    .unwrap();
+    let embedders: Vec<&str> = runs
+      .iter()
+      .map(|run| run.embedder.as_str())
+      .collect();
+    assert_eq!(embedders, vec![
+      "bag-of-words",
+      "tfidf"
+    ]);
+    assert!(
+      LeaderboardMetric::from_str(
+        "speed", true
+      )
+      .is_err()
+    );
+  }
+}
@@ -0,0 +1,142 @@
+use std::fs::{
+  self,
+  File
+};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{
+  Context,
+  Result
+};
+use chrono::{
+  DateTime,
+  Utc
+};
+use serde::Serialize;
+
+use super::metrics::{
+  AggregatedMetrics,
+  QueryReport
+};
+use crate::chunk::ChunkStrategy;
+use crate::config::{
+  Config,
+  Ranking
+};
+
+#[derive(Serialize)]
+struct EvaluationRun {
+  timestamp:      String,
+  embedder:       String,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  chunk_strategy: Option<ChunkStrategy>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  ranking:        Option<Ranking>,
+  metrics:        AggregatedMetrics,
+  queries:        Vec<QueryReport>
+}
+
+pub(super) fn persist_run(
+  config: &Config,
+  embedder_name: &str,
+  strategy: Option<ChunkStrategy>,
+  ranking: Option<Ranking>,
+  metrics: &AggregatedMetrics,
+  queries: &[QueryReport]
+) -> Result<PathBuf> {
+  let timestamp = Utc::now();
+  let path = run_file(
+    config,
+    "run",
+    embedder_name,
+    strategy,
+    ranking,
+    "json",
+    timestamp
+  )?;
+  let run = EvaluationRun {
+    timestamp: timestamp.to_rfc3339(),
+    embedder: embedder_name.to_string(),
+    chunk_strategy: strategy,
+    ranking,
+    metrics: metrics.clone(),
+    queries: queries.to_vec()
+  };
+  let mut file = File::create(&path)
+    .with_context(|| {
+      format!(
+        "create run file {:?}",
+        path
+      )
+    })?;
+  serde_json::to_writer_pretty(
+    &mut file, &run
+  )
+  .with_context(|| {
+    format!("write run file {:?}", path)
+  })?;
+  writeln!(file)?;
+  Ok(path)
+}
+
+/// Path for a logged artifact under
+/// `runs_dir/<date>/`, named after its
+/// kind, timestamp, embedder and
+/// optional chunk strategy and ranking.
+/// Creates the date directory.
+pub(super) fn run_file(
+  config: &Config,
+  kind: &str,
+  embedder_name: &str,
+  strategy: Option<ChunkStrategy>,
+  ranking: Option<Ranking>,
+  extension: &str,
+  timestamp: DateTime<Utc>
+) -> Result<PathBuf> {
+  let date_dir = PathBuf::from(
+    &config.stage2.runs_dir
+  )
+  .join(
+    timestamp
+      .format("%Y-%m-%d")
+      .to_string()
+  );
+  fs::create_dir_all(&date_dir)
+    .with_context(|| {
+      format!(
+        "create run directory {:?}",
+        date_dir
+      )
+    })?;
+  let mut filename = format!(
+    "{}-{}-{}",
+    kind,
+    timestamp.format("%Y%m%dT%H%M%SZ"),
+    embedder_name
+  );
+  if let Some(strategy) = strategy {
+    filename.push_str(&format!(
+      "-{}",
+      strategy
+    ));
+  }
+  if let Some(ranking) = ranking {
+    filename.push_str(&format!(
+      "-{}",
+      ranking
+    ));
+  }
+  filename.push_str(&format!(
+    ".{}",
+    extension
+  ));
+  Ok(
+    date_dir
+      .join(filename.replace('/', "-"))
+  )
+}
@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{
+  Deserialize,
+  Serialize
+};
+
+use crate::config::EvaluationQuery;
+use crate::search::SearchHit;
+
+#[derive(
+  Clone, Deserialize, Serialize,
+)]
+pub(super) struct AggregatedMetrics {
+  pub(super) recall:         f32,
+  pub(super) mrr:            f32,
+  pub(super) ndcg:           f32,
+  pub(super) avg_latency_ms: f32,
+  pub(super) index_size:     usize
+}
+
+#[derive(Clone, Serialize)]
+pub(super) struct QueryReport {
+  pub(super) name:       String,
+  pub(super) top_k:      usize,
+  pub(super) recall:     f32,
+  pub(super) precision:  f32,
+  pub(super) mrr:        f32,
+  pub(super) ndcg:       f32,
+  pub(super) hits:       usize,
+  pub(super) expected:   usize,
+  pub(super) latency_ms: f32,
+  /// Documents of the hits, in rank
+  /// order without repeats
+  #[serde(skip)]
+  pub(super) doc_ids:    Vec<String>
+}
+
+/// Scores `hits` against the query's
+/// expected terms. A term is satisfied
+/// by a chunk containing it or, with
+/// `synonyms`, any of its synonyms.
+pub(super) fn evaluate_query(
+  query: &EvaluationQuery,
+  hits: &[SearchHit],
+  top_k: usize,
+  synonyms: &BTreeMap<
+    String,
+    Vec<String>
+  >
+) -> QueryReport {
+  let normalized_terms: Vec<_> = query
+    .expected_terms
+    .iter()
+    .map(|term| {
+      term_variants(term, synonyms)
+    })
+    .collect();
+  let expected_count =
+    normalized_terms.len();
+  let mut satisfied =
+    vec![false; expected_count];
+  let mut relevance_flags = Vec::new();
+  let mut first_relevant_rank = None;
+  for (rank, hit) in
+    hits.iter().enumerate()
+  {
+    let chunk_text =
+      hit.chunk.text.to_lowercase();
+    let mut relevant = false;
+    for (idx, term) in normalized_terms
+      .iter()
+      .enumerate()
+    {
+      if !satisfied[idx]
+        && term.iter().any(|variant| {
+          chunk_text.contains(variant)
+        })
+      {
+        satisfied[idx] = true;
+        relevant = true;
+      }
+    }
+    if relevant
+      && first_relevant_rank.is_none()
+    {
+      first_relevant_rank =
+        Some(rank + 1);
+    }
+    relevance_flags.push(relevant);
+  }
+  let matched = satisfied
+    .iter()
+    .filter(|&&v| v)
+    .count();
+  let recall = if expected_count == 0 {
+    0.0
+  } else {
+    matched as f32
+      / expected_count as f32
+  };
+  let precision = if hits.is_empty() {
+    0.0
+  } else {
+    relevance_flags
+      .iter()
+      .filter(|&&flag| flag)
+      .count() as f32
+      / hits.len() as f32
+  };
+  let mrr = first_relevant_rank
+    .map(|rank| 1.0 / rank as f32)
+    .unwrap_or(0.0);
+  let ndcg = compute_ndcg(
+    &relevance_flags,
+    matched
+  );
+  let mut doc_ids: Vec<String> =
+    Vec::new();
+  for hit in hits {
+    if !doc_ids
+      .contains(&hit.document.id)
+    {
+      doc_ids
+        .push(hit.document.id.clone());
+    }
+  }
+  QueryReport {
+    name: query.name.clone(),
+    top_k,
+    recall,
+    precision,
+    mrr,
+    ndcg,
+    hits: hits.len(),
+    expected: expected_count,
+    latency_ms: 0.0,
+    doc_ids
+  }
+}
+
+/// `term` and its synonyms, lowercased.
+/// Synonyms apply both ways: a term
+/// listed under a key also matches the
+/// key and the key's other synonyms.
+fn term_variants(
+  term: &str,
+  synonyms: &BTreeMap<
+    String,
+    Vec<String>
+  >
+) -> Vec<String> {
+  let term = term.to_lowercase();
+  let mut variants = vec![term.clone()];
+  for (key, values) in synonyms {
+    let key = key.to_lowercase();
+    let values: Vec<String> = values
+      .iter()
+      .map(|value| value.to_lowercase())
+      .collect();
+    if key == term
+      || values.contains(&term)
+    {
+      for variant in
+        std::iter::once(key)
+          .chain(values)
+      {
+        if !variants.contains(&variant)
+        {
+          variants.push(variant);
+        }
+      }
+    }
+  }
+  variants
+}
+
+fn compute_ndcg(
+  flags: &[bool],
+  relevant: usize
+) -> f32 {
+  if relevant == 0 {
+    return 0.0;
+  }
+  let actual: f32 = flags
+    .iter()
+    .enumerate()
+    .map(|(idx, &flag)| {
+      if !flag {
+        return 0.0;
+      }
+      let rank = idx + 1;
+      1.0 / (rank as f32 + 1.0).log2()
+    })
+    .sum();
+  let ideal: f32 = (0..relevant)
+    .map(|idx| {
+      let rank = idx + 1;
+      1.0 / (rank as f32 + 1.0).log2()
+    })
+    .sum();
+  if ideal == 0.0 {
+    0.0
+  } else {
+    actual / ideal
+  }
+}
+
+/// Averages the per-query metrics;
+/// with `skip_unlabeled`, queries
+/// without expected terms are left out
+/// of the relevance averages.
+pub(super) fn aggregate_metrics(
+  reports: &[QueryReport],
+  latencies: &[Duration],
+  index_size: usize,
+  skip_unlabeled: bool
+) -> AggregatedMetrics {
+  if reports.is_empty()
+    || latencies.is_empty()
+  {
+    return AggregatedMetrics {
+      recall: 0.0,
+      mrr: 0.0,
+      ndcg: 0.0,
+      avg_latency_ms: 0.0,
+      index_size
+    };
+  }
+  let scored: Vec<&QueryReport> =
+    reports
+      .iter()
+      .filter(|r| {
+        !skip_unlabeled
+          || r.expected > 0
+      })
+      .collect();
+  let total =
+    scored.len().max(1) as f32;
+  let recall = scored
+    .iter()
+    .map(|r| r.recall)
+    .sum::<f32>()
+    / total;
+  let mrr = scored
+    .iter()
+    .map(|r| r.mrr)
+    .sum::<f32>()
+    / total;
+  let ndcg = scored
+    .iter()
+    .map(|r| r.ndcg)
+    .sum::<f32>()
+    / total;
+  let avg_latency_ms = latencies
+    .iter()
+    .map(|duration| {
+      duration.as_secs_f32() * 1000.0
+    })
+    .sum::<f32>()
+    / latencies.len() as f32;
+  AggregatedMetrics {
+    recall,
+    mrr,
+    ndcg,
+    avg_latency_ms,
+    index_size
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::chunk::{
+    Chunk,
+    ChunkStrategy
+  };
+  use crate::state::Document;
+
+  fn hit(text: &str) -> SearchHit {
+    SearchHit {
+      chunk:    Chunk {
+        id:        "c1".into(),
+        doc_id:    "d1".into(),
+        text:      text.into(),
+        start:     0,
+        end:       text.len(),
+        strategy:
+          ChunkStrategy::Structured,
+        language:  None,
+        raw_start: None,
+        raw_end:   None,
+        summary:   None,
+        heading:   None,
+        run_id:    0
+      },
+      document: Document {
+        id:          "d1".into(),
+        path:        "d1.txt".into(),
+        hash:        "d1".into(),
+        token_count: 0,
+        tags:        Default::default(),
+        aliases:     Vec::new(),
+        normalized:  None
+      },
+      score:    1.0
+    }
+  }
+
+  #[test]
+  fn synonyms_satisfy_expected_terms() {
+    let query = EvaluationQuery {
+      name:           "car".into(),
+      query:          "fast car".into(),
+      expected_terms: vec![
+        "car".into(),
+        "fast".into(),
+      ],
+      top_k:          None
+    };
+    let hits =
+      [hit("A fast Automobile.")];
+    let exact = evaluate_query(
+      &query,
+      &hits,
+      1,
+      &BTreeMap::new()
+    );
+    assert_eq!(exact.recall, 0.5);
+    let synonyms = BTreeMap::from([(
+      "auto".to_string(),
+      vec![
+        "Car".to_string(),
+        "automobile".to_string(),
+      ]
+    )]);
+    let expanded = evaluate_query(
+      &query, &hits, 1, &synonyms
+    );
+    assert_eq!(expanded.recall, 1.0);
+  }
+
+  #[test]
+  fn unlabeled_queries_can_be_left_out()
+  {
+    let query =
+      |name: &str,
+       expected_terms: &[&str]| {
+        EvaluationQuery {
+          name:           name.into(),
+          query:          "whale"
+            .into(),
+          expected_terms:
+            expected_terms
+              .iter()
+              .map(|term| {
+                term.to_string()
+              })
+              .collect(),
+          top_k:          None
+        }
+      };
+    let hits = [hit("The whale.")];
+    let reports = [
+      evaluate_query(
+        &query("labeled", &["whale"]),
+        &hits,
+        1,
+        &BTreeMap::new()
+      ),
+      evaluate_query(
+        &query("unlabeled", &[]),
+        &hits,
+        1,
+        &BTreeMap::new()
+      )
+    ];
+    let latencies =
+      [Duration::from_millis(2); 2];
+    let counted = aggregate_metrics(
+      &reports, &latencies, 1, false
+    );
+    assert_eq!(counted.recall, 0.5);
+    let skipped = aggregate_metrics(
+      &reports, &latencies, 1, true
+    );
+    assert_eq!(skipped.recall, 1.0);
+    assert_eq!(skipped.mrr, 1.0);
+    assert!(
+      (skipped.avg_latency_ms - 2.0)
+        .abs()
+        < 1e-3
+    );
+  }
+}
@@ -0,0 +1,491 @@
+mod coverage;
+mod leaderboard;
+mod log;
+mod metrics;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod sweep;
+pub(crate) mod tune;
+
+use std::path::Path;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use anyhow::Result;
+#[cfg(feature = "sqlite")]
+use chrono::Utc;
+use rayon::prelude::*;
+
+use self::coverage::{
+  coverage,
+  coverage_lines
+};
+pub use self::leaderboard::print_leaderboard;
+use self::log::persist_run;
+use self::metrics::{
+  AggregatedMetrics,
+  QueryReport,
+  aggregate_metrics,
+  evaluate_query
+};
+use self::sweep::sweep_thresholds;
+pub use self::tune::run_tune;
+use crate::chunk::ChunkStrategy;
+use crate::config::{
+  Config,
+  EvaluationQuery,
+  Ranking
+};
+use crate::embedder::{
+  Embedder,
+  build_embedder
+};
+use crate::index::VectorIndex;
+use crate::pipeline;
+use crate::search::{
+  SearchFilter,
+  search_hits
+};
+use crate::state::State;
+
+/// Evaluates every configured embedder
+/// (and chunk strategy and ranking);
+/// `coverage`
+/// also reports the documents no query
+/// retrieved.
+pub fn run_evaluation(
+  config: &Config,
+  state: &State,
+  index: &VectorIndex,
+  coverage: bool
+) -> Result<()> {
+  if !config.stage2.enabled {
+    println!(
+      "Stage 2 evaluation is disabled."
+    );
+    return Ok(());
+  }
+  let queries =
+    &config.stage2.evaluation.queries;
+  if queries.is_empty() {
+    println!(
+      "No evaluation queries \
+       configured."
+    );
+    return Ok(());
+  }
+  if index.entries().is_empty() {
+    println!(
+      "No indexed chunks yet. Run \
+       `oxbed ingest` before \
+       evaluating."
+    );
+    return Ok(());
+  }
+  if config
+    .stage2
+    .skip_unlabeled_queries
+  {
+    for query in
+      queries.iter().filter(|query| {
+        query.expected_terms.is_empty()
+      })
+    {
+      eprintln!(
+        "Warning: query {:?} has no \
+         expected terms; leaving it \
+         out of recall, MRR and nDCG",
+        query.name
+      );
+    }
+  }
+  let strategies =
+    &config.stage2.chunk_strategies;
+  for kind in
+    &config.stage2.embedder_kinds
+  {
+    let embedder = build_embedder(
+      kind.clone(),
+      config
+    )?;
+    if strategies.is_empty() {
+      evaluate_rankings(
+        config,
+        embedder.as_ref(),
+        None,
+        state,
+        index,
+        coverage
+      )?;
+      continue;
+    }
+    for strategy in strategies {
+      let (state, index) =
+        pipeline::rebuild_corpus(
+          *strategy,
+          config,
+          state,
+          index,
+          embedder.as_ref()
+        );
+      evaluate_rankings(
+        config,
+        embedder.as_ref(),
+        Some(*strategy),
+        &state,
+        &index,
+        coverage
+      )?;
+    }
+  }
+  Ok(())
+}
+
+/// Evaluates one corpus under each of
+/// `stage2.rankings`, or once with
+/// `stage1.search.ranking` when none
+/// are listed.
+fn evaluate_rankings(
+  config: &Config,
+  embedder: &dyn Embedder,
+  strategy: Option<ChunkStrategy>,
+  state: &State,
+  index: &VectorIndex,
+  show_coverage: bool
+) -> Result<()> {
+  if config.stage2.rankings.is_empty() {
+    return evaluate_corpus(
+      config,
+      embedder,
+      strategy,
+      None,
+      state,
+      index,
+      show_coverage
+    );
+  }
+  for &ranking in
+    &config.stage2.rankings
+  {
+    let mut ranked = config.clone();
+    ranked.stage1.search.ranking =
+      ranking;
+    evaluate_corpus(
+      &ranked,
+      embedder,
+      strategy,
+      Some(ranking),
+      state,
+      index,
+      show_coverage
+    )?;
+  }
+  Ok(())
+}
+
+/// Runs every configured query against
+/// one corpus/embedder combination,
+/// then logs and prints the metrics.
+fn evaluate_corpus(
+  config: &Config,
+  embedder: &dyn Embedder,
+  strategy: Option<ChunkStrategy>,
+  ranking: Option<Ranking>,
+  state: &State,
+  index: &VectorIndex,
+  show_coverage: bool
+) -> Result<()> {
+  let embedder_name = embedder.name();
+  let (query_reports, latencies): (
+    Vec<_>,
+    Vec<_>
+  ) = run_queries(
+    config, embedder, state, index
+  )?
+  .into_iter()
+  .unzip();
+  let aggregated = aggregate_metrics(
+    &query_reports,
+    &latencies,
+    index.entries().len(),
+    config
+      .stage2
+      .skip_unlabeled_queries
+  );
+  if config.stage2.log_evaluation {
+    let run_path = persist_run(
+      config,
+      &embedder_name,
+      strategy,
+      ranking,
+      &aggregated,
+      &query_reports
+    )?;
+    println!(
+      "Logged evaluation run to {}",
+      run_path.display()
+    );
+  }
+  if let Some(db) =
+    &config.stage2.sqlite_db
+  {
+    record_sqlite(
+      db,
+      &embedder_name,
+      strategy,
+      ranking,
+      &aggregated
+    )?;
+  }
+  let variant: Vec<String> = strategy
+    .map(|strategy| {
+      strategy.to_string()
+    })
+    .into_iter()
+    .chain(ranking.map(|ranking| {
+      ranking.to_string()
+    }))
+    .collect();
+  let label = if variant.is_empty() {
+    embedder_name.clone()
+  } else {
+    format!(
+      "{} ({})",
+      embedder_name,
+      variant.join(", ")
+    )
+  };
+  for line in summary_lines(
+    &label,
+    &aggregated,
+    &query_reports,
+    config.stage2.verbose_queries
+  ) {
+    println!("{}", line);
+  }
+  if show_coverage {
+    for line in coverage_lines(
+      &label,
+      &coverage(state, &query_reports)
+    ) {
+      println!("{}", line);
+    }
+  }
+  if !config
+    .stage2
+    .threshold_sweep
+    .is_empty()
+  {
+    sweep_thresholds(
+      config, embedder, strategy,
+      ranking, &label, state, index
+    )?;
+  }
+  Ok(())
+}
+
+/// The aggregate console line, followed
+/// by one line per query when
+/// `verbose` is set.
+fn summary_lines(
+  label: &str,
+  aggregated: &AggregatedMetrics,
+  reports: &[QueryReport],
+  verbose: bool
+) -> Vec<String> {
+  let mut lines = vec![format!(
+    "Evaluation {} → recall={:.3}, \
+     mrr={:.3}, nDCG={:.3}, \
+     latency={:.1}ms, index={} entries",
+    label,
+    aggregated.recall,
+    aggregated.mrr,
+    aggregated.ndcg,
+    aggregated.avg_latency_ms,
+    aggregated.index_size
+  )];
+  if verbose {
+    lines.extend(reports.iter().map(
+      |report| {
+        format!(
+          "  {}: recall={:.3}, \
+           mrr={:.3}, nDCG={:.3}, \
+           hits={}",
+          report.name,
+          report.recall,
+          report.mrr,
+          report.ndcg,
+          report.hits
+        )
+      }
+    ));
+  }
+  lines
+}
+
+/// Appends the run's metrics to the
+/// `stage2.sqlite_db` database.
+#[cfg(feature = "sqlite")]
+fn record_sqlite(
+  db: &Path,
+  embedder_name: &str,
+  strategy: Option<ChunkStrategy>,
+  ranking: Option<Ranking>,
+  metrics: &AggregatedMetrics
+) -> Result<()> {
+  sqlite::record_run(
+    db,
+    &Utc::now().to_rfc3339(),
+    embedder_name,
+    strategy,
+    ranking,
+    metrics
+  )
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn record_sqlite(
+  _db: &Path,
+  _embedder_name: &str,
+  _strategy: Option<ChunkStrategy>,
+  _ranking: Option<Ranking>,
+  _metrics: &AggregatedMetrics
+) -> Result<()> {
+  eprintln!(
+    "Warning: stage2.sqlite_db is set \
+     but oxbed was built without the \
+     sqlite feature"
+  );
+  Ok(())
+}
+
+/// Runs the configured queries, in
+/// parallel when
+/// `stage2.parallel_queries` is set.
+/// Reports keep the configured query
+/// order either way. Each latency is
+/// the wall time of its own query, so
+/// under parallel execution it also
+/// includes time spent waiting on other
+/// queries for CPU.
+fn run_queries(
+  config: &Config,
+  embedder: &dyn Embedder,
+  state: &State,
+  index: &VectorIndex
+) -> Result<Vec<(QueryReport, Duration)>>
+{
+  let queries =
+    &config.stage2.evaluation.queries;
+  let run = |query| {
+    run_query(
+      config, embedder, state, index,
+      query
+    )
+  };
+  if config.stage2.parallel_queries {
+    queries
+      .par_iter()
+      .map(run)
+      .collect()
+  } else {
+    queries.iter().map(run).collect()
+  }
+}
+
+fn run_query(
+  config: &Config,
+  embedder: &dyn Embedder,
+  state: &State,
+  index: &VectorIndex,
+  query: &EvaluationQuery
+) -> Result<(QueryReport, Duration)> {
+  let resolved_top_k =
+    query.top_k.unwrap_or(
+      config.stage1.search.top_k
+    );
+  let start = Instant::now();
+  let hits = search_hits(
+    embedder,
+    &query.query,
+    resolved_top_k,
+    &SearchFilter::default(),
+    config,
+    state,
+    index
+  )?;
+  let duration = start.elapsed();
+  let mut report = evaluate_query(
+    query,
+    &hits,
+    resolved_top_k,
+    &config.stage2.synonyms
+  );
+  report.latency_ms =
+    duration.as_secs_f32() * 1000.0;
+  Ok((report, duration))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn verbose_summary_lists_each_query()
+  {
+    let aggregated =
+      AggregatedMetrics {
+        recall:         0.5,
+        mrr:            0.5,
+        ndcg:           0.5,
+        avg_latency_ms: 1.0,
+        index_size:     2
+      };
+    let report = |name: &str| {
+      QueryReport {
+        name:       name.into(),
+        top_k:      1,
+        recall:     1.0,
+        precision:  1.0,
+        mrr:        1.0,
+        ndcg:       1.0,
+        hits:       1,
+        expected:   1,
+        latency_ms: 1.0,
+        doc_ids:    Vec::new()
+      }
+    };
+    let reports =
+      [report("whale"), report("ahab")];
+    let quiet = summary_lines(
+      "tf",
+      &aggregated,
+      &reports,
+      false
+    );
+    assert_eq!(quiet.len(), 1);
+    assert!(reports.iter().all(
+      |report| {
+        !quiet[0].contains(&report.name)
+      }
+    ));
+    let verbose = summary_lines(
+      "tf",
+      &aggregated,
+      &reports,
+      true
+    );
+    assert_eq!(verbose[0], quiet[0]);
+    assert!(
+      verbose[1].contains("whale")
+    );
+    assert!(
+      verbose[2].contains("ahab")
+    );
+    assert!(
+      verbose[2].ends_with("hits=1")
+    );
+  }
+}
@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{
+  Context,
+  Result
+};
+use rusqlite::{
+  Connection,
+  params
+};
+
+use super::metrics::AggregatedMetrics;
+use crate::chunk::ChunkStrategy;
+use crate::config::Ranking;
+
+const CREATE_RUNS: &str =
+  "CREATE TABLE IF NOT EXISTS \
+   evaluation_runs (id INTEGER \
+   PRIMARY KEY, timestamp TEXT NOT \
+   NULL, embedder TEXT NOT NULL, \
+   chunk_strategy TEXT, recall REAL \
+   NOT NULL, mrr REAL NOT NULL, ndcg \
+   REAL NOT NULL, avg_latency_ms REAL \
+   NOT NULL, index_size INTEGER NOT \
+   NULL, ranking TEXT)";
+
+/// Appends one evaluation run to the
+/// `evaluation_runs` table of the
+/// database at `path`, creating the
+/// file and table when missing and
+/// adding the `ranking` column to
+/// tables created before it.
+pub(super) fn record_run(
+  path: &Path,
+  timestamp: &str,
+  embedder_name: &str,
+  strategy: Option<ChunkStrategy>,
+  ranking: Option<Ranking>,
+  metrics: &AggregatedMetrics
+) -> Result<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)
+      .with_context(|| {
+        format!(
+          "create directory {:?}",
+          parent
+        )
+      })?;
+  }
+  let connection =
+    Connection::open(path)
+      .with_context(|| {
+        format!(
+          "open evaluation database \
+           {:?}",
+          path
+        )
+      })?;
+  connection
+    .execute(CREATE_RUNS, [])?;
+  if connection
+    .prepare(
+      "SELECT ranking FROM \
+       evaluation_runs"
+    )
+    .is_err()
+  {
+    connection.execute(
+      "ALTER TABLE evaluation_runs \
+       ADD COLUMN ranking TEXT",
+      []
+    )?;
+  }
+  connection
+    .execute(
+      "INSERT INTO evaluation_runs \
+       (timestamp, embedder, \
+       chunk_strategy, recall, mrr, \
+       ndcg, avg_latency_ms, \
+       index_size, ranking) VALUES \
+       (?1, ?2, ?3, ?4, ?5, ?6, ?7, \
+       ?8, ?9)",
+      params![
+        timestamp,
+        embedder_name,
+        strategy.map(|strategy| {
+          strategy.to_string()
+        }),
+        metrics.recall,
+        metrics.mrr,
+        metrics.ndcg,
+        metrics.avg_latency_ms,
+        metrics.index_size as i64,
+        ranking.map(|ranking| {
+          ranking.to_string()
+        })
+      ]
+    )
+    .with_context(|| {
+      format!(
+        "record evaluation run in {:?}",
+        path
+      )
+    })?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use tempfile::TempDir;
+
+  use super::*;
+
+  #[test]
+  fn runs_accumulate_for_trend_queries()
+  {
+    let dir = TempDir::new().unwrap();
+    let path =
+      dir.path().join("runs/eval.db");
+    for (timestamp, recall) in [
+      ("2026-01-01T00:00:00Z", 0.5),
+      ("2026-01-02T00:00:00Z", 0.75)
+    ] {
+      record_run(
+        &path,
+        timestamp,
+        "tf",
+        Some(ChunkStrategy::Fixed),
+        Some(Ranking::Bm25),
+        &AggregatedMetrics {
+          recall,
+          mrr: 0.5,
+          ndcg: 0.5,
+          avg_latency_ms: 1.0,
+          index_size: 3
+        }
+      )
+      .unwrap();
+    }
+    let connection =
+      Connection::open(&path).unwrap();
+    let mut statement = connection
+      .prepare(
+        "SELECT recall, \
+         chunk_strategy, ranking FROM \
+         evaluation_runs WHERE \
+         embedder = 'tf' ORDER BY \
+         timestamp"
+      )
+      .unwrap();
+    let rows: Vec<(
+      f32,
+      String,
+      String
+    )> = statement
+      .query_map([], |row| {
+        Ok((
+          row.get(0)?,
+          row.get(1)?,
+          row.get(2)?
+        ))
+      })
+      .unwrap()
+      .collect::<Result<_, _>>()
+      .unwrap();
+    assert_eq!(rows, vec![
+      (
+        0.5,
+        "fixed".to_string(),
+        "bm25".to_string()
+      ),
+      (
+        0.75,
+        "fixed".to_string(),
+        "bm25".to_string()
+      )
+    ]);
+  }
+}
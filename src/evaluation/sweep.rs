@@ -0,0 +1,120 @@
+use std::fs;
+
+use anyhow::{
+  Context,
+  Result
+};
+use chrono::Utc;
+
+use super::log::run_file;
+use super::run_queries;
+use crate::chunk::ChunkStrategy;
+use crate::config::{
+  Config,
+  Ranking
+};
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::state::State;
+
+/// Re-runs the queries once per
+/// `stage2.threshold_sweep` value, used
+/// as `score_threshold`, and prints the
+/// mean recall, precision and F1 for
+/// each. The table is also logged as a
+/// CSV next to the evaluation runs.
+pub(super) fn sweep_thresholds(
+  config: &Config,
+  embedder: &dyn Embedder,
+  strategy: Option<ChunkStrategy>,
+  ranking: Option<Ranking>,
+  label: &str,
+  state: &State,
+  index: &VectorIndex
+) -> Result<()> {
+  let mut swept = config.clone();
+  let mut rows = Vec::new();
+  for &threshold in
+    &config.stage2.threshold_sweep
+  {
+    swept
+      .stage1
+      .search
+      .score_threshold = threshold;
+    let reports: Vec<_> = run_queries(
+      &swept, embedder, state, index
+    )?
+    .into_iter()
+    .map(|(report, _)| report)
+    .collect();
+    let total =
+      reports.len().max(1) as f32;
+    let recall = reports
+      .iter()
+      .map(|r| r.recall)
+      .sum::<f32>()
+      / total;
+    let precision = reports
+      .iter()
+      .map(|r| r.precision)
+      .sum::<f32>()
+      / total;
+    let f1 = if recall + precision > 0.0
+    {
+      2.0 * recall * precision
+        / (recall + precision)
+    } else {
+      0.0
+    };
+    rows.push([
+      threshold, recall, precision, f1
+    ]);
+  }
+  println!(
+    "Threshold sweep {}:",
+    label
+  );
+  let mut csv = String::from(
+    "threshold,recall,precision,f1\n"
+  );
+  for [
+    threshold,
+    recall,
+    precision,
+    f1
+  ] in rows
+  {
+    println!(
+      "  threshold={:.3} recall={:.3} \
+       precision={:.3} f1={:.3}",
+      threshold, recall, precision, f1
+    );
+    csv.push_str(&format!(
+      "{},{},{},{}\n",
+      threshold, recall, precision, f1
+    ));
+  }
+  if config.stage2.log_evaluation {
+    let path = run_file(
+      config,
+      "sweep",
+      &embedder.name(),
+      strategy,
+      ranking,
+      "csv",
+      Utc::now()
+    )?;
+    fs::write(&path, csv)
+      .with_context(|| {
+        format!(
+          "write sweep file {:?}",
+          path
+        )
+      })?;
+    println!(
+      "Logged threshold sweep to {}",
+      path.display()
+    );
+  }
+  Ok(())
+}
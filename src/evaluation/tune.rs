@@ -0,0 +1,256 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{
+  Context,
+  Result,
+  bail
+};
+use toml_edit::{
+  Document,
+  Item,
+  Table,
+  TableLike,
+  value
+};
+
+use super::metrics::aggregate_metrics;
+use super::run_queries;
+use crate::chunk::ChunkStrategy;
+use crate::config::Config;
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::pipeline;
+use crate::state::State;
+
+/// One chunking combination tried by
+/// `tune` and the metrics it scored.
+#[derive(Clone, Debug)]
+pub struct TuneResult {
+  pub strategy:   ChunkStrategy,
+  pub max_tokens: usize,
+  pub overlap:    usize,
+  pub recall:     f32,
+  pub mrr:        f32
+}
+
+/// Rechunks the corpus into a transient
+/// index for every `stage2.tuning`
+/// combination and scores the
+/// evaluation queries against it.
+/// Results are ordered best first, by
+/// recall and then MRR. Combinations
+/// whose overlap is not below
+/// `max_tokens` are skipped.
+pub fn tune(
+  config: &Config,
+  state: &State,
+  index: &VectorIndex,
+  embedder: &dyn Embedder
+) -> Result<Vec<TuneResult>> {
+  let tuning = &config.stage2.tuning;
+  let mut grid = Vec::new();
+  for &strategy in &tuning.strategies {
+    for &max_tokens in
+      &tuning.max_tokens
+    {
+      for &overlap in &tuning.overlap {
+        if overlap < max_tokens {
+          grid.push((
+            strategy, max_tokens,
+            overlap
+          ));
+        }
+      }
+    }
+  }
+  if grid.is_empty() {
+    bail!(
+      "stage2.tuning has no valid \
+       combinations"
+    );
+  }
+  if grid.len()
+    > tuning.max_combinations
+  {
+    bail!(
+      "stage2.tuning spans {} \
+       combinations, above \
+       max_combinations ({})",
+      grid.len(),
+      tuning.max_combinations
+    );
+  }
+  let mut tuned = config.clone();
+  let mut results = Vec::new();
+  for (strategy, max_tokens, overlap) in
+    grid
+  {
+    tuned.stage1.chunk.max_tokens =
+      max_tokens;
+    tuned.stage1.chunk.overlap =
+      overlap;
+    let (state, index) =
+      pipeline::rebuild_corpus(
+        strategy, &tuned, state, index,
+        embedder
+      );
+    let (reports, latencies): (
+      Vec<_>,
+      Vec<_>
+    ) = run_queries(
+      &tuned, embedder, &state, &index
+    )?
+    .into_iter()
+    .unzip();
+    let metrics = aggregate_metrics(
+      &reports,
+      &latencies,
+      index.entries().len(),
+      config
+        .stage2
+        .skip_unlabeled_queries
+    );
+    results.push(TuneResult {
+      strategy,
+      max_tokens,
+      overlap,
+      recall: metrics.recall,
+      mrr: metrics.mrr
+    });
+  }
+  results.sort_by(|a, b| {
+    b.recall
+      .partial_cmp(&a.recall)
+      .unwrap_or(Ordering::Equal)
+      .then_with(|| {
+        b.mrr
+          .partial_cmp(&a.mrr)
+          .unwrap_or(Ordering::Equal)
+      })
+  });
+  Ok(results)
+}
+
+/// Runs `tune`, prints every
+/// combination best first, and, with
+/// `write`, stores the best
+/// `max_tokens`/`overlap` in that TOML
+/// file (created when missing).
+pub fn run_tune(
+  config: &Config,
+  state: &State,
+  index: &VectorIndex,
+  embedder: &dyn Embedder,
+  write: Option<&Path>
+) -> Result<()> {
+  if config
+    .stage2
+    .evaluation
+    .queries
+    .is_empty()
+  {
+    println!(
+      "No evaluation queries \
+       configured."
+    );
+    return Ok(());
+  }
+  if index.entries().is_empty() {
+    println!(
+      "No indexed chunks yet. Run \
+       `oxbed ingest` before tuning."
+    );
+    return Ok(());
+  }
+  let results = tune(
+    config, state, index, embedder
+  )?;
+  for result in &results {
+    println!(
+      "{} max_tokens={} overlap={} \
+       recall={:.3} mrr={:.3}",
+      result.strategy,
+      result.max_tokens,
+      result.overlap,
+      result.recall,
+      result.mrr
+    );
+  }
+  let best = &results[0];
+  println!(
+    "Best: --strategy {} with \
+     max_tokens={} overlap={}",
+    best.strategy,
+    best.max_tokens,
+    best.overlap
+  );
+  if let Some(path) = write {
+    write_chunk_settings(path, best)?;
+    println!(
+      "Wrote chunk settings to {}",
+      path.display()
+    );
+  }
+  Ok(())
+}
+
+/// Sets `stage1.chunk.max_tokens` and
+/// `overlap` in the TOML file at
+/// `path`, leaving its other keys,
+/// comments and layout untouched.
+fn write_chunk_settings(
+  path: &Path,
+  best: &TuneResult
+) -> Result<()> {
+  let mut document: Document =
+    if path.exists() {
+      fs::read_to_string(path)
+        .with_context(|| {
+          format!(
+            "read config {:?}",
+            path
+          )
+        })?
+        .parse()
+        .with_context(|| {
+          format!(
+            "parse config {:?}",
+            path
+          )
+        })?
+    } else {
+      Document::new()
+    };
+  let mut table: &mut dyn TableLike =
+    document.as_table_mut();
+  for key in ["stage1", "chunk"] {
+    table = table
+      .entry(key)
+      .or_insert_with(|| {
+        let mut table = Table::new();
+        table.set_implicit(true);
+        Item::Table(table)
+      })
+      .as_table_like_mut()
+      .with_context(|| {
+        format!(
+          "{} is not a table in {:?}",
+          key, path
+        )
+      })?;
+  }
+  table.insert(
+    "max_tokens",
+    value(best.max_tokens as i64)
+  );
+  table.insert(
+    "overlap",
+    value(best.overlap as i64)
+  );
+  fs::write(path, document.to_string())
+    .with_context(|| {
+      format!("write config {:?}", path)
+    })
+}
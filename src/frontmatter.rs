@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use anyhow::{
+  Result,
+  bail
+};
+
+const DELIMITER: &str = "---";
+
+/// Key/value metadata parsed from a
+/// `---`-delimited block at the top of
+/// a file.
+#[derive(Debug, Default)]
+pub struct FrontMatter {
+  pub tags: BTreeMap<String, String>,
+  /// Byte offset where the content
+  /// after the closing delimiter
+  /// starts
+  pub body_start: usize
+}
+
+/// Parses leading front-matter.
+/// Returns `Ok(None)` when the content
+/// does not open with `---`, and an
+/// error when the block is unterminated
+/// or holds a line that is neither
+/// `key: value`, a `- item` continuing
+/// the previous key, a comment, nor
+/// blank. List values (`- item` lines
+/// or `[a, b]`) are joined with `, `.
+pub fn parse(
+  content: &str
+) -> Result<Option<FrontMatter>> {
+  let mut lines =
+    content.split_inclusive('\n');
+  let Some(first) = lines.next() else {
+    return Ok(None);
+  };
+  if first.trim_end() != DELIMITER {
+    return Ok(None);
+  }
+  let mut front = FrontMatter {
+    body_start: first.len(),
+    ..FrontMatter::default()
+  };
+  let mut last_key: Option<String> =
+    None;
+  for line in lines {
+    front.body_start += line.len();
+    let trimmed = line.trim();
+    if line.trim_end() == DELIMITER {
+      return Ok(Some(front));
+    }
+    if trimmed.is_empty()
+      || trimmed.starts_with('#')
+    {
+      continue;
+    }
+    if let Some(item) =
+      trimmed.strip_prefix("- ")
+      && let Some(key) = &last_key
+    {
+      let value = front
+        .tags
+        .entry(key.clone())
+        .or_default();
+      if !value.is_empty() {
+        value.push_str(", ");
+      }
+      value.push_str(unquote(item));
+      continue;
+    }
+    let Some((key, value)) =
+      trimmed.split_once(':')
+    else {
+      bail!(
+        "front-matter line {:?} is \
+         not `key: value`",
+        trimmed
+      );
+    };
+    let key = key.trim();
+    if key.is_empty() {
+      bail!(
+        "front-matter line {:?} has \
+         no key",
+        trimmed
+      );
+    }
+    front
+      .tags
+      .insert(key.into(), list(value));
+    last_key = Some(key.into());
+  }
+  bail!(
+    "front-matter is missing its \
+     closing `---`"
+  )
+}
+
+fn list(value: &str) -> String {
+  let value = value.trim();
+  match value
+    .strip_prefix('[')
+    .and_then(|inner| {
+      inner.strip_suffix(']')
+    }) {
+    | Some(inner) => {
+      inner
+        .split(',')
+        .map(unquote)
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+    | None => unquote(value).into()
+  }
+}
+
+fn unquote(value: &str) -> &str {
+  let value = value.trim();
+  ['"', '\'']
+    .iter()
+    .find_map(|quote| {
+      value
+        .strip_prefix(*quote)?
+        .strip_suffix(*quote)
+    })
+    .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_scalars_and_lists() {
+    let content =
+      "---\ntitle: \"Moby \
+       Dick\"\ntags: [sea, \
+       whales]\nauthors:\n  - \
+       Melville\n---\nCall me Ishmael.";
+    let front = parse(content)
+      .unwrap()
+      .expect("front-matter");
+    assert_eq!(
+      front.tags["title"],
+      "Moby Dick"
+    );
+    assert_eq!(
+      front.tags["tags"],
+      "sea, whales"
+    );
+    assert_eq!(
+      front.tags["authors"],
+      "Melville"
+    );
+    assert_eq!(
+      &content[front.body_start..],
+      "Call me Ishmael."
+    );
+  }
+
+  #[test]
+  fn rejects_malformed_blocks() {
+    assert!(
+      parse("no front matter")
+        .unwrap()
+        .is_none()
+    );
+    assert!(
+      parse("---\ntitle: x\nbody")
+        .is_err()
+    );
+    assert!(
+      parse("---\njust text\n---\n")
+        .is_err()
+    );
+  }
+}
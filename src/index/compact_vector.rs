@@ -0,0 +1,79 @@
+use serde::{
+  Deserialize,
+  Deserializer,
+  Serialize,
+  Serializer
+};
+
+use crate::embedder::SparseVector;
+
+#[derive(Serialize)]
+struct CompactRef<'a> {
+  terms:   Vec<&'a str>,
+  weights: Vec<f32>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+  Compact {
+    terms:   Vec<String>,
+    weights: Vec<f32>
+  },
+  Map(SparseVector)
+}
+
+pub fn serialize<S: Serializer>(
+  vector: &SparseVector,
+  serializer: S
+) -> Result<S::Ok, S::Error> {
+  let mut pairs: Vec<_> =
+    vector.iter().collect();
+  pairs.sort_by(|a, b| a.0.cmp(b.0));
+  CompactRef {
+    terms:   pairs
+      .iter()
+      .map(|(term, _)| term.as_str())
+      .collect(),
+    weights: pairs
+      .iter()
+      .map(|(_, weight)| **weight)
+      .collect()
+  }
+  .serialize(serializer)
+}
+
+pub fn deserialize<'de, D>(
+  deserializer: D
+) -> Result<SparseVector, D::Error>
+where
+  D: Deserializer<'de>
+{
+  match Repr::deserialize(deserializer)?
+  {
+    | Repr::Compact {
+      terms,
+      weights
+    } => {
+      if terms.len() != weights.len() {
+        return Err(
+          serde::de::Error::custom(
+            format!(
+              "vector has {} terms \
+               but {} weights",
+              terms.len(),
+              weights.len()
+            )
+          )
+        );
+      }
+      Ok(
+        terms
+          .into_iter()
+          .zip(weights)
+          .collect()
+      )
+    }
+    | Repr::Map(vector) => Ok(vector)
+  }
+}
@@ -0,0 +1,242 @@
+use std::collections::{
+  HashMap,
+  HashSet
+};
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use serde::{
+  Deserialize,
+  Serialize
+};
+
+use crate::embedder::SparseVector;
+
+/// Stores a [`SparseVector`] as two
+/// parallel arrays sorted by term,
+/// which is smaller than a JSON object
+/// and diffs stably. The older object
+/// form is still accepted on read.
+mod compact_vector;
+pub(crate) mod persist;
+mod search;
+mod similarity;
+
+pub use self::search::{
+  BM25_B,
+  BM25_K1
+};
+pub use self::similarity::{
+  cosine_fn,
+  cosine_similarity
+};
+
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+pub struct IndexEntry {
+  pub chunk_id:         String,
+  pub doc_id:           String,
+  #[serde(with = "compact_vector")]
+  pub vector:           SparseVector,
+  /// Hash of the inputs the vector was
+  /// embedded from, when recorded
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub embed_input_hash: Option<String>,
+  /// Raw count of each term kept from
+  /// the embedded text, when recorded;
+  /// BM25 scores with these and takes
+  /// their sum as the entry length
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub term_counts: Option<SparseVector>
+}
+
+/// Size figures of a [`VectorIndex`],
+/// for capacity planning.
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexStats {
+  pub entries:         usize,
+  pub distinct_terms:  usize,
+  pub nonzero_entries: usize,
+  /// Mean share of the vocabulary set
+  /// in each vector
+  pub average_density: f32,
+  /// Estimated heap and inline bytes
+  /// held by the entries
+  pub approx_bytes:    usize
+}
+
+pub struct VectorIndex {
+  entries:     Vec<IndexEntry>,
+  /// Mean chunk vector per document,
+  /// built on first use and dropped
+  /// whenever the entries change
+  doc_vectors: OnceLock<
+    Vec<(String, SparseVector)>
+  >,
+  /// Document frequencies and mean
+  /// length for BM25, built on first
+  /// use like `doc_vectors`
+  bm25_stats:  OnceLock<Bm25Stats>,
+  /// The index file the entries were
+  /// loaded from or last saved to, and
+  /// its checksum; cleared whenever
+  /// the entries change
+  saved:       Option<(PathBuf, u32)>
+}
+
+struct Bm25Stats {
+  document_frequency:
+    HashMap<String, usize>,
+  avg_length:         f32
+}
+
+impl VectorIndex {
+  pub fn from_entries(
+    entries: Vec<IndexEntry>
+  ) -> Self {
+    Self {
+      entries,
+      doc_vectors: OnceLock::new(),
+      bm25_stats: OnceLock::new(),
+      saved: None
+    }
+  }
+
+  pub fn add_chunk(
+    &mut self,
+    chunk_id: String,
+    doc_id: String,
+    vector: SparseVector,
+    embed_input_hash: Option<String>,
+    term_counts: Option<SparseVector>
+  ) {
+    self.entries.push(IndexEntry {
+      chunk_id,
+      doc_id,
+      vector,
+      embed_input_hash,
+      term_counts
+    });
+    self.doc_vectors.take();
+    self.bm25_stats.take();
+    self.saved = None;
+  }
+
+  /// Drops every entry belonging to
+  /// `doc_id`.
+  pub fn remove_document(
+    &mut self,
+    doc_id: &str
+  ) {
+    self.entries.retain(|entry| {
+      entry.doc_id != doc_id
+    });
+    self.doc_vectors.take();
+    self.bm25_stats.take();
+    self.saved = None;
+  }
+
+  /// Every term that appears in a
+  /// stored vector.
+  pub fn vocabulary(
+    &self
+  ) -> HashSet<String> {
+    self
+      .entries
+      .iter()
+      .flat_map(|entry| {
+        entry.vector.keys().cloned()
+      })
+      .collect()
+  }
+
+  /// Counts terms and vector entries
+  /// and estimates the memory held by
+  /// the index. Map sizes assume one
+  /// control byte per bucket, so the
+  /// byte figure is approximate.
+  pub fn stats(&self) -> IndexStats {
+    let distinct_terms =
+      self.vocabulary().len();
+    let nonzero_entries = self
+      .entries
+      .iter()
+      .map(|entry| entry.vector.len())
+      .sum();
+    let approx_bytes = self
+      .entries
+      .iter()
+      .map(|entry| {
+        size_of::<IndexEntry>()
+          + entry.chunk_id.capacity()
+          + entry.doc_id.capacity()
+          + entry
+            .embed_input_hash
+            .as_ref()
+            .map_or(0, String::capacity)
+          + entry.vector.capacity()
+            * (size_of::<(String, f32)>(
+            ) + 1)
+          + entry
+            .vector
+            .keys()
+            .map(String::capacity)
+            .sum::<usize>()
+      })
+      .sum();
+    let average_density =
+      if self.entries.is_empty()
+        || distinct_terms == 0
+      {
+        0.0
+      } else {
+        nonzero_entries as f32
+          / (self.entries.len()
+            * distinct_terms)
+            as f32
+      };
+    IndexStats {
+      entries: self.entries.len(),
+      distinct_terms,
+      nonzero_entries,
+      average_density,
+      approx_bytes
+    }
+  }
+
+  pub fn entries(
+    &self
+  ) -> &[IndexEntry] {
+    &self.entries
+  }
+
+  pub fn into_entries(
+    self
+  ) -> Vec<IndexEntry> {
+    self.entries
+  }
+
+  pub fn vector_for(
+    &self,
+    chunk_id: &str
+  ) -> Option<&SparseVector> {
+    self
+      .entries
+      .iter()
+      .find(|entry| {
+        entry.chunk_id == chunk_id
+      })
+      .map(|entry| &entry.vector)
+  }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,396 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{
+  Context,
+  Result,
+  anyhow,
+  bail
+};
+
+use super::{
+  IndexEntry,
+  SparseVector,
+  VectorIndex
+};
+use crate::storage;
+
+const MAGIC: &[u8; 6] = b"OXBIDX";
+const VERSION: u8 = 2;
+
+/// Encodes `entries` in the index file
+/// format: a magic tag and version,
+/// the entry count, then each entry's
+/// IDs, its terms and weights sorted by
+/// term, its optional input hash, and
+/// its optional term counts laid out
+/// like the weights. Integers are
+/// little-endian and strings carry a
+/// `u32` byte length.
+pub(crate) fn encode(
+  entries: &[IndexEntry]
+) -> Vec<u8> {
+  let mut out = MAGIC.to_vec();
+  out.push(VERSION);
+  put_u64(&mut out, entries.len());
+  for entry in entries {
+    put_str(&mut out, &entry.chunk_id);
+    put_str(&mut out, &entry.doc_id);
+    put_vector(&mut out, &entry.vector);
+    match &entry.embed_input_hash {
+      | Some(hash) => {
+        out.push(1);
+        put_str(&mut out, hash);
+      }
+      | None => out.push(0)
+    }
+    match &entry.term_counts {
+      | Some(counts) => {
+        out.push(1);
+        put_vector(&mut out, counts);
+      }
+      | None => out.push(0)
+    }
+  }
+  out
+}
+
+/// Decodes an index file written by
+/// [`encode`].
+pub(crate) fn decode(
+  bytes: &[u8]
+) -> Result<Vec<IndexEntry>> {
+  let mut reader = Reader {
+    bytes,
+    pos: 0
+  };
+  if reader.take(MAGIC.len())? != MAGIC
+  {
+    bail!("not an oxbed index file");
+  }
+  let version = reader.u8()?;
+  if version != VERSION {
+    bail!(
+      "unsupported index file version \
+       {}",
+      version
+    );
+  }
+  let count = reader.u64()?;
+  let mut entries = Vec::new();
+  for _ in 0..count {
+    let chunk_id = reader.string()?;
+    let doc_id = reader.string()?;
+    let vector = reader.vector()?;
+    let embed_input_hash = reader
+      .flag()?
+      .then(|| reader.string())
+      .transpose()?;
+    let term_counts = reader
+      .flag()?
+      .then(|| reader.vector())
+      .transpose()?;
+    entries.push(IndexEntry {
+      chunk_id,
+      doc_id,
+      vector,
+      embed_input_hash,
+      term_counts
+    });
+  }
+  if reader.pos != bytes.len() {
+    bail!(
+      "trailing bytes in index file"
+    );
+  }
+  Ok(entries)
+}
+
+impl VectorIndex {
+  /// Reads the index file at `path`.
+  pub fn load_from(
+    path: &Path
+  ) -> Result<Self> {
+    let bytes = fs::read(path)
+      .with_context(|| {
+        format!(
+          "read index from {:?}",
+          path
+        )
+      })?;
+    let entries = decode(&bytes)
+      .with_context(|| {
+        format!(
+          "parse index file {:?}",
+          path
+        )
+      })?;
+    let mut index =
+      Self::from_entries(entries);
+    index.saved = Some((
+      path.to_path_buf(),
+      crc32fast::hash(&bytes)
+    ));
+    Ok(index)
+  }
+
+  /// Checksum of the index file the
+  /// entries were loaded from or last
+  /// saved to, unless they changed
+  /// since.
+  pub fn checksum(
+    &self
+  ) -> Option<u32> {
+    self
+      .saved
+      .as_ref()
+      .map(|(_, checksum)| *checksum)
+  }
+
+  /// Writes the entries to `path`,
+  /// through a temporary file, unless
+  /// it already holds them unchanged.
+  /// With no entries the file is
+  /// removed instead. Returns the
+  /// checksum of the written contents,
+  /// if any.
+  pub fn save_to(
+    &mut self,
+    path: &Path
+  ) -> Result<Option<u32>> {
+    if self.entries.is_empty() {
+      self.saved = None;
+      if let Err(err) =
+        fs::remove_file(path)
+        && err.kind()
+          != ErrorKind::NotFound
+      {
+        return Err(err).with_context(
+          || {
+            format!(
+              "remove index file {:?}",
+              path
+            )
+          }
+        );
+      }
+      return Ok(None);
+    }
+    if let Some((saved, checksum)) =
+      &self.saved
+      && saved == path
+      && path.exists()
+    {
+      return Ok(Some(*checksum));
+    }
+    let bytes = encode(&self.entries);
+    if let Some(parent) = path.parent()
+    {
+      fs::create_dir_all(parent)
+        .with_context(|| {
+          format!(
+            "create index directory \
+             {:?}",
+            parent
+          )
+        })?;
+    }
+    storage::replace(path, &bytes)
+      .with_context(|| {
+        format!(
+          "write index to {:?}",
+          path
+        )
+      })?;
+    let checksum =
+      crc32fast::hash(&bytes);
+    self.saved = Some((
+      path.to_path_buf(),
+      checksum
+    ));
+    Ok(Some(checksum))
+  }
+}
+
+fn put_u32(
+  out: &mut Vec<u8>,
+  n: usize
+) {
+  out.extend_from_slice(
+    &(n as u32).to_le_bytes()
+  );
+}
+
+fn put_u64(
+  out: &mut Vec<u8>,
+  n: usize
+) {
+  out.extend_from_slice(
+    &(n as u64).to_le_bytes()
+  );
+}
+
+fn put_str(
+  out: &mut Vec<u8>,
+  s: &str
+) {
+  put_u32(out, s.len());
+  out.extend_from_slice(s.as_bytes());
+}
+
+fn put_vector(
+  out: &mut Vec<u8>,
+  vector: &SparseVector
+) {
+  let mut pairs: Vec<_> =
+    vector.iter().collect();
+  pairs.sort_by(|a, b| a.0.cmp(b.0));
+  put_u32(out, pairs.len());
+  for (term, weight) in pairs {
+    put_str(out, term);
+    out.extend_from_slice(
+      &weight.to_le_bytes()
+    );
+  }
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  pos:   usize
+}
+
+impl<'a> Reader<'a> {
+  fn take(
+    &mut self,
+    len: usize
+  ) -> Result<&'a [u8]> {
+    let slice = self
+      .bytes
+      .get(self.pos..self.pos + len)
+      .ok_or_else(|| {
+        anyhow!("truncated index file")
+      })?;
+    self.pos += len;
+    Ok(slice)
+  }
+
+  fn array<const N: usize>(
+    &mut self
+  ) -> Result<[u8; N]> {
+    Ok(self.take(N)?.try_into()?)
+  }
+
+  fn u8(&mut self) -> Result<u8> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn flag(&mut self) -> Result<bool> {
+    match self.u8()? {
+      | 0 => Ok(false),
+      | 1 => Ok(true),
+      | other => {
+        bail!(
+          "bad flag byte {} in index \
+           file",
+          other
+        )
+      }
+    }
+  }
+
+  fn u32(&mut self) -> Result<usize> {
+    Ok(
+      u32::from_le_bytes(self.array()?)
+        as usize
+    )
+  }
+
+  fn u64(&mut self) -> Result<usize> {
+    Ok(
+      u64::from_le_bytes(self.array()?)
+        as usize
+    )
+  }
+
+  fn vector(
+    &mut self
+  ) -> Result<SparseVector> {
+    let terms = self.u32()?;
+    let mut vector =
+      SparseVector::new();
+    for _ in 0..terms {
+      let term = self.string()?;
+      let weight = f32::from_le_bytes(
+        self.array()?
+      );
+      vector.insert(term, weight);
+    }
+    Ok(vector)
+  }
+
+  fn string(
+    &mut self
+  ) -> Result<String> {
+    let len = self.u32()?;
+    Ok(
+      std::str::from_utf8(
+        self.take(len)?
+      )
+      .context(
+        "index string is not UTF-8"
+      )?
+      .to_owned()
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn entries_round_trip() {
+    let entries = vec![
+      IndexEntry {
+        chunk_id:         "c1".into(),
+        doc_id:           "d1".into(),
+        vector:
+          SparseVector::from([
+            ("whale".to_string(), 0.75),
+            ("ship".to_string(), 0.25)
+          ]),
+        embed_input_hash: Some(
+          "abc".into()
+        ),
+        term_counts:      Some(
+          SparseVector::from([
+            ("whale".to_string(), 3.0),
+            ("ship".to_string(), 1.0)
+          ])
+        )
+      },
+      IndexEntry {
+        chunk_id:         "c2".into(),
+        doc_id:           "d1".into(),
+        vector:
+          SparseVector::new(),
+        embed_input_hash: None,
+        term_counts:      None
+      },
+    ];
+    let bytes = encode(&entries);
+    let decoded =
+      decode(&bytes).unwrap();
+    assert_eq!(
+      serde_json::to_value(&decoded)
+        .unwrap(),
+      serde_json::to_value(&entries)
+        .unwrap()
+    );
+    assert!(
+      decode(&bytes[..bytes.len() - 1])
+        .is_err()
+    );
+  }
+}
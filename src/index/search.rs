@@ -0,0 +1,310 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::{
+  Bm25Stats,
+  IndexEntry,
+  VectorIndex,
+  cosine_fn
+};
+use crate::config::IdfSmoothing;
+use crate::embedder::SparseVector;
+
+/// The customary BM25 term-count
+/// saturation, used unless configured.
+pub const BM25_K1: f32 = 1.2;
+/// The customary BM25 length
+/// normalization, used unless
+/// configured.
+pub const BM25_B: f32 = 0.75;
+
+impl VectorIndex {
+  pub fn search(
+    &self,
+    query: &SparseVector,
+    top_k: usize
+  ) -> Vec<(usize, f32)> {
+    self.search_where(
+      query,
+      top_k,
+      false,
+      |_| true
+    )
+  }
+
+  /// Ranks documents accepted by
+  /// `filter` by the cosine between
+  /// `query` and their pooled (mean)
+  /// chunk vector, returning at most
+  /// `top_n` IDs with a positive score.
+  pub fn top_documents(
+    &self,
+    query: &SparseVector,
+    top_n: usize,
+    high_precision: bool,
+    filter: impl Fn(&str) -> bool
+  ) -> Vec<&str> {
+    let cosine =
+      cosine_fn(high_precision);
+    let mut scored: Vec<(&str, f32)> =
+      self
+        .doc_vectors()
+        .iter()
+        .filter(|(doc_id, _)| {
+          filter(doc_id)
+        })
+        .map(|(doc_id, vector)| {
+          (
+            doc_id.as_str(),
+            cosine(query, vector)
+          )
+        })
+        .filter(|(_, score)| {
+          *score > 0.0
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+      b.1
+        .partial_cmp(&a.1)
+        .unwrap_or(Ordering::Equal)
+    });
+    scored
+      .into_iter()
+      .take(top_n)
+      .map(|(doc_id, _)| doc_id)
+      .collect()
+  }
+
+  fn doc_vectors(
+    &self
+  ) -> &[(String, SparseVector)] {
+    self.doc_vectors.get_or_init(|| {
+      let mut pooled: HashMap<
+        &str,
+        (SparseVector, usize)
+      > = HashMap::new();
+      for entry in &self.entries {
+        let (sum, count) = pooled
+          .entry(entry.doc_id.as_str())
+          .or_default();
+        for (token, value) in
+          &entry.vector
+        {
+          *sum
+            .entry(token.clone())
+            .or_insert(0.0) += value;
+        }
+        *count += 1;
+      }
+      pooled
+        .into_iter()
+        .map(
+          |(
+            doc_id,
+            (mut sum, count)
+          )| {
+            for value in
+              sum.values_mut()
+            {
+              *value /= count as f32;
+            }
+            (doc_id.to_string(), sum)
+          }
+        )
+        .collect()
+    })
+  }
+
+  /// Like [`VectorIndex::search`] but
+  /// only scores entries accepted by
+  /// `filter`, accumulating in `f64`
+  /// when `high_precision` is set.
+  pub fn search_where(
+    &self,
+    query: &SparseVector,
+    top_k: usize,
+    high_precision: bool,
+    filter: impl Fn(&IndexEntry) -> bool
+  ) -> Vec<(usize, f32)> {
+    if query.is_empty() {
+      return Vec::new();
+    }
+    let cosine =
+      cosine_fn(high_precision);
+    let mut scored: Vec<(usize, f32)> =
+      self
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+          filter(entry)
+        })
+        .map(|(idx, entry)| {
+          (
+            idx,
+            cosine(
+              query,
+              &entry.vector
+            )
+          )
+        })
+        .filter(|(_, score)| {
+          *score > 0.0
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+      b.1
+        .partial_cmp(&a.1)
+        .unwrap_or(Ordering::Equal)
+    });
+    scored.truncate(top_k);
+    scored
+  }
+
+  /// Ranks every entry by Okapi BM25
+  /// with [`BM25_K1`] and [`BM25_B`].
+  #[allow(dead_code)]
+  pub fn search_bm25(
+    &self,
+    query: &SparseVector,
+    top_k: usize
+  ) -> Vec<(usize, f32)> {
+    self.search_bm25_where(
+      query,
+      top_k,
+      BM25_K1,
+      BM25_B,
+      |_| true
+    )
+  }
+
+  /// Ranks entries accepted by `filter`
+  /// by Okapi BM25 over the query's
+  /// terms, with `k1` saturating term
+  /// counts and `b` scaling length
+  /// normalization. Entries stored
+  /// without term counts are taken to
+  /// be of average length, with counts
+  /// approximated as their weights
+  /// times that length.
+  pub fn search_bm25_where(
+    &self,
+    query: &SparseVector,
+    top_k: usize,
+    k1: f32,
+    b: f32,
+    filter: impl Fn(&IndexEntry) -> bool
+  ) -> Vec<(usize, f32)> {
+    if query.is_empty() {
+      return Vec::new();
+    }
+    let stats = self.bm25_stats();
+    let documents = self.entries.len();
+    let mut scored: Vec<(usize, f32)> =
+      self
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+          filter(entry)
+        })
+        .map(|(idx, entry)| {
+          let (counts, length, scale) =
+            match &entry.term_counts {
+              | Some(counts) => {
+                (
+                  counts,
+                  counts
+                    .values()
+                    .sum::<f32>(),
+                  1.0
+                )
+              }
+              | None => {
+                (
+                  &entry.vector,
+                  stats.avg_length,
+                  stats.avg_length
+                )
+              }
+            };
+          let norm = k1
+            * (1.0 - b
+              + b * length
+                / stats.avg_length);
+          let score = query
+            .keys()
+            .filter_map(|term| {
+              let tf = counts
+                .get(term)?
+                * scale;
+              let df = stats
+                .document_frequency
+                .get(term)
+                .copied()
+                .unwrap_or(0);
+              Some(
+                IdfSmoothing::Bm25
+                  .idf(documents, df)
+                  * tf
+                  * (k1 + 1.0)
+                  / (tf + norm)
+              )
+            })
+            .sum::<f32>();
+          (idx, score)
+        })
+        .filter(|(_, score)| {
+          *score > 0.0
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+      b.1
+        .partial_cmp(&a.1)
+        .unwrap_or(Ordering::Equal)
+    });
+    scored.truncate(top_k);
+    scored
+  }
+
+  fn bm25_stats(&self) -> &Bm25Stats {
+    self.bm25_stats.get_or_init(|| {
+      let mut document_frequency =
+        HashMap::new();
+      for entry in &self.entries {
+        for term in entry
+          .term_counts
+          .as_ref()
+          .unwrap_or(&entry.vector)
+          .keys()
+        {
+          *document_frequency
+            .entry(term.clone())
+            .or_insert(0) += 1;
+        }
+      }
+      let lengths: Vec<f32> = self
+        .entries
+        .iter()
+        .filter_map(|entry| {
+          entry.term_counts.as_ref()
+        })
+        .map(|counts| {
+          counts.values().sum()
+        })
+        .collect();
+      let avg_length =
+        if lengths.is_empty() {
+          1.0
+        } else {
+          (lengths.iter().sum::<f32>()
+            / lengths.len() as f32)
+            .max(1.0)
+        };
+      Bm25Stats {
+        document_frequency,
+        avg_length
+      }
+    })
+  }
+}
@@ -0,0 +1,76 @@
+use crate::embedder::SparseVector;
+
+pub fn cosine_similarity(
+  a: &SparseVector,
+  b: &SparseVector
+) -> f32 {
+  let mut dot = 0.0;
+  let mut norm_a = 0.0;
+  let mut norm_b = 0.0;
+  for value in a.values() {
+    norm_a += value * value;
+  }
+  for value in b.values() {
+    norm_b += value * value;
+  }
+  if norm_a == 0.0 || norm_b == 0.0 {
+    return 0.0;
+  }
+  for (token, a_val) in a {
+    if let Some(b_val) = b.get(token) {
+      dot += a_val * b_val;
+    }
+  }
+  dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// [`cosine_similarity`] with the dot
+/// product and norms accumulated in
+/// `f64`, so long vectors do not drift
+/// enough to reorder near-tied scores.
+pub fn cosine_similarity_f64(
+  a: &SparseVector,
+  b: &SparseVector
+) -> f32 {
+  let norm = |vector: &SparseVector| {
+    vector
+      .values()
+      .map(|value| {
+        f64::from(*value)
+          * f64::from(*value)
+      })
+      .sum::<f64>()
+  };
+  let norm_a = norm(a);
+  let norm_b = norm(b);
+  if norm_a == 0.0 || norm_b == 0.0 {
+    return 0.0;
+  }
+  let dot: f64 = a
+    .iter()
+    .filter_map(|(token, a_val)| {
+      b.get(token).map(|b_val| {
+        f64::from(*a_val)
+          * f64::from(*b_val)
+      })
+    })
+    .sum();
+  (dot
+    / (norm_a.sqrt() * norm_b.sqrt()))
+    as f32
+}
+
+/// The cosine function selected by
+/// `stage1.search.high_precision`.
+pub fn cosine_fn(
+  high_precision: bool
+) -> fn(
+  &SparseVector,
+  &SparseVector
+) -> f32 {
+  if high_precision {
+    cosine_similarity_f64
+  } else {
+    cosine_similarity
+  }
+}
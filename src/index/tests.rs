@@ -0,0 +1,246 @@
+use super::*;
+use crate::embedder::{
+  Embedder,
+  SparseVector,
+  TfEmbedder
+};
+
+#[test]
+fn stats_count_every_vector_entry() {
+  let embedder = TfEmbedder::new(1);
+  let mut index =
+    VectorIndex::from_entries(
+      Vec::new()
+    );
+  for (id, text) in [
+    ("c1", "alpha beta gamma"),
+    ("c2", "beta delta"),
+    ("c3", "alpha")
+  ] {
+    index.add_chunk(
+      id.into(),
+      "doc".into(),
+      embedder.embed(text),
+      None,
+      None
+    );
+  }
+  let stats = index.stats();
+  assert_eq!(
+    stats.nonzero_entries,
+    index
+      .entries()
+      .iter()
+      .map(|entry| entry.vector.len())
+      .sum::<usize>()
+  );
+  assert_eq!(stats.nonzero_entries, 6);
+  assert_eq!(stats.distinct_terms, 4);
+  assert_eq!(
+    stats.average_density,
+    0.5
+  );
+  assert!(stats.approx_bytes > 0);
+}
+
+#[test]
+fn search_returns_best_match_first() {
+  let mut index =
+    VectorIndex::from_entries(
+      Vec::new()
+    );
+  let mut vector = SparseVector::new();
+  vector.insert("a".into(), 1.0);
+  index.add_chunk(
+    "c1".into(),
+    "doc".into(),
+    vector,
+    None,
+    None
+  );
+  let mut vector2 = SparseVector::new();
+  vector2.insert("a".into(), 0.5);
+  vector2.insert("b".into(), 0.5);
+  index.add_chunk(
+    "c2".into(),
+    "doc".into(),
+    vector2,
+    None,
+    None
+  );
+  let query =
+    TfEmbedder::new(1).embed("a");
+  let results = index.search(&query, 2);
+  assert_eq!(results.len(), 2);
+  assert!(results[0].1 >= results[1].1);
+}
+
+#[test]
+fn bm25_prefers_rare_terms_and_short_chunks()
+ {
+  let embedder = TfEmbedder::new(1);
+  let mut index =
+    VectorIndex::from_entries(
+      Vec::new()
+    );
+  for (id, text) in [
+    ("short", "whale ship"),
+    (
+      "long",
+      "whale ship one two three four \
+       five six seven eight"
+    ),
+    ("rare", "ship sea")
+  ] {
+    index.add_chunk(
+      id.into(),
+      "doc".into(),
+      embedder.embed(text),
+      None,
+      Some(embedder.term_counts(text))
+    );
+  }
+  let ranked = |query: &str| {
+    index
+      .search_bm25_where(
+        &embedder.embed(query),
+        3,
+        1.2,
+        0.75,
+        |_| true
+      )
+      .into_iter()
+      .map(|(idx, _)| {
+        index.entries[idx]
+          .chunk_id
+          .as_str()
+      })
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(ranked("whale"), vec![
+    "short", "long"
+  ]);
+  assert_eq!(
+    ranked("whale sea"),
+    vec!["rare", "short", "long"]
+  );
+}
+
+#[test]
+fn search_bm25_uses_the_default_parameters()
+ {
+  let embedder = TfEmbedder::new(1);
+  let mut index =
+    VectorIndex::from_entries(
+      Vec::new()
+    );
+  for (id, text) in [
+    ("a", "whale ship"),
+    ("b", "whale whale sea sea sea"),
+    ("c", "cactus sand")
+  ] {
+    index.add_chunk(
+      id.into(),
+      "doc".into(),
+      embedder.embed(text),
+      None,
+      Some(embedder.term_counts(text))
+    );
+  }
+  let query =
+    embedder.embed("whale sea");
+  let ranked =
+    index.search_bm25(&query, 3);
+  assert_eq!(
+    ranked,
+    index.search_bm25_where(
+      &query,
+      3,
+      BM25_K1,
+      BM25_B,
+      |_| true
+    )
+  );
+  assert_eq!(
+    ranked
+      .iter()
+      .map(|(idx, _)| *idx)
+      .collect::<Vec<_>>(),
+    vec![1, 0]
+  );
+}
+
+#[test]
+fn vectors_read_old_and_compact_forms()
+{
+  let old = concat!(
+    r#"{"chunk_id":"c","doc_id":"d","#,
+    r#""vector":{"b":0.25,"a":0.75}}"#
+  );
+  let compact = concat!(
+    r#"{"chunk_id":"c","doc_id":"d","#,
+    r#""vector":{"terms":["a","b"],"#,
+    r#""weights":[0.75,0.25]}}"#
+  );
+  let from_old: IndexEntry =
+    serde_json::from_str(old).unwrap();
+  let from_compact: IndexEntry =
+    serde_json::from_str(compact)
+      .unwrap();
+  assert_eq!(
+    from_old.vector,
+    from_compact.vector
+  );
+  assert_eq!(
+    serde_json::to_string(&from_old)
+      .unwrap(),
+    compact
+  );
+}
+
+#[test]
+fn high_precision_breaks_near_ties_correctly()
+ {
+  let vector = |a: f32, b: f32| {
+    SparseVector::from([
+      ("a".to_string(), a),
+      ("b".to_string(), b)
+    ])
+  };
+  let mut index =
+    VectorIndex::from_entries(
+      Vec::new()
+    );
+  // Exact cosines with the query are
+  // 0.99999997 for c1 and 0.99999994
+  // for c2, but f32 sums rank c2
+  // first.
+  index.add_chunk(
+    "c2".into(),
+    "doc".into(),
+    vector(1453.0, 8700.0),
+    None,
+    None
+  );
+  index.add_chunk(
+    "c1".into(),
+    "doc".into(),
+    vector(484.0, 2900.0),
+    None,
+    None
+  );
+  let query = vector(1.0, 6.0);
+  let best = |high_precision| {
+    let (idx, _) = index.search_where(
+      &query,
+      1,
+      high_precision,
+      |_| true
+    )[0];
+    index.entries()[idx]
+      .chunk_id
+      .clone()
+  };
+  assert_eq!(best(false), "c2");
+  assert_eq!(best(true), "c1");
+}
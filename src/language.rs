@@ -0,0 +1,80 @@
+/// Detects the language of `text` and
+/// returns its ISO 639-3 code, or
+/// `None` when detection is unreliable
+/// or the `language-detection` feature
+/// is off.
+#[cfg(feature = "language-detection")]
+pub fn detect(
+  text: &str
+) -> Option<String> {
+  whatlang::detect(text)
+    .filter(|info| info.is_reliable())
+    .map(|info| {
+      info.lang().code().to_string()
+    })
+}
+
+#[cfg(not(
+  feature = "language-detection"
+))]
+pub fn detect(
+  _text: &str
+) -> Option<String> {
+  None
+}
+
+pub fn is_supported() -> bool {
+  cfg!(feature = "language-detection")
+}
+
+/// Common function words of `language`
+/// (an ISO 639-3 code), sorted for
+/// binary search; empty for languages
+/// without a list.
+pub fn stopwords(
+  language: &str
+) -> &'static [&'static str] {
+  match language {
+    | "eng" => ENGLISH_STOPWORDS,
+    | "spa" => SPANISH_STOPWORDS,
+    | "fra" => FRENCH_STOPWORDS,
+    | "deu" => GERMAN_STOPWORDS,
+    | _ => &[]
+  }
+}
+
+const ENGLISH_STOPWORDS: &[&str] = &[
+  "a", "an", "and", "are", "as", "at",
+  "be", "but", "by", "for", "from",
+  "has", "have", "he", "her", "his",
+  "in", "is", "it", "its", "of", "on",
+  "or", "she", "that", "the", "their",
+  "they", "this", "to", "was", "were",
+  "which", "will", "with"
+];
+
+const SPANISH_STOPWORDS: &[&str] = &[
+  "a", "al", "como", "con", "de",
+  "del", "el", "ella", "en", "es",
+  "esta", "este", "la", "las", "lo",
+  "los", "más", "no", "o", "para",
+  "pero", "por", "que", "se", "su",
+  "sus", "un", "una", "y"
+];
+
+const FRENCH_STOPWORDS: &[&str] = &[
+  "au", "aux", "avec", "ce", "dans",
+  "de", "des", "du", "elle", "en",
+  "est", "et", "il", "la", "le", "les",
+  "leur", "mais", "ne", "ou", "par",
+  "pas", "pour", "qui", "sur", "un",
+  "une"
+];
+
+const GERMAN_STOPWORDS: &[&str] = &[
+  "aber", "auf", "aus", "bei", "das",
+  "dem", "den", "der", "des", "die",
+  "ein", "eine", "einen", "er", "es",
+  "für", "ist", "mit", "nicht", "oder",
+  "sie", "sind", "und", "von", "zu"
+];
@@ -0,0 +1,182 @@
+/// Irregular English forms and their
+/// dictionary lemmas, sorted by form
+/// for binary search.
+const IRREGULAR: &[(&str, &str)] = &[
+  ("are", "be"),
+  ("ate", "eat"),
+  ("best", "good"),
+  ("better", "good"),
+  ("bought", "buy"),
+  ("brought", "bring"),
+  ("came", "come"),
+  ("children", "child"),
+  ("did", "do"),
+  ("does", "do"),
+  ("done", "do"),
+  ("eaten", "eat"),
+  ("elder", "old"),
+  ("eldest", "old"),
+  ("farther", "far"),
+  ("farthest", "far"),
+  ("feet", "foot"),
+  ("found", "find"),
+  ("further", "far"),
+  ("furthest", "far"),
+  ("geese", "goose"),
+  ("gone", "go"),
+  ("got", "get"),
+  ("had", "have"),
+  ("has", "have"),
+  ("is", "be"),
+  ("knew", "know"),
+  ("knives", "knife"),
+  ("known", "know"),
+  ("least", "little"),
+  ("leaves", "leaf"),
+  ("less", "little"),
+  ("lice", "louse"),
+  ("lives", "life"),
+  ("made", "make"),
+  ("men", "man"),
+  ("mice", "mouse"),
+  ("more", "much"),
+  ("most", "much"),
+  ("oxen", "ox"),
+  ("people", "person"),
+  ("ran", "run"),
+  ("said", "say"),
+  ("saw", "see"),
+  ("seen", "see"),
+  ("taken", "take"),
+  ("teeth", "tooth"),
+  ("thought", "think"),
+  ("took", "take"),
+  ("was", "be"),
+  ("went", "go"),
+  ("were", "be"),
+  ("wives", "wife"),
+  ("wolves", "wolf"),
+  ("women", "woman"),
+  ("worse", "bad"),
+  ("worst", "bad"),
+  ("written", "write"),
+  ("wrote", "write")
+];
+
+/// Words ending in `s` that are not
+/// plurals.
+const NOT_PLURAL: &[&str] = &[
+  "always", "analysis", "basis", "bus",
+  "gas", "lens", "news", "perhaps",
+  "physics", "series", "species",
+  "this", "thus", "towards"
+];
+
+/// Maps an inflected lowercase English
+/// word to its dictionary lemma:
+/// irregular forms ("better" → "good",
+/// "mice" → "mouse") come from a table
+/// and regular noun plurals have their
+/// suffix removed. Other words are
+/// returned unchanged.
+pub fn lemmatize(word: &str) -> String {
+  if let Ok(idx) = IRREGULAR
+    .binary_search_by_key(
+      &word,
+      |(form, _)| *form
+    )
+  {
+    return IRREGULAR[idx].1.to_string();
+  }
+  singular(word)
+    .unwrap_or_else(|| word.to_string())
+}
+
+/// The singular of a regular plural, or
+/// `None` when `word` does not look
+/// like one.
+fn singular(
+  word: &str
+) -> Option<String> {
+  if word.len() <= 3
+    || !word
+      .bytes()
+      .all(|b| b.is_ascii_lowercase())
+    || NOT_PLURAL.contains(&word)
+  {
+    return None;
+  }
+  if let Some(stem) =
+    word.strip_suffix("ies")
+  {
+    return Some(format!("{}y", stem));
+  }
+  for suffix in [
+    "sses", "xes", "zes", "ches",
+    "shes"
+  ] {
+    if word.ends_with(suffix) {
+      return Some(
+        word[..word.len() - 2]
+          .to_string()
+      );
+    }
+  }
+  if ["ss", "us", "is"]
+    .iter()
+    .any(|end| word.ends_with(end))
+  {
+    return None;
+  }
+  word
+    .strip_suffix('s')
+    .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn irregular_table_is_sorted() {
+    assert!(IRREGULAR.windows(2).all(
+      |pair| pair[0].0 < pair[1].0
+    ));
+  }
+
+  #[test]
+  fn irregular_forms_map_to_lemmas() {
+    for (form, lemma) in [
+      ("mice", "mouse"),
+      ("geese", "goose"),
+      ("children", "child"),
+      ("better", "good"),
+      ("worst", "bad"),
+      ("further", "far")
+    ] {
+      assert_eq!(
+        lemmatize(form),
+        lemma
+      );
+    }
+  }
+
+  #[test]
+  fn regular_plurals_are_singularized()
+  {
+    for (form, lemma) in [
+      ("whales", "whale"),
+      ("studies", "study"),
+      ("boxes", "box"),
+      ("classes", "class"),
+      ("glass", "glass"),
+      ("series", "series"),
+      ("mouse", "mouse")
+    ] {
+      assert_eq!(
+        lemmatize(form),
+        lemma
+      );
+    }
+  }
+}
@@ -1,15 +1,25 @@
 mod args;
 mod chunk;
 mod config;
+mod corpus_stats;
 mod embedder;
 mod evaluation;
+mod frontmatter;
 mod index;
+mod language;
+mod lemma;
+mod minhash;
 mod normalization;
 mod pipeline;
+mod rng;
 mod search;
+mod section;
 mod stage3;
 mod stage4;
 mod state;
+mod storage;
+mod summary;
+mod tokenizer;
 
 use anyhow::Result;
 use clap::Parser;
@@ -18,9 +28,12 @@ use crate::args::Cli;
 use crate::config::Config;
 
 fn main() -> Result<()> {
-  let config =
-    Config::load("oxbed-config.toml")
-      .unwrap_or_default();
   let cli = Cli::parse();
+  let mut config = Config::resolve(
+    cli.config.as_deref()
+  )?;
+  if cli.seed.is_some() {
+    config.stage1.seed = cli.seed;
+  }
   pipeline::run(cli.command, config)
 }
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{
+  Hash,
+  Hasher
+};
+
+/// Words per shingle.
+const SHINGLE_SIZE: usize = 3;
+
+/// MinHash signatures split into LSH
+/// bands: `bands * rows` hash
+/// functions, where two texts become
+/// candidates when all `rows` values
+/// of any band agree.
+#[derive(Clone, Copy, Debug)]
+pub struct MinHasher {
+  bands: usize,
+  rows:  usize
+}
+
+impl MinHasher {
+  pub fn new(
+    bands: usize,
+    rows: usize
+  ) -> Self {
+    Self {
+      bands: bands.max(1),
+      rows:  rows.max(1)
+    }
+  }
+
+  /// Minimum of each hash function
+  /// over the word shingles of `text`.
+  /// Texts shorter than a shingle are
+  /// hashed as one shingle.
+  pub fn signature(
+    &self,
+    text: &str
+  ) -> Vec<u64> {
+    let words: Vec<&str> =
+      text.split_whitespace().collect();
+    let shingles: Vec<u64> = words
+      .windows(
+        SHINGLE_SIZE
+          .min(words.len())
+          .max(1)
+      )
+      .map(hash_of)
+      .collect();
+    (0..self.bands * self.rows)
+      .map(|seed| {
+        shingles
+          .iter()
+          .map(|shingle| {
+            mix(
+              *shingle
+                ^ mix(seed as u64)
+            )
+          })
+          .min()
+          .unwrap_or(u64::MAX)
+      })
+      .collect()
+  }
+
+  /// One `(band, key)` per band; equal
+  /// keys in a band share a bucket.
+  pub fn band_keys(
+    &self,
+    signature: &[u64]
+  ) -> Vec<(usize, u64)> {
+    signature
+      .chunks(self.rows)
+      .take(self.bands)
+      .map(hash_of)
+      .enumerate()
+      .collect()
+  }
+}
+
+/// Estimated Jaccard similarity: the
+/// share of positions where the two
+/// signatures agree.
+pub fn similarity(
+  a: &[u64],
+  b: &[u64]
+) -> f32 {
+  if a.is_empty() || a.len() != b.len()
+  {
+    return 0.0;
+  }
+  let equal = a
+    .iter()
+    .zip(b)
+    .filter(|(x, y)| x == y)
+    .count();
+  equal as f32 / a.len() as f32
+}
+
+/// Signatures bucketed by band, so a
+/// lookup only compares against texts
+/// sharing at least one bucket.
+pub struct LshIndex<K> {
+  hasher:  MinHasher,
+  items:   Vec<(K, Vec<u64>)>,
+  buckets:
+    HashMap<(usize, u64), Vec<usize>>
+}
+
+impl<K> LshIndex<K> {
+  pub fn new(
+    hasher: MinHasher
+  ) -> Self {
+    Self {
+      hasher,
+      items: Vec::new(),
+      buckets: HashMap::new()
+    }
+  }
+
+  pub fn hasher(&self) -> &MinHasher {
+    &self.hasher
+  }
+
+  pub fn insert(
+    &mut self,
+    key: K,
+    signature: Vec<u64>
+  ) {
+    let idx = self.items.len();
+    for band in
+      self.hasher.band_keys(&signature)
+    {
+      self
+        .buckets
+        .entry(band)
+        .or_default()
+        .push(idx);
+    }
+    self.items.push((key, signature));
+  }
+
+  /// The most similar stored text whose
+  /// estimated similarity reaches
+  /// `threshold`, among those sharing a
+  /// bucket with `signature`.
+  pub fn find(
+    &self,
+    signature: &[u64],
+    threshold: f32
+  ) -> Option<(&K, f32)> {
+    let mut candidates: Vec<usize> =
+      self
+        .hasher
+        .band_keys(signature)
+        .iter()
+        .filter_map(|band| {
+          self.buckets.get(band)
+        })
+        .flatten()
+        .copied()
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+      .into_iter()
+      .map(|idx| {
+        let (key, other) =
+          &self.items[idx];
+        (
+          key,
+          similarity(signature, other)
+        )
+      })
+      .filter(|(_, score)| {
+        *score >= threshold
+      })
+      .max_by(|a, b| {
+        a.1.total_cmp(&b.1)
+      })
+  }
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  value.hash(&mut hasher);
+  hasher.finish()
+}
+
+/// SplitMix64 finalizer, used to derive
+/// the independent hash functions.
+fn mix(mut x: u64) -> u64 {
+  x = x.wrapping_add(
+    0x9e37_79b9_7f4a_7c15
+  );
+  x = (x ^ (x >> 30)).wrapping_mul(
+    0xbf58_476d_1ce4_e5b9
+  );
+  x = (x ^ (x >> 27)).wrapping_mul(
+    0x94d0_49bb_1331_11eb
+  );
+  x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reshuffled_paragraphs_share_a_bucket()
+   {
+    let paragraphs = [
+      "the whale rose from the grey \
+       water and the crew watched it \
+       circle the ship in silence",
+      "ahab paced the deck through \
+       the night muttering about the \
+       white whale and his lost leg",
+      "at dawn the harpooners \
+       sharpened their irons and the \
+       mates checked every line and \
+       boat",
+      "ishmael wrote in his journal \
+       about the sea the sky and the \
+       strange fellowship aboard"
+    ];
+    let original =
+      paragraphs.join("\n\n");
+    let reshuffled = [
+      paragraphs[2],
+      paragraphs[0],
+      paragraphs[3],
+      paragraphs[1]
+    ]
+    .join("\n\n");
+    let unrelated =
+      "desert caravans crossed the \
+       dunes at night guided by stars \
+       and old songs";
+    let hasher = MinHasher::new(16, 4);
+    let a = hasher.signature(&original);
+    let b =
+      hasher.signature(&reshuffled);
+    let shared = hasher
+      .band_keys(&a)
+      .into_iter()
+      .filter(|band| {
+        hasher
+          .band_keys(&b)
+          .contains(band)
+      })
+      .count();
+    assert!(shared > 0);
+    let mut index =
+      LshIndex::new(hasher);
+    index.insert("original", a);
+    index.insert(
+      "unrelated",
+      hasher.signature(unrelated)
+    );
+    let (key, score) = index
+      .find(&b, 0.7)
+      .expect("near duplicate");
+    assert_eq!(*key, "original");
+    assert!(score >= 0.7);
+    assert!(
+      index
+        .find(
+          &hasher.signature(
+            "a completely different \
+             text about mountains and \
+             rivers"
+          ),
+          0.7
+        )
+        .is_none()
+    );
+  }
+}
@@ -0,0 +1,67 @@
+use std::ops::Range;
+use std::path::Path;
+
+/// Opens and closes a fenced code
+/// block.
+const CODE_FENCE: &str = "```";
+
+/// Extensions of Markdown files.
+const MARKDOWN_EXTENSIONS: [&str; 2] =
+  ["md", "markdown"];
+
+/// Whether `path` has a Markdown
+/// extension.
+pub fn is_markdown(
+  path: &Path
+) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| {
+      MARKDOWN_EXTENSIONS.contains(
+        &ext.to_lowercase().as_str()
+      )
+    })
+}
+
+/// Byte ranges of ```-fenced code
+/// blocks, from the opening fence line
+/// through the closing one (or the end
+/// of `input` when unterminated).
+/// Fences may be indented by up to
+/// three spaces.
+pub fn code_blocks(
+  input: &str
+) -> Vec<Range<usize>> {
+  let mut blocks = Vec::new();
+  let mut open = None;
+  let mut offset = 0;
+  for line in
+    input.split_inclusive('\n')
+  {
+    let indent = line.len()
+      - line
+        .trim_start_matches(' ')
+        .len();
+    let is_fence = indent <= 3
+      && line[indent..]
+        .starts_with(CODE_FENCE);
+    if is_fence {
+      match open.take() {
+        | Some(start) => {
+          blocks.push(
+            start
+              ..offset
+                + line.trim_end().len()
+          );
+        }
+        | None => open = Some(offset)
+      }
+    }
+    offset += line.len();
+  }
+  if let Some(start) = open {
+    blocks.push(start..input.len());
+  }
+  blocks
+}
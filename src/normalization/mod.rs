@@ -0,0 +1,372 @@
+use std::ops::Range;
+use std::path::Path;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::{
+  PatternPolicy,
+  Stage1Normalization
+};
+
+mod markdown;
+mod words;
+
+pub use self::markdown::{
+  code_blocks,
+  is_markdown
+};
+use self::words::{
+  hyphenated_breaks,
+  is_email,
+  words
+};
+
+/// Sentinel replacing URLs under
+/// [`PatternPolicy::Token`].
+pub const URL_TOKEN: &str = "<url>";
+
+/// Sentinel replacing email addresses
+/// under [`PatternPolicy::Token`].
+pub const EMAIL_TOKEN: &str = "<email>";
+
+/// Applies NFKC, collapses runs of
+/// whitespace and blank lines, and
+/// optionally rejoins words hyphenated
+/// across line breaks and drops or
+/// masks URLs and email addresses.
+/// ```-fenced code blocks can be kept
+/// verbatim.
+#[derive(Clone, Debug, Default)]
+pub struct Normalizer {
+  join_hyphenated_linebreaks: bool,
+  keep_linebreak_hyphen:      bool,
+  url_policy: PatternPolicy,
+  email_policy: PatternPolicy,
+  preserve_code_blocks:       bool
+}
+
+/// A span of raw input emitted as
+/// `text` instead of being normalized.
+struct Replacement {
+  range: Range<usize>,
+  text:  &'static str
+}
+
+/// Maps each byte of normalized text
+/// back to the byte span of the raw
+/// input it was produced from.
+#[derive(Debug, Default)]
+pub struct OffsetMap {
+  spans: Vec<(usize, usize)>
+}
+
+impl OffsetMap {
+  /// Raw byte range covering the
+  /// normalized range `start..end`.
+  pub fn raw_range(
+    &self,
+    start: usize,
+    end: usize
+  ) -> Option<(usize, usize)> {
+    if start >= end {
+      return None;
+    }
+    let first =
+      self.spans.get(start)?;
+    let last =
+      self.spans.get(end - 1)?;
+    Some((first.0, last.1))
+  }
+}
+
+impl Normalizer {
+  pub fn from_config(
+    config: &Stage1Normalization
+  ) -> Self {
+    Self {
+      join_hyphenated_linebreaks:
+        config
+          .join_hyphenated_linebreaks,
+      keep_linebreak_hyphen:
+        config.keep_linebreak_hyphen,
+      url_policy:
+        config.url_policy,
+      email_policy:
+        config.email_policy,
+      preserve_code_blocks:
+        config.preserve_code_blocks
+    }
+  }
+
+  /// This normalizer as applied to the
+  /// file at `path`: code blocks are
+  /// only preserved in Markdown files.
+  pub fn for_file(
+    &self,
+    path: &Path
+  ) -> Self {
+    Self {
+      preserve_code_blocks: self
+        .preserve_code_blocks
+        && is_markdown(path),
+      ..self.clone()
+    }
+  }
+
+  pub fn normalize(
+    &self,
+    input: &str
+  ) -> String {
+    self.normalize_with_offsets(input).0
+  }
+
+  /// Normalizes `input` like
+  /// [`Normalizer::normalize`] and
+  /// records where every output byte
+  /// came from. NFKC is applied per
+  /// grapheme cluster so each output
+  /// character can be traced to its
+  /// source cluster.
+  pub fn normalize_with_offsets(
+    &self,
+    input: &str
+  ) -> (String, OffsetMap) {
+    let mut replacements =
+      self.patterns(input);
+    if self.join_hyphenated_linebreaks {
+      replacements.extend(
+        hyphenated_breaks(
+          input,
+          self.keep_linebreak_hyphen
+        )
+      );
+    }
+    replacements
+      .sort_by_key(|r| r.range.start);
+    let verbatim =
+      if self.preserve_code_blocks {
+        code_blocks(input)
+      } else {
+        Vec::new()
+      };
+    let mut covered = 0;
+    replacements.retain(|r| {
+      let keep = r.range.start
+        >= covered
+        && !verbatim.iter().any(
+          |block| {
+            block.start < r.range.end
+              && r.range.start
+                < block.end
+          }
+        );
+      if keep {
+        covered = r.range.end;
+      }
+      keep
+    });
+    normalize_replacing(
+      input,
+      &replacements,
+      &verbatim
+    )
+  }
+
+  /// Replacements for whitespace
+  /// separated words that look like
+  /// URLs (`http://`, `https://`,
+  /// `www.`) or email addresses,
+  /// ignoring surrounding brackets,
+  /// quotes and trailing punctuation.
+  fn patterns(
+    &self,
+    input: &str
+  ) -> Vec<Replacement> {
+    let mut replacements = Vec::new();
+    if self.url_policy
+      == PatternPolicy::Keep
+      && self.email_policy
+        == PatternPolicy::Keep
+    {
+      return replacements;
+    }
+    for (start, word) in words(input) {
+      let core = word
+        .trim_start_matches([
+          '(', '[', '<', '"', '\''
+        ]);
+      let start =
+        start + word.len() - core.len();
+      let core =
+        core.trim_end_matches([
+          '.', ',', ';', ':', '!', '?',
+          ')', ']', '>', '"', '\''
+        ]);
+      let lower = core.to_lowercase();
+      let (policy, token) = if [
+        "http://", "https://", "www."
+      ]
+      .iter()
+      .any(|prefix| {
+        lower.starts_with(prefix)
+      }) {
+        (self.url_policy, URL_TOKEN)
+      } else if is_email(core) {
+        (self.email_policy, EMAIL_TOKEN)
+      } else {
+        continue;
+      };
+      let text = match policy {
+        | PatternPolicy::Keep => {
+          continue
+        }
+        | PatternPolicy::Drop => "",
+        | PatternPolicy::Token => token
+      };
+      replacements.push(Replacement {
+        range: start
+          ..start + core.len(),
+        text
+      });
+    }
+    replacements
+  }
+}
+
+/// Normalizes `input`, emitting each
+/// replacement's text (mapped to its
+/// whole raw span) in place of the
+/// graphemes it covers and copying
+/// `verbatim` ranges unchanged (except
+/// for dropped `\r`).
+fn normalize_replacing(
+  input: &str,
+  replacements: &[Replacement],
+  verbatim: &[Range<usize>]
+) -> (String, OffsetMap) {
+  let mut replacements =
+    replacements.iter().peekable();
+  let mut normalized =
+    String::with_capacity(input.len());
+  let mut spans =
+    Vec::with_capacity(input.len());
+  let mut last_was_space = false;
+  let mut newline_count = 0;
+  for (raw_start, grapheme) in
+    input.grapheme_indices(true)
+  {
+    while replacements
+      .next_if(|r| {
+        r.range.end <= raw_start
+      })
+      .is_some()
+    {}
+    if let Some(r) = replacements.peek()
+      && r.range.contains(&raw_start)
+    {
+      if raw_start == r.range.start
+        && !r.text.is_empty()
+      {
+        normalized.push_str(r.text);
+        spans.extend(
+          std::iter::repeat_n(
+            (
+              r.range.start,
+              r.range.end
+            ),
+            r.text.len()
+          )
+        );
+        newline_count = 0;
+        last_was_space = false;
+      }
+      continue;
+    }
+    let span = (
+      raw_start,
+      raw_start + grapheme.len()
+    );
+    if verbatim.iter().any(|block| {
+      block.contains(&raw_start)
+    }) {
+      let kept =
+        grapheme.replace('\r', "");
+      normalized.push_str(&kept);
+      spans.extend(
+        std::iter::repeat_n(
+          span,
+          kept.len()
+        )
+      );
+      newline_count = 0;
+      last_was_space = kept
+        .chars()
+        .next_back()
+        .is_some_and(
+          char::is_whitespace
+        );
+      continue;
+    }
+    for ch in grapheme.nfkc() {
+      let pushed = match ch {
+        | '\r' => continue,
+        | '\n' => {
+          let pushed =
+            if newline_count < 2 {
+              normalized.push('\n');
+              Some('\n')
+            } else {
+              None
+            };
+          newline_count += 1;
+          last_was_space = true;
+          pushed
+        }
+        | c if c.is_whitespace() => {
+          if newline_count > 0 {
+            newline_count = 0;
+          }
+          if !last_was_space {
+            normalized.push(' ');
+            last_was_space = true;
+            Some(' ')
+          } else {
+            None
+          }
+        }
+        | other => {
+          newline_count = 0;
+          normalized.push(other);
+          last_was_space = false;
+          Some(other)
+        }
+      };
+      if let Some(pushed) = pushed {
+        spans.extend(
+          std::iter::repeat_n(
+            span,
+            pushed.len_utf8()
+          )
+        );
+      }
+    }
+  }
+  let leading = normalized.len()
+    - normalized.trim_start().len();
+  let trimmed_len = normalized
+    .trim_end()
+    .len()
+    .max(leading);
+  spans.truncate(trimmed_len);
+  spans.drain(..leading);
+  let text = normalized
+    [leading..trimmed_len]
+    .to_string();
+  (text, OffsetMap {
+    spans
+  })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,129 @@
+use super::*;
+
+fn normalize(raw: &str) -> String {
+  Normalizer::default().normalize(raw)
+}
+
+#[test]
+fn normalize_collapses_whitespace_and_nfkc()
+ {
+  let raw = "\u{FB01}   bar baz";
+  let normalized = normalize(raw);
+  assert_eq!(normalized, "fi bar baz");
+}
+
+#[test]
+fn offsets_map_back_to_raw_text() {
+  let raw =
+    "  alpha \t beta\r\n\r\n\r\ngamma";
+  let (normalized, offsets) =
+    Normalizer::default()
+      .normalize_with_offsets(raw);
+  assert_eq!(
+    normalized,
+    "alpha beta\n\ngamma"
+  );
+  let start =
+    normalized.find("beta").unwrap();
+  let (raw_start, raw_end) = offsets
+    .raw_range(start, normalized.len())
+    .unwrap();
+  assert_eq!(
+    &raw[raw_start..raw_end],
+    "beta\r\n\r\n\r\ngamma"
+  );
+}
+
+#[test]
+fn hyphenated_linebreaks_are_rejoined()
+{
+  let mut config =
+    Stage1Normalization {
+      join_hyphenated_linebreaks: true,
+      ..Default::default()
+    };
+  let raw = "the inter-\n  national \
+             well-known co-\n 42";
+  assert_eq!(
+    Normalizer::from_config(&config)
+      .normalize(raw),
+    "the international well-known \
+     co-\n42"
+  );
+  config.keep_linebreak_hyphen = true;
+  let (normalized, offsets) =
+    Normalizer::from_config(&config)
+      .normalize_with_offsets(
+        "inter-\r\nnational"
+      );
+  assert_eq!(
+    normalized,
+    "inter-national"
+  );
+  assert_eq!(
+    offsets.raw_range(6, 14),
+    Some((8, 16))
+  );
+}
+
+#[test]
+fn urls_and_emails_follow_their_policy()
+{
+  let raw =
+    "see (https://example.com/a?b=1), \
+     or mail ops@example.org.";
+  let mut config =
+    Stage1Normalization {
+      url_policy: PatternPolicy::Token,
+      email_policy:
+        PatternPolicy::Token,
+      ..Default::default()
+    };
+  assert_eq!(
+    Normalizer::from_config(&config)
+      .normalize(raw),
+    "see (<url>), or mail <email>."
+  );
+  config.url_policy =
+    PatternPolicy::Drop;
+  config.email_policy =
+    PatternPolicy::Keep;
+  assert_eq!(
+    Normalizer::from_config(&config)
+      .normalize(raw),
+    "see (), or mail ops@example.org."
+  );
+}
+
+#[test]
+fn markdown_code_blocks_keep_their_layout()
+ {
+  let config = Stage1Normalization {
+    preserve_code_blocks: true,
+    ..Default::default()
+  };
+  let raw = concat!(
+    "Intro   text\n\n```rust\n",
+    "fn main() {\n    let  x = 1;",
+    "\n\n\n    x\n}\n```\n",
+    "After    code"
+  );
+  let normalizer =
+    Normalizer::from_config(&config);
+  assert_eq!(
+    normalizer
+      .for_file(Path::new("notes.md"))
+      .normalize(raw),
+    "Intro text\n\n```rust\nfn main() \
+     {\n    let  x = 1;\n\n\n    \
+     x\n}\n```\nAfter code"
+  );
+  assert_eq!(
+    normalizer
+      .for_file(Path::new("notes.txt"))
+      .normalize(raw),
+    "Intro text\n\n```rust\nfn main() \
+     {\nlet x = \
+     1;\n\nx\n}\n```\nAfter code"
+  );
+}
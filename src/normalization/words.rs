@@ -0,0 +1,92 @@
+use super::Replacement;
+
+/// Byte offset and text of every
+/// whitespace separated word.
+pub(super) fn words(
+  input: &str
+) -> Vec<(usize, &str)> {
+  let mut words = Vec::new();
+  let mut start = None;
+  for (idx, ch) in input.char_indices()
+  {
+    if ch.is_whitespace() {
+      if let Some(s) = start.take() {
+        words.push((s, &input[s..idx]));
+      }
+    } else if start.is_none() {
+      start = Some(idx);
+    }
+  }
+  if let Some(s) = start {
+    words.push((s, &input[s..]));
+  }
+  words
+}
+
+pub(super) fn is_email(
+  word: &str
+) -> bool {
+  let Some((local, domain)) =
+    word.split_once('@')
+  else {
+    return false;
+  };
+  !local.is_empty()
+    && !domain.contains('@')
+    && domain.contains('.')
+    && !domain.starts_with('.')
+    && !domain.ends_with('.')
+}
+
+/// Raw spans to drop so that
+/// `word-\nword` reads `wordword` (or
+/// `word-word` when `keep_hyphen`).
+/// Only hyphens between letters count,
+/// and indentation on the next line is
+/// dropped with the break.
+pub(super) fn hyphenated_breaks(
+  input: &str,
+  keep_hyphen: bool
+) -> Vec<Replacement> {
+  let mut breaks = Vec::new();
+  for (idx, _) in
+    input.match_indices('-')
+  {
+    let after_letter = input[..idx]
+      .chars()
+      .next_back()
+      .is_some_and(char::is_alphabetic);
+    let rest = &input[idx + 1..];
+    let Some(next_line) = rest
+      .strip_prefix("\r\n")
+      .or_else(|| {
+        rest.strip_prefix('\n')
+      })
+    else {
+      continue;
+    };
+    let next_line = next_line
+      .trim_start_matches([' ', '\t']);
+    if !after_letter
+      || !next_line
+        .chars()
+        .next()
+        .is_some_and(
+          char::is_alphabetic
+        )
+    {
+      continue;
+    }
+    let start = if keep_hyphen {
+      idx + 1
+    } else {
+      idx
+    };
+    breaks.push(Replacement {
+      range: start
+        ..input.len() - next_line.len(),
+      text:  ""
+    });
+  }
+  breaks
+}
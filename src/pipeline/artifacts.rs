@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::fs::{
+  self,
+  File
+};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{
+  Context,
+  Result
+};
+
+use crate::chunk::Chunk;
+use crate::storage;
+
+pub(super) fn accumulate_word_counts(
+  counts: &mut HashMap<String, usize>,
+  text: &str
+) {
+  for token in text
+    .split(|c: char| {
+      !c.is_alphanumeric()
+    })
+    .filter(|part| !part.is_empty())
+  {
+    let word = token.to_lowercase();
+    *counts.entry(word).or_insert(0) +=
+      1;
+  }
+}
+
+pub(super) fn emit_word_tally_csv(
+  path: &Path,
+  counts: &HashMap<String, usize>
+) -> Result<()> {
+  let mut entries: Vec<_> =
+    counts.iter().collect();
+  entries.sort_by(|a, b| {
+    b.1
+      .cmp(a.1)
+      .then_with(|| a.0.cmp(b.0))
+  });
+  let mut file = File::create(path)?;
+  writeln!(file, "word,count")?;
+  for (word, count) in entries {
+    writeln!(
+      file,
+      "{},{}",
+      word, count
+    )?;
+  }
+  Ok(())
+}
+
+pub(super) fn ensure_parent(
+  path: &Path
+) -> Result<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)
+      .with_context(|| {
+        format!(
+          "create directory {:?}",
+          parent
+        )
+      })?;
+  }
+  Ok(())
+}
+
+pub(super) fn emit_chunks_jsonl(
+  chunks: &[Chunk],
+  path: &Path
+) -> Result<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)
+      .with_context(|| {
+        format!(
+          "create chunk data \
+           directory {:?}",
+          parent
+        )
+      })?;
+  }
+  let mut contents = Vec::new();
+  for chunk in chunks {
+    serde_json::to_writer(
+      &mut contents,
+      chunk
+    )?;
+    writeln!(contents)?;
+  }
+  storage::write(path, &contents)
+}
@@ -0,0 +1,107 @@
+use anyhow::{
+  Context,
+  Result
+};
+use chrono::Utc;
+
+use crate::config::Config;
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::search::{
+  SearchFilter,
+  search_hits
+};
+use crate::state::{
+  Collection,
+  State
+};
+
+/// Runs `query` and stores the chunk
+/// IDs of its hits, in rank order, as
+/// collection `name`, replacing any
+/// collection of that name.
+pub(super) fn save_collection(
+  name: &str,
+  query: &str,
+  top_k: usize,
+  config: &Config,
+  state: &mut State,
+  index: &VectorIndex,
+  embedder: &dyn Embedder
+) -> Result<usize> {
+  let hits = search_hits(
+    embedder,
+    query,
+    top_k,
+    &SearchFilter::default(),
+    config,
+    state,
+    index
+  )?;
+  let chunk_ids: Vec<String> = hits
+    .into_iter()
+    .map(|hit| hit.chunk.id)
+    .collect();
+  let saved = chunk_ids.len();
+  state.collections.insert(
+    name.to_string(),
+    Collection {
+      query: query.to_string(),
+      saved_at: Utc::now().to_rfc3339(),
+      chunk_ids
+    }
+  );
+  Ok(saved)
+}
+
+/// Prints a saved collection's query
+/// and chunks; chunks removed since it
+/// was saved are reported as missing.
+pub(super) fn show_collection(
+  name: &str,
+  state: &State
+) -> Result<()> {
+  let collection = state
+    .collections
+    .get(name)
+    .with_context(|| {
+      format!(
+        "no collection named {:?}",
+        name
+      )
+    })?;
+  println!(
+    "Collection {} (query: {:?}, \
+     saved {})",
+    name,
+    collection.query,
+    collection.saved_at
+  );
+  for chunk_id in &collection.chunk_ids
+  {
+    let Some(chunk) =
+      state.find_chunk(chunk_id)
+    else {
+      println!(
+        " → Chunk ID: {} (missing)",
+        chunk_id
+      );
+      continue;
+    };
+    let path = state
+      .find_document(&chunk.doc_id)
+      .map(|doc| doc.path.as_str())
+      .unwrap_or_default();
+    println!(" → Document: {}", path);
+    println!(
+      " → Chunk ID: {}",
+      chunk.id
+    );
+    println!(
+      " → Chunk: {}",
+      chunk.text.trim()
+    );
+    println!("----------");
+  }
+  Ok(())
+}
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::artifacts::emit_chunks_jsonl;
+use super::rechunk::rechunk;
+use super::stats::recompute_stats;
+use crate::chunk::ChunkStrategy;
+use crate::config::Config;
+use crate::embedder::{
+  Embedder,
+  FrozenVocabEmbedder,
+  build_configured_embedder
+};
+use crate::index::VectorIndex;
+use crate::state::State;
+
+/// Loads the corpus `config` stores,
+/// refreshing stale corpus stats, and
+/// the embedder to query it with.
+pub(super) fn load_corpus(
+  config: &Config
+) -> Result<(
+  State,
+  VectorIndex,
+  Box<dyn Embedder>
+)> {
+  let state_path =
+    config.stage1.storage.state_path();
+  let mut state =
+    State::load_from(&state_path)?;
+  if state.stats.documents
+    != state.documents.len()
+  {
+    recompute_stats(&mut state, config);
+  }
+  let index =
+    state.load_index(&state_path);
+  let mut embedder =
+    build_configured_embedder(config)?;
+  if config.stage1.embedder.frozen_vocab
+  {
+    let vocabulary = index.vocabulary();
+    if !vocabulary.is_empty() {
+      embedder = Box::new(
+        FrozenVocabEmbedder::new(
+          embedder, vocabulary
+        )
+      );
+    }
+  }
+  Ok((state, index, embedder))
+}
+
+/// Builds a throwaway copy of the
+/// corpus rechunked with `strategy` and
+/// embedded with `embedder`; the stored
+/// corpus is left untouched.
+pub(crate) fn rebuild_corpus(
+  strategy: ChunkStrategy,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex,
+  embedder: &dyn Embedder
+) -> (State, VectorIndex) {
+  let mut state = state.clone();
+  let mut index =
+    VectorIndex::from_entries(
+      index.entries().to_vec()
+    );
+  rechunk(
+    strategy, config, &mut state,
+    &mut index, embedder
+  );
+  (state, index)
+}
+
+/// Writes the chunks JSONL, the index
+/// file and the state file.
+pub(super) fn save_corpus(
+  state: &mut State,
+  index: &mut VectorIndex,
+  config: &Config,
+  state_path: &Path
+) -> Result<()> {
+  emit_chunks_jsonl(
+    &state.chunks,
+    &config
+      .stage1
+      .storage
+      .chunks_path()
+  )?;
+  state
+    .save_index(index, state_path)?;
+  state.save_to(state_path)
+}
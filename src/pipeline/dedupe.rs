@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::rechunk::reconstruct_text;
+use super::save_corpus;
+use super::stats::remove_document;
+use crate::config::{
+  Config,
+  Stage1Ingest
+};
+use crate::index::VectorIndex;
+use crate::minhash::{
+  LshIndex,
+  MinHasher
+};
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+/// A stored document whose text nearly
+/// duplicates an earlier one.
+pub(super) struct NearDuplicate {
+  pub doc_id:       String,
+  pub duplicate_of: String,
+  pub similarity:   f32
+}
+
+pub(super) fn minhasher(
+  ingest_cfg: &Stage1Ingest
+) -> MinHasher {
+  MinHasher::new(
+    ingest_cfg.minhash_bands,
+    ingest_cfg.minhash_rows
+  )
+}
+
+/// LSH index over the stored documents,
+/// keyed by document ID, with each
+/// text rebuilt from its chunks.
+pub(super) fn document_index(
+  ingest_cfg: &Stage1Ingest,
+  state: &State
+) -> LshIndex<String> {
+  let hasher = minhasher(ingest_cfg);
+  let mut index = LshIndex::new(hasher);
+  for document in &state.documents {
+    if let Some(text) = reconstruct_text(
+      &state.chunks,
+      &document.id
+    ) {
+      index.insert(
+        document.id.clone(),
+        hasher.signature(&text)
+      );
+    }
+  }
+  index
+}
+
+/// Walks the documents in corpus order
+/// and reports each one whose
+/// estimated similarity to an earlier
+/// document reaches
+/// `near_duplicate_threshold`.
+pub(super) fn near_duplicates(
+  ingest_cfg: &Stage1Ingest,
+  state: &State
+) -> Vec<NearDuplicate> {
+  let hasher = minhasher(ingest_cfg);
+  let mut index: LshIndex<String> =
+    LshIndex::new(hasher);
+  let mut found = Vec::new();
+  for document in &state.documents {
+    let Some(text) = reconstruct_text(
+      &state.chunks,
+      &document.id
+    ) else {
+      continue;
+    };
+    let signature =
+      hasher.signature(&text);
+    if let Some((
+      original,
+      similarity
+    )) = index.find(
+      &signature,
+      ingest_cfg
+        .near_duplicate_threshold
+    ) {
+      found.push(NearDuplicate {
+        doc_id: document.id.clone(),
+        duplicate_of: original.clone(),
+        similarity
+      });
+      continue;
+    }
+    index.insert(
+      document.id.clone(),
+      signature
+    );
+  }
+  found
+}
+
+/// Reports near-duplicate documents,
+/// removing them from the corpus and
+/// saving it when `remove` is set.
+pub(super) fn report_near_duplicates(
+  remove: bool,
+  config: &Config,
+  state: &mut State,
+  index: &mut VectorIndex,
+  state_path: &Path
+) -> Result<()> {
+  let found = near_duplicates(
+    &config.stage1.ingest,
+    state
+  );
+  for duplicate in &found {
+    let path = |doc_id: &str| {
+      state
+        .find_document(doc_id)
+        .map(|doc| doc.path.clone())
+        .unwrap_or_default()
+    };
+    println!(
+      "{} ≈ {} ({:.2})",
+      path(&duplicate.doc_id),
+      path(&duplicate.duplicate_of),
+      duplicate.similarity
+    );
+  }
+  if remove && !found.is_empty() {
+    let tokenizer =
+      Tokenizer::from_config(
+        &config.stage1.embedder
+      );
+    for duplicate in &found {
+      remove_document(
+        state,
+        index,
+        &duplicate.doc_id,
+        &tokenizer
+      );
+    }
+    save_corpus(
+      state, index, config, state_path
+    )?;
+  }
+  println!(
+    "{} near-duplicate documents{}.",
+    found.len(),
+    if remove {
+      " removed"
+    } else {
+      ""
+    }
+  );
+  Ok(())
+}
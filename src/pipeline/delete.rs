@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use anyhow::{
+  Result,
+  bail
+};
+
+use super::sources::document_path;
+use super::stats::remove_document;
+use crate::config::Config;
+use crate::index::VectorIndex;
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+/// What a delete removed.
+pub(super) struct DeleteSummary {
+  pub documents: usize,
+  pub chunks:    usize
+}
+
+/// Removes the document with ID
+/// `doc_id` or stored for `path`
+/// (resolved as ingest stores it, so
+/// already deleted files still match),
+/// with its chunks, index entries, and
+/// corpus stats. Fails when no
+/// document matches.
+pub(super) fn delete(
+  config: &Config,
+  state: &mut State,
+  index: &mut VectorIndex,
+  doc_id: Option<&str>,
+  path: Option<&Path>
+) -> Result<DeleteSummary> {
+  let stored_path = path.map(|path| {
+    document_path(
+      path,
+      &config.stage1.storage
+    )
+  });
+  let doc_ids: Vec<String> = state
+    .documents
+    .iter()
+    .filter(|doc| {
+      doc_id == Some(doc.id.as_str())
+        || stored_path.as_deref()
+          == Some(doc.path.as_str())
+    })
+    .map(|doc| doc.id.clone())
+    .collect();
+  if doc_ids.is_empty() {
+    match (doc_id, stored_path) {
+      | (Some(doc_id), _) => {
+        bail!(
+          "no document with ID {:?}",
+          doc_id
+        )
+      }
+      | (None, Some(path)) => {
+        bail!(
+          "no document stored for {:?}",
+          path
+        )
+      }
+      | (None, None) => {
+        bail!(
+          "delete needs a document ID \
+           or path"
+        )
+      }
+    }
+  }
+  let before = state.chunks.len();
+  let tokenizer =
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    );
+  for doc_id in &doc_ids {
+    remove_document(
+      state, index, doc_id, &tokenizer
+    );
+  }
+  Ok(DeleteSummary {
+    documents: doc_ids.len(),
+    chunks:    before
+      - state.chunks.len()
+  })
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use sha2::{
+  Digest,
+  Sha256
+};
+
+use crate::chunk::Chunk;
+use crate::config::Config;
+use crate::embedder::{
+  Embedder,
+  SparseVector
+};
+use crate::index::VectorIndex;
+
+/// Turns chunks into index entries:
+/// embeds the text (or summary) with
+/// the embedder routed to the chunk's
+/// language, folds in the weighted
+/// file-name tokens, and records a
+/// hash of those inputs so reindexing
+/// can skip chunks whose vector would
+/// not change.
+pub(super) struct EntryBuilder<'a> {
+  embedder:          &'a dyn Embedder,
+  fingerprint:       String,
+  path_token_weight: f32,
+  search_summaries:  bool
+}
+
+impl<'a> EntryBuilder<'a> {
+  pub(super) fn new(
+    config: &Config,
+    embedder: &'a dyn Embedder
+  ) -> Self {
+    Self {
+      embedder,
+      fingerprint: format!(
+        "{}|{:?}",
+        embedder.fingerprint(),
+        config.stage1.embedder
+      ),
+      path_token_weight: config
+        .stage1
+        .embedder
+        .path_token_weight,
+      search_summaries: config
+        .stage1
+        .search
+        .search_summaries
+    }
+  }
+
+  /// The text a chunk's vector is
+  /// built from: its summary when
+  /// `search_summaries` is on and one
+  /// exists, otherwise its full text.
+  pub(super) fn embed_text<'c>(
+    &self,
+    chunk: &'c Chunk
+  ) -> &'c str {
+    match &chunk.summary {
+      | Some(summary)
+        if self.search_summaries =>
+      {
+        summary
+      }
+      | _ => &chunk.text
+    }
+  }
+
+  /// Embeds the words of the file name
+  /// (without extension), treating
+  /// punctuation such as `_` and `-` as
+  /// separators, scaled by
+  /// `path_token_weight`. Empty when
+  /// the weight is not positive.
+  pub(super) fn path_vector(
+    &self,
+    file: &Path
+  ) -> SparseVector {
+    if self.path_token_weight <= 0.0 {
+      return SparseVector::new();
+    }
+    let stem = file
+      .file_stem()
+      .map(|stem| {
+        stem.to_string_lossy()
+      })
+      .unwrap_or_default();
+    let words: String = stem
+      .chars()
+      .map(|c| {
+        if c.is_alphanumeric() {
+          c
+        } else {
+          ' '
+        }
+      })
+      .collect();
+    let mut vector = self
+      .embedder
+      .embed_document(&words);
+    for value in vector.values_mut() {
+      *value *= self.path_token_weight;
+    }
+    vector
+  }
+
+  /// Hash of everything that feeds a
+  /// chunk's vector: the embedder's
+  /// fingerprint and settings, the
+  /// document path, the chunk text,
+  /// and its language tag when it has
+  /// one.
+  pub(super) fn input_hash(
+    &self,
+    doc_path: &str,
+    chunk: &Chunk
+  ) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+      &self.fingerprint,
+      doc_path,
+      self.embed_text(chunk)
+    ]
+    .into_iter()
+    .chain(chunk.language.as_deref())
+    {
+      hasher.update(part.as_bytes());
+      hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+  }
+
+  /// The embedder for chunks tagged
+  /// `language`, when it tokenizes
+  /// that language differently.
+  fn routed(
+    &self,
+    language: Option<&str>
+  ) -> Option<Box<dyn Embedder>> {
+    language.and_then(|language| {
+      self
+        .embedder
+        .for_language(language)
+    })
+  }
+
+  /// Embeds the chunks in one batch
+  /// per language.
+  pub(super) fn embed_chunks(
+    &self,
+    chunks: &[&Chunk]
+  ) -> Vec<SparseVector> {
+    let mut groups: BTreeMap<
+      Option<&str>,
+      Vec<usize>
+    > = BTreeMap::new();
+    for (position, chunk) in
+      chunks.iter().enumerate()
+    {
+      groups
+        .entry(
+          chunk.language.as_deref()
+        )
+        .or_default()
+        .push(position);
+    }
+    let mut vectors = vec![
+      SparseVector::new();
+      chunks.len()
+    ];
+    for (language, positions) in groups
+    {
+      let routed =
+        self.routed(language);
+      let embedder = routed
+        .as_deref()
+        .unwrap_or(self.embedder);
+      let texts: Vec<&str> = positions
+        .iter()
+        .map(|&position| {
+          self.embed_text(
+            chunks[position]
+          )
+        })
+        .collect();
+      for (position, vector) in
+        positions.into_iter().zip(
+          embedder.embed_batch(&texts)
+        )
+      {
+        vectors[position] = vector;
+      }
+    }
+    vectors
+  }
+
+  /// Embeds the chunks of one document
+  /// in a batch and adds them to
+  /// `index`.
+  pub(super) fn add_batch(
+    &self,
+    index: &mut VectorIndex,
+    chunks: &[Chunk],
+    doc_path: &str,
+    path_vector: &SparseVector
+  ) {
+    let vectors = self.embed_chunks(
+      &chunks
+        .iter()
+        .collect::<Vec<_>>()
+    );
+    for (chunk, vector) in
+      chunks.iter().zip(vectors)
+    {
+      self.add_embedded(
+        index,
+        chunk,
+        vector,
+        doc_path,
+        path_vector
+      );
+    }
+  }
+
+  /// Adds `chunk` to `index` with its
+  /// text `vector` plus `path_vector`,
+  /// along with its input hash and the
+  /// term counts of its text.
+  pub(super) fn add_embedded(
+    &self,
+    index: &mut VectorIndex,
+    chunk: &Chunk,
+    mut vector: SparseVector,
+    doc_path: &str,
+    path_vector: &SparseVector
+  ) {
+    let text = self.embed_text(chunk);
+    let routed = self.routed(
+      chunk.language.as_deref()
+    );
+    let embedder = routed
+      .as_deref()
+      .unwrap_or(self.embedder);
+    for (token, value) in path_vector {
+      *vector
+        .entry(token.clone())
+        .or_insert(0.0) += value;
+    }
+    index.add_chunk(
+      chunk.id.clone(),
+      chunk.doc_id.clone(),
+      vector,
+      Some(
+        self
+          .input_hash(doc_path, chunk)
+      ),
+      Some(embedder.term_counts(text))
+    );
+  }
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fs::{
+  self,
+  File
+};
+use std::io::{
+  BufWriter,
+  Write
+};
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use anyhow::{
+  Context,
+  Result
+};
+use serde::Serialize;
+
+use super::artifacts::ensure_parent;
+use crate::chunk::Chunk;
+use crate::config::Stage1Storage;
+use crate::state::State;
+
+#[derive(Debug, Default)]
+pub(super) struct ExportSummary {
+  pub exported:   usize,
+  pub duplicates: usize,
+  pub sidecar:    Option<PathBuf>
+}
+
+#[derive(Serialize)]
+struct DuplicateRecord<'a> {
+  chunk_id:     &'a str,
+  doc_id:       &'a str,
+  duplicate_of: &'a str
+}
+
+/// Streams every stored chunk to
+/// `path` as JSON lines without
+/// touching the corpus. With `dedupe`,
+/// a chunk whose text (lowercased,
+/// whitespace collapsed) was already
+/// written is skipped and recorded in
+/// a `<stem>.duplicates.jsonl` sidecar
+/// pointing at the first occurrence.
+pub(super) fn export(
+  state: &State,
+  path: &Path,
+  dedupe: bool
+) -> Result<ExportSummary> {
+  ensure_parent(path)?;
+  let mut out =
+    BufWriter::new(create(path)?);
+  let mut sidecar = None;
+  let mut first_seen: HashMap<
+    String,
+    &str
+  > = HashMap::new();
+  let mut summary =
+    ExportSummary::default();
+  for chunk in &state.chunks {
+    if dedupe {
+      let key = chunk
+        .text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+      if let Some(original) =
+        first_seen.get(&key)
+      {
+        if sidecar.is_none() {
+          let sidecar_path =
+            sidecar_path(path);
+          sidecar =
+            Some(BufWriter::new(
+              create(&sidecar_path)?
+            ));
+          summary.sidecar =
+            Some(sidecar_path);
+        }
+        if let Some(writer) =
+          sidecar.as_mut()
+        {
+          serde_json::to_writer(
+            &mut *writer,
+            &DuplicateRecord {
+              chunk_id:     &chunk.id,
+              doc_id:       &chunk
+                .doc_id,
+              duplicate_of: original
+            }
+          )?;
+          writeln!(writer)?;
+        }
+        summary.duplicates += 1;
+        continue;
+      }
+      first_seen.insert(key, &chunk.id);
+    }
+    serde_json::to_writer(
+      &mut out, chunk
+    )?;
+    writeln!(out)?;
+    summary.exported += 1;
+  }
+  out.flush()?;
+  if let Some(mut writer) = sidecar {
+    writer.flush()?;
+  }
+  Ok(summary)
+}
+
+/// Streams the corpus to `path` as one
+/// Markdown file: a `#` heading per
+/// document path, then a section per
+/// chunk in offset order. A chunk's raw
+/// source text is written when its
+/// source file is still readable and
+/// the chunk records raw offsets;
+/// otherwise its normalized text is,
+/// with its own headings demoted below
+/// the chunk's. Returns the number of
+/// documents written.
+pub(super) fn export_markdown(
+  state: &State,
+  storage: &Stage1Storage,
+  path: &Path
+) -> Result<usize> {
+  ensure_parent(path)?;
+  let mut out =
+    BufWriter::new(create(path)?);
+  let mut by_document: HashMap<
+    &str,
+    Vec<&Chunk>
+  > = HashMap::new();
+  for chunk in &state.chunks {
+    by_document
+      .entry(chunk.doc_id.as_str())
+      .or_default()
+      .push(chunk);
+  }
+  for document in &state.documents {
+    writeln!(
+      out,
+      "# {}\n",
+      document.path
+    )?;
+    let mut chunks = by_document
+      .remove(document.id.as_str())
+      .unwrap_or_default();
+    chunks
+      .sort_by_key(|chunk| chunk.start);
+    let source = fs::read_to_string(
+      storage
+        .resolve_path(&document.path)
+    )
+    .ok();
+    for (idx, chunk) in
+      chunks.iter().enumerate()
+    {
+      let raw = source
+        .as_deref()
+        .zip(chunk.raw_start)
+        .zip(chunk.raw_end)
+        .and_then(
+          |((source, start), end)| {
+            source.get(start..end)
+          }
+        );
+      writeln!(
+        out,
+        "## Chunk {}\n\n{}\n",
+        idx + 1,
+        demote_headings(
+          raw
+            .unwrap_or(&chunk.text)
+            .trim(),
+          2
+        )
+      )?;
+    }
+  }
+  out.flush()?;
+  Ok(state.documents.len())
+}
+
+/// Pushes the Markdown headings in
+/// `text` down `levels` levels (to at
+/// most `######`) so they nest under
+/// the export's own headings. Setext
+/// headings become ATX ones; fenced
+/// code is left alone.
+fn demote_headings(
+  text: &str,
+  levels: usize
+) -> String {
+  let heading =
+    |level: usize, title: &str| {
+      format!(
+        "{} {}",
+        "#".repeat(
+          (level + levels).min(6)
+        ),
+        title.trim()
+      )
+    };
+  let mut lines: Vec<String> =
+    Vec::new();
+  let mut in_fence = false;
+  let mut after_text = false;
+  for line in text.lines() {
+    let trimmed = line.trim_start();
+    let indented =
+      line.len() - trimmed.len() > 3;
+    if !indented
+      && (trimmed.starts_with("```")
+        || trimmed.starts_with("~~~"))
+    {
+      in_fence = !in_fence;
+    } else if !in_fence && !indented {
+      let hashes = trimmed
+        .chars()
+        .take_while(|c| *c == '#')
+        .count();
+      let rest = &trimmed[hashes..];
+      if (1..=6).contains(&hashes)
+        && (rest.is_empty()
+          || rest
+            .starts_with([' ', '\t']))
+      {
+        lines
+          .push(heading(hashes, rest));
+        after_text = false;
+        continue;
+      }
+      let underline =
+        trimmed.trim_end();
+      let setext = match underline
+        .chars()
+        .next()
+      {
+        | Some(c @ ('=' | '-'))
+          if underline
+            .chars()
+            .all(|d| d == c) =>
+        {
+          Some(
+            if c == '=' {
+              1
+            } else {
+              2
+            }
+          )
+        }
+        | _ => None
+      };
+      if after_text
+        && let Some(level) = setext
+        && let Some(title) = lines.pop()
+      {
+        lines
+          .push(heading(level, &title));
+        after_text = false;
+        continue;
+      }
+      after_text = !trimmed.is_empty();
+      lines.push(line.to_string());
+      continue;
+    }
+    after_text = false;
+    lines.push(line.to_string());
+  }
+  lines.join("\n")
+}
+
+fn sidecar_path(
+  path: &Path
+) -> PathBuf {
+  let stem = path
+    .file_stem()
+    .map(|stem| {
+      stem
+        .to_string_lossy()
+        .into_owned()
+    })
+    .unwrap_or_else(|| "export".into());
+  path.with_file_name(format!(
+    "{}.duplicates.jsonl",
+    stem
+  ))
+}
+
+fn create(path: &Path) -> Result<File> {
+  File::create(path).with_context(
+    || format!("create {:?}", path)
+  )
+}
@@ -0,0 +1,166 @@
+use std::fmt::Write as _;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use anyhow::{
+  Result,
+  bail
+};
+
+use super::load_corpus;
+use crate::config::Config;
+use crate::search::{
+  SearchFilter,
+  SearchHit,
+  search_hits
+};
+
+/// A hit and the data directory of the
+/// corpus it came from.
+pub(super) struct FederatedHit {
+  pub corpus: PathBuf,
+  pub hit:    SearchHit
+}
+
+/// `config` with its storage files
+/// moved into `data_dir`, keeping their
+/// file names.
+fn corpus_config(
+  config: &Config,
+  data_dir: &Path
+) -> Config {
+  let mut config = config.clone();
+  let storage =
+    &mut config.stage1.storage;
+  for file in [
+    &mut storage.state_file,
+    &mut storage.chunks_file
+  ] {
+    if let Some(name) =
+      Path::new(file.as_str())
+        .file_name()
+    {
+      *file = data_dir
+        .join(name)
+        .to_string_lossy()
+        .into_owned();
+    }
+  }
+  storage.artifact_dir = data_dir
+    .to_string_lossy()
+    .into_owned();
+  config
+}
+
+/// Runs `query` against the corpus in
+/// each of `data_dirs` and merges the
+/// hits by score, best first. With
+/// `stage1.search.federated_normalize`,
+/// each corpus's scores are first
+/// divided by its best score so no
+/// corpus dominates through scale
+/// alone.
+pub(super) fn federated_search(
+  query: &str,
+  data_dirs: &[PathBuf],
+  top_k: usize,
+  config: &Config
+) -> Result<Vec<FederatedHit>> {
+  let mut merged = Vec::new();
+  for data_dir in data_dirs {
+    let corpus =
+      corpus_config(config, data_dir);
+    if !corpus
+      .stage1
+      .storage
+      .state_path()
+      .exists()
+    {
+      bail!(
+        "no corpus state in {:?}",
+        data_dir
+      );
+    }
+    let (state, index, embedder) =
+      load_corpus(&corpus)?;
+    let mut hits = search_hits(
+      embedder.as_ref(),
+      query,
+      top_k,
+      &SearchFilter::default(),
+      &corpus,
+      &state,
+      &index
+    )?;
+    let best = hits
+      .iter()
+      .map(|hit| hit.score)
+      .fold(0.0_f32, f32::max);
+    if config
+      .stage1
+      .search
+      .federated_normalize
+      && best > 0.0
+    {
+      for hit in &mut hits {
+        hit.score /= best;
+      }
+    }
+    merged.extend(
+      hits.into_iter().map(|hit| {
+        FederatedHit {
+          corpus: data_dir.clone(),
+          hit
+        }
+      })
+    );
+  }
+  merged.sort_by(|a, b| {
+    b.hit.score.total_cmp(&a.hit.score)
+  });
+  merged.truncate(top_k);
+  Ok(merged)
+}
+
+/// Text listing of federated hits, each
+/// labeled with its corpus.
+pub(super) fn text_federated(
+  hits: &[FederatedHit]
+) -> String {
+  let mut out = String::new();
+  for (rank, federated) in
+    hits.iter().enumerate()
+  {
+    let hit = &federated.hit;
+    let _ = writeln!(
+      out,
+      "Result {} (score: {:.3})",
+      rank + 1,
+      hit.score
+    );
+    let _ = writeln!(
+      out,
+      " → Corpus: {}",
+      federated.corpus.display()
+    );
+    let _ = writeln!(
+      out,
+      " → Document: {}",
+      hit.document.path
+    );
+    let _ = writeln!(
+      out,
+      " → Chunk ID: {}",
+      hit.chunk.id
+    );
+    let _ = writeln!(
+      out,
+      " → Chunk: {}",
+      hit.chunk.text.trim()
+    );
+    out.push_str("----------\n");
+  }
+  out
+}
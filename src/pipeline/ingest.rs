@@ -0,0 +1,553 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use anyhow::Result;
+use sha2::{
+  Digest,
+  Sha256
+};
+
+use super::artifacts::{
+  accumulate_word_counts,
+  emit_word_tally_csv,
+  ensure_parent
+};
+use super::dedupe::document_index;
+use super::embedding::EntryBuilder;
+use super::sources::{
+  collect_sources,
+  document_path,
+  manifest_sources,
+  read_source,
+  split_frontmatter
+};
+use crate::chunk::{
+  Chunk,
+  ChunkStrategy,
+  Chunker
+};
+use crate::config::{
+  ChunkCapSampling,
+  Config,
+  DuplicateContent,
+  IngestErrorPolicy,
+  Stage1Chunk
+};
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::normalization::Normalizer;
+use crate::section::assign_headings;
+use crate::state::{
+  Document,
+  State
+};
+use crate::tokenizer::Tokenizer;
+use crate::{
+  language,
+  rng
+};
+
+pub(super) struct IngestOptions {
+  /// `path` is a manifest listing the
+  /// files to ingest
+  pub from_manifest:   bool,
+  pub strategy:        ChunkStrategy,
+  pub emit_word_tally: bool,
+  pub emit_normalized: bool
+}
+
+#[derive(Default)]
+pub(super) struct IngestSummary {
+  pub failed: Vec<PathBuf>
+}
+
+pub(super) fn ingest(
+  path: &Path,
+  options: IngestOptions,
+  config: &Config,
+  state: &mut State,
+  index: &mut VectorIndex,
+  embedder: &dyn Embedder
+) -> Result<IngestSummary> {
+  let extensions =
+    &config.stage1.ingest.extensions;
+  let source_files = if options
+    .from_manifest
+  {
+    manifest_sources(path, extensions)?
+  } else {
+    collect_sources(path, extensions)?
+  };
+  if source_files.is_empty() {
+    println!(
+      "No text or Markdown files \
+       found at {:?}",
+      path
+    );
+    return Ok(IngestSummary::default());
+  }
+  let run_id = state.next_ingest_run();
+  let detect_language = config
+    .stage1
+    .ingest
+    .detect_language;
+  if detect_language
+    && !language::is_supported()
+  {
+    eprintln!(
+      "Warning: language detection \
+       requested but oxbed was built \
+       without the language-detection \
+       feature"
+    );
+  }
+  let chunker = build_chunker(
+    options.strategy,
+    config
+  );
+  let entries =
+    EntryBuilder::new(config, embedder);
+  let normalizer =
+    Normalizer::from_config(
+      &config.stage1.normalization
+    );
+  let tokenizer =
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    );
+  // The same vocabulary the frozen
+  // embedder was built with, so new
+  // terms stay out of the stats too.
+  let frozen_vocabulary = config
+    .stage1
+    .embedder
+    .frozen_vocab
+    .then(|| index.vocabulary())
+    .filter(|vocabulary| {
+      !vocabulary.is_empty()
+    });
+  let artifacts_dir = PathBuf::from(
+    &config.stage1.storage.artifact_dir
+  );
+  let normalized_path =
+    if options.emit_normalized {
+      Some(
+        artifacts_dir
+          .join("normalized.txt")
+      )
+    } else {
+      None
+    };
+  let word_tally_path =
+    if options.emit_word_tally {
+      Some(
+        artifacts_dir
+          .join("word_tally.csv")
+      )
+    } else {
+      None
+    };
+  let mut normalized_writer =
+    if let Some(path) =
+      normalized_path.as_ref()
+    {
+      ensure_parent(path)?;
+      Some(File::create(path)?)
+    } else {
+      None
+    };
+  let mut word_counts =
+    if word_tally_path.is_some() {
+      Some(HashMap::new())
+    } else {
+      None
+    };
+  let mut near_duplicates = config
+    .stage1
+    .ingest
+    .skip_near_duplicates
+    .then(|| {
+      document_index(
+        &config.stage1.ingest,
+        state
+      )
+    });
+  let mut summary =
+    IngestSummary::default();
+  for file in source_files {
+    let read = read_source(
+      &file,
+      &config.stage1.ingest
+    );
+    let content = match read {
+      | Ok(Some(content)) => content,
+      | Ok(None) => continue,
+      | Err(err) => {
+        match config.stage1.ingest.on_error
+        {
+          | IngestErrorPolicy::Abort => {
+            return Err(err);
+          }
+          | IngestErrorPolicy::Skip => {
+            eprintln!(
+              "Warning: skipping {:?}: \
+               {:#}",
+              file, err
+            );
+            summary.failed.push(file);
+            continue;
+          }
+        }
+      }
+    };
+    let (body_start, tags) =
+      split_frontmatter(
+        &content,
+        &file,
+        &config.stage1.ingest
+      );
+    let (normalized, offsets) =
+      normalizer
+        .for_file(&file)
+        .normalize_with_offsets(
+          &content[body_start..]
+        );
+    if let Some(writer) =
+      normalized_writer.as_mut()
+    {
+      writeln!(
+        writer,
+        "### {}\n",
+        file.display()
+      )?;
+      writeln!(
+        writer,
+        "{}\n",
+        normalized
+      )?;
+    }
+    if let Some(counts) =
+      word_counts.as_mut()
+    {
+      accumulate_word_counts(
+        counts,
+        &normalized
+      );
+    }
+    let hash = hash_text(&normalized);
+    if state.has_document(&hash) {
+      match config
+        .stage1
+        .ingest
+        .duplicate_content
+      {
+        | DuplicateContent::Skip => {
+          println!(
+            "Skipping already ingested \
+             {:?}",
+            file
+          );
+          continue;
+        }
+        | DuplicateContent::AddPathOnly => {
+          record_alias(
+            state,
+            &hash,
+            document_path(
+              &file,
+              &config.stage1.storage
+            )
+          );
+          continue;
+        }
+        | DuplicateContent::AddFull => {
+          println!(
+            "Ingesting duplicate \
+             content of {:?} as a new \
+             document",
+            file
+          );
+        }
+      }
+    }
+    let signature = near_duplicates
+      .as_ref()
+      .map(|index| {
+        index
+          .hasher()
+          .signature(&normalized)
+      });
+    if let (
+      Some(index),
+      Some(signature)
+    ) =
+      (&near_duplicates, &signature)
+      && let Some((
+        original,
+        similarity
+      )) = index.find(
+        signature,
+        config
+          .stage1
+          .ingest
+          .near_duplicate_threshold
+      )
+    {
+      println!(
+        "Skipping near-duplicate {:?} \
+         ({:.2} similar to document \
+         {})",
+        file, similarity, original
+      );
+      continue;
+    }
+    let doc_id =
+      rng::uuid().to_string();
+    let doc_path = document_path(
+      &file,
+      &config.stage1.storage
+    );
+    let document = Document {
+      id: doc_id.clone(),
+      path: doc_path,
+      hash: hash.clone(),
+      token_count: embedder
+        .token_count(&normalized),
+      tags,
+      aliases: Vec::new(),
+      normalized: config
+        .stage1
+        .storage
+        .keep_normalized
+        .then(|| normalized.clone())
+    };
+    if normalized.trim().is_empty() {
+      eprintln!(
+        "Warning: skipping {:?}: no \
+         content after normalization",
+        file
+      );
+      continue;
+    }
+    let path_vector =
+      entries.path_vector(&file);
+    let mut chunks = cap_chunks(
+      chunker
+        .chunk(&doc_id, &normalized),
+      &config.stage1.chunk,
+      &file
+    );
+    if chunks.is_empty() {
+      println!(
+        "No chunks produced for {:?}",
+        file
+      );
+      continue;
+    }
+    assign_headings(
+      &mut chunks,
+      &normalized,
+      &file
+    );
+    for chunk in &mut chunks {
+      chunk.run_id = run_id;
+      if let Some((
+        raw_start,
+        raw_end
+      )) = offsets.raw_range(
+        chunk.start,
+        chunk.end
+      ) {
+        chunk.raw_start =
+          Some(body_start + raw_start);
+        chunk.raw_end =
+          Some(body_start + raw_end);
+      }
+      if detect_language {
+        chunk.language =
+          language::detect(&chunk.text);
+      }
+    }
+    entries.add_batch(
+      index,
+      &chunks,
+      &document.path,
+      &path_vector
+    );
+    state.stats.add_document_where(
+      &document,
+      &chunks,
+      &tokenizer,
+      |term| {
+        frozen_vocabulary
+          .as_ref()
+          .is_none_or(|vocabulary| {
+            vocabulary.contains(term)
+          })
+      }
+    );
+    state.chunks.extend(chunks);
+    state.record_ingest_run(run_id);
+    if let (
+      Some(index),
+      Some(signature)
+    ) =
+      (&mut near_duplicates, signature)
+    {
+      index.insert(doc_id, signature);
+    }
+    state.documents.push(document);
+    if config
+      .stage1
+      .ingest
+      .verbose_documents
+    {
+      println!(
+        "Document {} → {} tokens",
+        file.display(),
+        embedder
+          .token_count(&normalized)
+      );
+    }
+  }
+  if let (Some(path), Some(counts)) = (
+    word_tally_path.as_ref(),
+    word_counts.as_ref()
+  ) {
+    ensure_parent(path)?;
+    emit_word_tally_csv(path, counts)?;
+  }
+  Ok(summary)
+}
+
+/// Records `path` as an alias of the
+/// stored document with `hash`, unless
+/// it is already one of its paths.
+fn record_alias(
+  state: &mut State,
+  hash: &str,
+  path: String
+) {
+  let Some(document) = state
+    .documents
+    .iter_mut()
+    .find(|doc| doc.hash == hash)
+  else {
+    return;
+  };
+  if document.path == path
+    || document.aliases.contains(&path)
+  {
+    println!(
+      "Skipping already ingested {:?}",
+      path
+    );
+    return;
+  }
+  println!(
+    "Recorded {:?} as an alias of \
+     document {}",
+    path, document.id
+  );
+  document.aliases.push(path);
+}
+
+pub(super) fn build_chunker(
+  strategy: ChunkStrategy,
+  config: &Config
+) -> Chunker {
+  let chunk_cfg = &config.stage1.chunk;
+  Chunker::with_config(
+    strategy,
+    chunk_cfg.max_tokens,
+    chunk_cfg.overlap,
+    chunk_cfg.split_on_double_newline,
+    chunk_cfg.dedupe_segments,
+    chunk_cfg.chunk_separators.clone()
+  )
+  .with_min_unique_ratio(
+    chunk_cfg.min_unique_ratio
+  )
+  .with_snap_to_sentence(
+    chunk_cfg.snap_to_sentence
+  )
+  .with_keep_lists_together(
+    chunk_cfg.keep_lists_together
+  )
+  .with_normalizer(
+    Normalizer::from_config(
+      &config.stage1.normalization
+    )
+  )
+  .with_summarizer(chunk_cfg.summarizer)
+  .with_tokenizer(
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    )
+  )
+}
+
+/// Enforces `max_chunks_per_doc`,
+/// keeping either the first chunks or
+/// evenly spaced ones (always
+/// including the first), in document
+/// order.
+pub(super) fn cap_chunks(
+  chunks: Vec<Chunk>,
+  chunk_cfg: &Stage1Chunk,
+  source: &Path
+) -> Vec<Chunk> {
+  let Some(max) =
+    chunk_cfg.max_chunks_per_doc
+  else {
+    return chunks;
+  };
+  let total = chunks.len();
+  if total <= max {
+    return chunks;
+  }
+  println!(
+    "Dropped {} of {} chunks from \
+     {:?} (max_chunks_per_doc = {})",
+    total - max,
+    total,
+    source,
+    max
+  );
+  match chunk_cfg.chunk_cap_sampling {
+    | ChunkCapSampling::First => {
+      chunks
+        .into_iter()
+        .take(max)
+        .collect()
+    }
+    | ChunkCapSampling::Even => {
+      let mut next = 0;
+      chunks
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| {
+          let keep = next < max
+            && *idx
+              == next * total / max;
+          if keep {
+            next += 1;
+          }
+          keep
+        })
+        .map(|(_, chunk)| chunk)
+        .collect()
+    }
+  }
+}
+
+fn hash_text(text: &str) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(text.as_bytes());
+  format!("{:x}", hasher.finalize())
+}
@@ -0,0 +1,155 @@
+use anyhow::{
+  Result,
+  bail
+};
+
+use crate::args::ListTarget;
+use crate::chunk::Chunk;
+use crate::state::State;
+
+/// Bytes shared by two consecutive
+/// chunks of a document.
+#[derive(Debug, PartialEq)]
+pub(super) struct Overlap {
+  pub start:  usize,
+  pub end:    usize,
+  pub shared: String
+}
+
+pub(super) fn list(
+  target: ListTarget,
+  doc_id: Option<&str>,
+  show_overlap: bool,
+  state: &State
+) -> Result<()> {
+  for line in list_lines(
+    target,
+    doc_id,
+    show_overlap,
+    state
+  )? {
+    println!("{}", line);
+  }
+  Ok(())
+}
+
+/// One line per document or chunk.
+/// Chunks are listed in `start` order
+/// when restricted to `doc_id`; with
+/// `show_overlap`, each chunk is
+/// followed by the range and text it
+/// shares with the next one.
+pub(super) fn list_lines(
+  target: ListTarget,
+  doc_id: Option<&str>,
+  show_overlap: bool,
+  state: &State
+) -> Result<Vec<String>> {
+  if let Some(doc_id) = doc_id
+    && state
+      .find_document(doc_id)
+      .is_none()
+  {
+    bail!(
+      "unknown document {}",
+      doc_id
+    );
+  }
+  if show_overlap && doc_id.is_none() {
+    bail!(
+      "--show-overlap needs --doc-id"
+    );
+  }
+  let lines = match target {
+    | ListTarget::Documents => {
+      state
+        .documents
+        .iter()
+        .filter(|doc| {
+          doc_id.is_none_or(|id| {
+            doc.id == id
+          })
+        })
+        .map(|doc| {
+          format!(
+            "{}  {}  {} tokens",
+            doc.id,
+            doc.path,
+            doc.token_count
+          )
+        })
+        .collect()
+    }
+    | ListTarget::Chunks => {
+      let mut chunks: Vec<&Chunk> =
+        state
+          .chunks
+          .iter()
+          .filter(|chunk| {
+            doc_id.is_none_or(|id| {
+              chunk.doc_id == id
+            })
+          })
+          .collect();
+      if doc_id.is_some() {
+        chunks.sort_by_key(|chunk| {
+          chunk.start
+        });
+      }
+      let mut lines = Vec::new();
+      for (idx, chunk) in
+        chunks.iter().enumerate()
+      {
+        lines.push(format!(
+          "{}  {}  [{}..{}]",
+          chunk.id,
+          chunk.doc_id,
+          chunk.start,
+          chunk.end
+        ));
+        if !show_overlap {
+          continue;
+        }
+        if let Some(shared) =
+          chunks.get(idx + 1).and_then(
+            |next| overlap(chunk, next)
+          )
+        {
+          lines.push(format!(
+            "  overlap [{}..{}]: {:?}",
+            shared.start,
+            shared.end,
+            shared.shared
+          ));
+        }
+      }
+      lines
+    }
+  };
+  Ok(lines)
+}
+
+/// The range `next` shares with
+/// `previous`, taken from the stored
+/// offsets, with the shared text read
+/// from the head of `next`.
+pub(super) fn overlap(
+  previous: &Chunk,
+  next: &Chunk
+) -> Option<Overlap> {
+  let start =
+    previous.start.max(next.start);
+  let end = previous.end.min(next.end);
+  if start >= end {
+    return None;
+  }
+  let shared = next.text.get(
+    start - next.start
+      ..end - next.start
+  )?;
+  Some(Overlap {
+    start,
+    end,
+    shared: shared.to_string()
+  })
+}
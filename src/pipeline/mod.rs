@@ -0,0 +1,596 @@
+mod artifacts;
+mod collection;
+mod corpus;
+mod dedupe;
+mod delete;
+mod embedding;
+mod export;
+mod federated;
+mod ingest;
+mod list;
+mod query;
+mod rechunk;
+mod reindex;
+mod render;
+mod sources;
+mod stats;
+mod timeout;
+mod undo;
+mod vocab;
+mod watch;
+
+use anyhow::{
+  Context,
+  Result
+};
+
+use self::collection::{
+  save_collection,
+  show_collection
+};
+pub(crate) use self::corpus::rebuild_corpus;
+use self::corpus::{
+  load_corpus,
+  save_corpus
+};
+use self::dedupe::report_near_duplicates;
+use self::delete::delete;
+use self::export::{
+  export,
+  export_markdown
+};
+use self::federated::{
+  federated_search,
+  text_federated
+};
+use self::ingest::{
+  IngestOptions,
+  ingest
+};
+use self::list::list;
+use self::query::{
+  DisplayBand,
+  QueryOptions,
+  diagnose,
+  embed,
+  search,
+  similar,
+  status
+};
+use self::rechunk::rechunk;
+use self::reindex::reindex;
+use self::render::output_rag;
+use self::stats::recompute_stats;
+use self::timeout::run_with_timeout;
+use self::undo::undo;
+use self::vocab::{
+  render_vocabulary,
+  vocabulary
+};
+use self::watch::watch;
+use crate::args::{
+  Command,
+  OutputFormat
+};
+use crate::config::Config;
+use crate::search::SearchFilter;
+use crate::tokenizer::Tokenizer;
+use crate::{
+  evaluation,
+  rng,
+  stage3,
+  stage4
+};
+
+pub fn run(
+  command: Command,
+  mut config: Config
+) -> Result<()> {
+  let state_path =
+    config.stage1.storage.state_path();
+  let (mut state, mut index, embedder) =
+    load_corpus(&config)?;
+  rng::seed(config.stage1.seed.map(
+    |seed| seed ^ state.id_digest()
+  ));
+  match command {
+    | Command::Ingest {
+      path,
+      from_manifest,
+      strategy,
+      emit_word_tally,
+      emit_normalized
+    } => {
+      let (path, from_manifest) =
+        match from_manifest {
+          | Some(manifest) => {
+            (manifest, true)
+          }
+          | None => {
+            (
+              path.context(
+                "ingest needs a path \
+                 or --from-manifest"
+              )?,
+              false
+            )
+          }
+        };
+      let summary = ingest(
+        &path,
+        IngestOptions {
+          from_manifest,
+          strategy,
+          emit_word_tally,
+          emit_normalized
+        },
+        &config,
+        &mut state,
+        &mut index,
+        embedder.as_ref()
+      )?;
+      save_corpus(
+        &mut state,
+        &mut index,
+        &config,
+        &state_path
+      )?;
+      println!(
+        "Ingested {} documents ({} \
+         chunks total).",
+        state.documents.len(),
+        state.chunks.len()
+      );
+      if !summary.failed.is_empty() {
+        println!(
+          "Skipped {} unreadable \
+           files:",
+          summary.failed.len()
+        );
+        for file in &summary.failed {
+          println!(
+            " → {}",
+            file.display()
+          );
+        }
+      }
+    }
+    | Command::Search {
+      query,
+      top_k,
+      language,
+      in_docs,
+      format,
+      json,
+      group_by_doc,
+      trace,
+      expand_section,
+      by_document,
+      min_display_score,
+      max_display_score,
+      include_vectors,
+      not,
+      cluster_results,
+      ranking
+    } => {
+      if let Some(ranking) = ranking {
+        config.stage1.search.ranking =
+          ranking;
+      }
+      let resolved_top_k = top_k
+        .unwrap_or(
+          config.stage1.search.top_k
+        );
+      let options = QueryOptions {
+        top_k: resolved_top_k,
+        filter: SearchFilter {
+          language,
+          doc_ids: in_docs,
+          negative: not
+        },
+        format: if json {
+          OutputFormat::Json
+        } else {
+          format
+        },
+        group_by_doc,
+        trace,
+        expand_section,
+        by_document,
+        display: DisplayBand {
+          min: min_display_score,
+          max: max_display_score
+        },
+        include_vectors,
+        cluster_results
+      };
+      run_with_timeout(
+        "search",
+        config.stage1.search.timeout_ms,
+        move || {
+          search(
+            &query,
+            &options,
+            &state,
+            &index,
+            embedder.as_ref(),
+            &config
+          )
+        }
+      )?;
+    }
+    | Command::SearchFederated {
+      query,
+      data_dirs,
+      top_k
+    } => {
+      let hits = federated_search(
+        &query,
+        &data_dirs,
+        top_k.unwrap_or(
+          config.stage1.search.top_k
+        ),
+        &config
+      )?;
+      if hits.is_empty() {
+        println!("No results found.");
+      }
+      print!(
+        "{}",
+        text_federated(&hits)
+      );
+    }
+    | Command::Diagnose {
+      query,
+      chunk_id
+    } => {
+      diagnose(
+        &query,
+        &chunk_id,
+        &state,
+        &index,
+        embedder.as_ref(),
+        &config
+      )?;
+    }
+    | Command::Similar {
+      chunk_id,
+      top_k
+    } => {
+      let resolved_top_k = top_k
+        .unwrap_or(
+          config.stage1.search.top_k
+        );
+      similar(
+        &chunk_id,
+        resolved_top_k,
+        &state,
+        &index,
+        &config
+      )?;
+    }
+    | Command::Train {
+      model,
+      version,
+      chunks
+    } => {
+      let result = stage4::train_model(
+        &config,
+        &model,
+        version.as_deref(),
+        chunks.as_deref()
+      )?;
+      println!(
+        "Trained {} {} → {}",
+        result.manifest.name,
+        result.manifest.version,
+        result.manifest_path.display()
+      );
+      println!(
+        "Training data: {}",
+        result.training_data.display()
+      );
+    }
+    | Command::Rag {
+      query,
+      top_k,
+      language,
+      in_docs,
+      format
+    } => {
+      let resolved_top_k = top_k
+        .unwrap_or(
+          config.stage1.search.top_k
+        );
+      let filter = SearchFilter {
+        language,
+        doc_ids: in_docs,
+        negative: None
+      };
+      let runs = run_with_timeout(
+        "rag",
+        config.stage3.timeout_ms,
+        move || {
+          stage3::run_stage3(
+            &query,
+            resolved_top_k,
+            &filter,
+            &config,
+            &state,
+            &index,
+            embedder.as_ref()
+          )
+        }
+      )?;
+      output_rag(&runs, format)?;
+    }
+    | Command::Evaluate {
+      coverage
+    } => {
+      evaluation::run_evaluation(
+        &config, &state, &index,
+        coverage
+      )?;
+    }
+    | Command::Tune {
+      write
+    } => {
+      evaluation::run_tune(
+        &config,
+        &state,
+        &index,
+        embedder.as_ref(),
+        write.as_deref()
+      )?;
+    }
+    | Command::Leaderboard {
+      metric,
+      top,
+      format
+    } => {
+      evaluation::print_leaderboard(
+        &config, metric, top, format
+      )?;
+    }
+    | Command::Watch {
+      path,
+      strategy
+    } => {
+      watch(
+        &path,
+        strategy,
+        &config,
+        &mut state,
+        &mut index,
+        embedder.as_ref(),
+        |_| true
+      )?;
+    }
+    | Command::Reindex {
+      force
+    } => {
+      let summary = reindex(
+        &config,
+        force,
+        &state,
+        &mut index,
+        embedder.as_ref()
+      );
+      recompute_stats(
+        &mut state, &config
+      );
+      save_corpus(
+        &mut state,
+        &mut index,
+        &config,
+        &state_path
+      )?;
+      println!(
+        "Reindexed {} chunks ({} \
+         re-embedded, {} unchanged).",
+        summary.reembedded
+          + summary.reused,
+        summary.reembedded,
+        summary.reused
+      );
+    }
+    | Command::Rechunk {
+      strategy
+    } => {
+      let before = state.chunks.len();
+      let documents = rechunk(
+        strategy,
+        &config,
+        &mut state,
+        &mut index,
+        embedder.as_ref()
+      );
+      save_corpus(
+        &mut state,
+        &mut index,
+        &config,
+        &state_path
+      )?;
+      println!(
+        "Rechunked {} documents ({} → \
+         {} chunks).",
+        documents,
+        before,
+        state.chunks.len()
+      );
+    }
+    | Command::Export {
+      path,
+      dedupe
+    } => {
+      let summary =
+        export(&state, &path, dedupe)?;
+      println!(
+        "Exported {} chunks to {}.",
+        summary.exported,
+        path.display()
+      );
+      if let Some(sidecar) =
+        summary.sidecar
+      {
+        println!(
+          "Skipped {} duplicate \
+           chunks (see {}).",
+          summary.duplicates,
+          sidecar.display()
+        );
+      }
+    }
+    | Command::ExportMarkdown {
+      path
+    } => {
+      let documents = export_markdown(
+        &state,
+        &config.stage1.storage,
+        &path
+      )?;
+      println!(
+        "Exported {} documents to {}.",
+        documents,
+        path.display()
+      );
+    }
+    | Command::Dedupe {
+      remove
+    } => {
+      report_near_duplicates(
+        remove,
+        &config,
+        &mut state,
+        &mut index,
+        &state_path
+      )?;
+    }
+    | Command::Delete {
+      doc_id,
+      path
+    } => {
+      let summary = delete(
+        &config,
+        &mut state,
+        &mut index,
+        doc_id.as_deref(),
+        path.as_deref()
+      )?;
+      save_corpus(
+        &mut state,
+        &mut index,
+        &config,
+        &state_path
+      )?;
+      println!(
+        "Deleted {} documents ({} \
+         chunks).",
+        summary.documents,
+        summary.chunks
+      );
+    }
+    | Command::Undo => {
+      match undo(
+        &config, &mut state, &mut index
+      ) {
+        | Some(summary) => {
+          save_corpus(
+            &mut state,
+            &mut index,
+            &config,
+            &state_path
+          )?;
+          println!(
+            "Undid ingest run {}: \
+             removed {} documents ({} \
+             chunks).",
+            summary.run_id,
+            summary.documents,
+            summary.chunks
+          );
+        }
+        | None => {
+          println!(
+            "No ingest run to undo."
+          );
+        }
+      }
+    }
+    | Command::Embed {
+      text
+    } => {
+      embed(text, embedder.as_ref())?;
+    }
+    | Command::SaveCollection {
+      name,
+      query,
+      top_k
+    } => {
+      let saved = save_collection(
+        &name,
+        &query,
+        top_k.unwrap_or(
+          config.stage1.search.top_k
+        ),
+        &config,
+        &mut state,
+        &index,
+        embedder.as_ref()
+      )?;
+      save_corpus(
+        &mut state,
+        &mut index,
+        &config,
+        &state_path
+      )?;
+      println!(
+        "Saved {} chunks to \
+         collection {}.",
+        saved, name
+      );
+    }
+    | Command::ShowCollection {
+      name
+    } => {
+      show_collection(&name, &state)?;
+    }
+    | Command::List {
+      target,
+      doc_id,
+      show_overlap
+    } => {
+      list(
+        target,
+        doc_id.as_deref(),
+        show_overlap,
+        &state
+      )?;
+    }
+    | Command::Status => {
+      status(&state, &index, &config)?;
+    }
+    | Command::Vocab {
+      min_df,
+      format
+    } => {
+      let tokenizer =
+        Tokenizer::from_config(
+          &config.stage1.embedder
+        );
+      print!(
+        "{}",
+        render_vocabulary(
+          &vocabulary(
+            &state, &tokenizer, min_df
+          ),
+          format
+        )?
+      );
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests;
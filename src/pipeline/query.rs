@@ -0,0 +1,493 @@
+use std::path::{
+  Path,
+  PathBuf
+};
+use std::{
+  fs,
+  io
+};
+
+use anyhow::{
+  Context,
+  Result
+};
+
+use super::artifacts::ensure_parent;
+use super::render::{
+  json_hits_with_vectors,
+  json_vector,
+  render_clusters,
+  render_documents,
+  render_hits,
+  sequential_ranks,
+  text_hits,
+  text_vector
+};
+use crate::args::OutputFormat;
+use crate::config::Config;
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::search::{
+  DOCUMENT_OVERFETCH,
+  SearchFilter,
+  Verdict,
+  cluster_hits,
+  diagnose as diagnose_chunk,
+  log_query,
+  rank_documents,
+  similar_hits,
+  trace_search
+};
+use crate::section::expand_sections;
+use crate::state::State;
+use crate::storage;
+
+pub(super) struct QueryOptions {
+  pub(super) top_k:           usize,
+  pub(super) filter: SearchFilter,
+  pub(super) format: OutputFormat,
+  pub(super) group_by_doc:    bool,
+  /// Where to write the query's
+  /// scoring trace as JSON
+  pub(super) trace: Option<PathBuf>,
+  /// Whether to replace hits with
+  /// their whole section
+  pub(super) expand_section:  bool,
+  /// Whether to rank documents rather
+  /// than chunks
+  pub(super) by_document:     bool,
+  pub(super) display: DisplayBand,
+  /// Whether JSON hits carry their
+  /// stored vectors
+  pub(super) include_vectors: bool,
+  /// Whether to print one hit per
+  /// cluster of similar hits
+  pub(super) cluster_results: bool
+}
+
+/// Score bounds on which results are
+/// printed. Applied after retrieval, so
+/// hidden results still count toward
+/// `top_k` and printed results keep
+/// their retrieval rank (in text and in
+/// the JSON `rank` field), leaving gaps
+/// where results were hidden.
+#[derive(
+  Clone, Copy, Debug, Default,
+)]
+pub(super) struct DisplayBand {
+  pub(super) min: Option<f32>,
+  pub(super) max: Option<f32>
+}
+
+impl DisplayBand {
+  /// The `items` whose score falls in
+  /// the band, with their 1-based
+  /// ranks in `items`.
+  pub(super) fn apply<T>(
+    &self,
+    items: Vec<T>,
+    score: impl Fn(&T) -> f32
+  ) -> (Vec<T>, Vec<usize>) {
+    items
+      .into_iter()
+      .zip(1..)
+      .filter(|(item, _)| {
+        let score = score(item);
+        self.min.is_none_or(|min| {
+          score >= min
+        }) && self.max.is_none_or(
+          |max| score <= max
+        )
+      })
+      .unzip()
+  }
+}
+
+pub(super) fn search(
+  query: &str,
+  options: &QueryOptions,
+  state: &State,
+  index: &VectorIndex,
+  embedder: &dyn Embedder,
+  config: &Config
+) -> Result<()> {
+  if index.entries().is_empty() {
+    println!(
+      "No indexed chunks yet. Run \
+       `oxbed ingest` first."
+    );
+    return Ok(());
+  }
+  let fetch = if options.by_document {
+    options.top_k * DOCUMENT_OVERFETCH
+  } else {
+    options.top_k
+  };
+  let (hits, trace) = trace_search(
+    embedder,
+    query,
+    fetch,
+    &options.filter,
+    config,
+    state,
+    index
+  )?;
+  log_query(
+    query,
+    options.top_k,
+    &hits,
+    config
+  );
+  if let Some(path) = &options.trace {
+    ensure_parent(path)?;
+    fs::write(
+      path,
+      serde_json::to_string_pretty(
+        &trace
+      )?
+    )
+    .with_context(|| {
+      format!("write trace {:?}", path)
+    })?;
+  }
+  let hits = if options.expand_section {
+    expand_sections(hits, state)
+  } else {
+    hits
+  };
+  if hits.is_empty()
+    && options.format
+      != OutputFormat::Json
+  {
+    println!(
+      "No matching chunks found for \
+       query."
+    );
+    return Ok(());
+  }
+  let vectors = options.include_vectors
+    && options.format
+      == OutputFormat::Json
+    && !options.group_by_doc
+    && !options.by_document;
+  if options.include_vectors && !vectors
+  {
+    eprintln!(
+      "Warning: --include-vectors \
+       only applies to JSON chunk \
+       lists; ignoring it"
+    );
+  }
+  if options.by_document {
+    let documents = rank_documents(
+      hits,
+      config
+        .stage1
+        .search
+        .document_score,
+      options.top_k
+    );
+    let (documents, ranks) = options
+      .display
+      .apply(documents, |doc| {
+        doc.score
+      });
+    if documents.is_empty() {
+      return no_displayed_results(
+        options.format
+      );
+    }
+    print!(
+      "{}",
+      render_documents(
+        &documents,
+        &ranks,
+        options.format
+      )?
+    );
+    return Ok(());
+  }
+  let (hits, ranks) = options
+    .display
+    .apply(hits, |hit| hit.score);
+  if hits.is_empty() {
+    return no_displayed_results(
+      options.format
+    );
+  }
+  let rendered =
+    if options.cluster_results {
+      let clusters = cluster_hits(
+        &hits,
+        index,
+        config
+          .stage1
+          .search
+          .cluster_similarity
+      );
+      let representatives: Vec<_> =
+        clusters
+          .iter()
+          .map(|cluster| {
+            hits[cluster[0]].clone()
+          })
+          .collect();
+      let cluster_ranks: Vec<_> =
+        clusters
+          .iter()
+          .map(|cluster| {
+            ranks[cluster[0]]
+          })
+          .collect();
+      let sizes: Vec<_> = clusters
+        .iter()
+        .map(Vec::len)
+        .collect();
+      render_clusters(
+        &representatives,
+        &cluster_ranks,
+        &sizes,
+        options.format
+      )?
+    } else if vectors {
+      json_hits_with_vectors(
+        &hits,
+        &ranks,
+        index,
+        config
+          .stage1
+          .search
+          .max_hit_vector_terms
+      )?
+    } else {
+      render_hits(
+        &hits,
+        &ranks,
+        options.format,
+        options.group_by_doc
+      )?
+    };
+  print!("{}", rendered);
+  Ok(())
+}
+
+/// Reports that every retrieved result
+/// fell outside the display band.
+fn no_displayed_results(
+  format: OutputFormat
+) -> Result<()> {
+  if format == OutputFormat::Json {
+    println!("[]");
+  } else {
+    println!(
+      "No results within the display \
+       score range."
+    );
+  }
+  Ok(())
+}
+
+pub(super) fn similar(
+  chunk_id: &str,
+  top_k: usize,
+  state: &State,
+  index: &VectorIndex,
+  config: &Config
+) -> Result<()> {
+  let hits = similar_hits(
+    chunk_id, top_k, config, state,
+    index
+  )?;
+  if hits.is_empty() {
+    println!(
+      "No chunks similar to {} found.",
+      chunk_id
+    );
+    return Ok(());
+  }
+  print!(
+    "{}",
+    text_hits(
+      &hits,
+      &sequential_ranks(hits.len())
+    )
+  );
+  Ok(())
+}
+
+pub(super) fn diagnose(
+  query: &str,
+  chunk_id: &str,
+  state: &State,
+  index: &VectorIndex,
+  embedder: &dyn Embedder,
+  config: &Config
+) -> Result<()> {
+  let top_k =
+    config.stage1.search.top_k;
+  let diagnosis = diagnose_chunk(
+    embedder, query, chunk_id, top_k,
+    config, state, index
+  )?;
+  println!(
+    "Query vector: {}",
+    text_vector(
+      &diagnosis.query_vector
+    )
+  );
+  println!(
+    "Chunk vector: {}",
+    text_vector(
+      &diagnosis.chunk_vector
+    )
+  );
+  if diagnosis.verdict
+    == Verdict::NoSharedTerms
+  {
+    println!(
+      "Cosine: {:.4} (not matched: \
+       chunks sharing no query term \
+       are never candidates)",
+      diagnosis.cosine
+    );
+  } else {
+    println!(
+      "Cosine: {:.4} (threshold \
+       {:.4}, {})",
+      diagnosis.cosine,
+      diagnosis.score_threshold,
+      if diagnosis.passes_threshold {
+        "passed"
+      } else {
+        "not passed"
+      }
+    );
+  }
+  match diagnosis.candidate_rank {
+    | Some(rank) => {
+      println!(
+        "Candidate rank: {}",
+        rank
+      )
+    }
+    | None => {
+      println!(
+        "Candidate rank: not fetched"
+      )
+    }
+  }
+  if !diagnosis.missing_terms.is_empty()
+  {
+    println!(
+      "Query terms missing from the \
+       chunk: {}",
+      diagnosis
+        .missing_terms
+        .join(", ")
+    );
+  }
+  println!(
+    "Verdict: {} (top_k {})",
+    diagnosis.verdict, top_k
+  );
+  Ok(())
+}
+
+/// Prints the vector `embedder`
+/// produces for `text`, or for stdin
+/// when no text is given.
+pub(super) fn embed(
+  text: Option<String>,
+  embedder: &dyn Embedder
+) -> Result<()> {
+  let text = match text {
+    | Some(text) => text,
+    | None => {
+      io::read_to_string(io::stdin())
+        .context(
+          "read text from stdin"
+        )?
+    }
+  };
+  print!(
+    "{}",
+    json_vector(
+      &embedder.embed(&text)
+    )?
+  );
+  Ok(())
+}
+
+pub(super) fn status(
+  state: &State,
+  index: &VectorIndex,
+  config: &Config
+) -> Result<()> {
+  println!(
+    "Documents: {}",
+    state.documents.len()
+  );
+  println!(
+    "Chunks: {}",
+    state.chunks.len()
+  );
+  println!(
+    "Vocabulary: {} terms",
+    state
+      .stats
+      .document_frequency
+      .len()
+  );
+  println!(
+    "Average document length: {:.1} \
+     tokens",
+    state.stats.avg_length()
+  );
+  let stats = index.stats();
+  println!(
+    "Index: {} vectors, {} distinct \
+     terms, {} nonzero entries",
+    stats.entries,
+    stats.distinct_terms,
+    stats.nonzero_entries
+  );
+  println!(
+    "Index density: {:.4} (~{} bytes \
+     in memory)",
+    stats.average_density,
+    stats.approx_bytes
+  );
+  let state_path = storage::resolve(
+    config.stage1.storage.state_path()
+  );
+  let file_size = |path: &Path| {
+    fs::metadata(path)
+      .map_or(0, |metadata| {
+        metadata.len()
+      })
+  };
+  if state_path.exists() {
+    let state_bytes =
+      file_size(&state_path);
+    let index_bytes = file_size(
+      &State::index_path(&state_path)
+    );
+    println!(
+      "On disk: {} bytes (state {}, \
+       index {})",
+      state_bytes + index_bytes,
+      state_bytes,
+      index_bytes
+    );
+  }
+  if let Some(last) =
+    state.documents.last()
+  {
+    println!(
+      "Latest document: {}",
+      last.path
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use super::embedding::EntryBuilder;
+use super::ingest::{
+  build_chunker,
+  cap_chunks
+};
+use super::stats::recompute_stats;
+use crate::chunk::{
+  Chunk,
+  ChunkStrategy
+};
+use crate::config::Config;
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::language;
+use crate::section::{
+  assign_headings,
+  merge_chunks
+};
+use crate::state::State;
+
+/// Re-splits every document with the
+/// current chunk settings without
+/// reading the source files again.
+///
+/// Documents ingested with
+/// `keep_normalized` are re-split from
+/// their stored normalized text.
+/// Otherwise each document's text is
+/// rebuilt from its stored chunks in
+/// offset order, so this assumes the
+/// chunks cover the document
+/// contiguously: segments dropped at
+/// ingest (deduped or filtered) stay
+/// lost, and gaps between chunks are
+/// rejoined with a blank line. Raw
+/// source offsets cannot be recovered
+/// and are cleared. Returns the number
+/// of documents rechunked.
+pub(super) fn rechunk(
+  strategy: ChunkStrategy,
+  config: &Config,
+  state: &mut State,
+  index: &mut VectorIndex,
+  embedder: &dyn Embedder
+) -> usize {
+  let chunker =
+    build_chunker(strategy, config);
+  let entries =
+    EntryBuilder::new(config, embedder);
+  let detect_language = config
+    .stage1
+    .ingest
+    .detect_language;
+  let documents =
+    state.documents.clone();
+  let mut rechunked = 0;
+  for document in &documents {
+    let Some(text) = document
+      .normalized
+      .clone()
+      .or_else(|| {
+        reconstruct_text(
+          &state.chunks,
+          &document.id
+        )
+      })
+    else {
+      continue;
+    };
+    let run_id = state
+      .chunks
+      .iter()
+      .filter(|chunk| {
+        chunk.doc_id == document.id
+      })
+      .map(|chunk| chunk.run_id)
+      .max()
+      .unwrap_or_default();
+    state.chunks.retain(|chunk| {
+      chunk.doc_id != document.id
+    });
+    index.remove_document(&document.id);
+    let path_vector = entries
+      .path_vector(Path::new(
+        &document.path
+      ));
+    let mut chunks = cap_chunks(
+      chunker
+        .chunk(&document.id, &text),
+      &config.stage1.chunk,
+      Path::new(&document.path)
+    );
+    assign_headings(
+      &mut chunks,
+      &text,
+      Path::new(&document.path)
+    );
+    for chunk in &mut chunks {
+      chunk.run_id = run_id;
+      if detect_language {
+        chunk.language =
+          language::detect(&chunk.text);
+      }
+    }
+    entries.add_batch(
+      index,
+      &chunks,
+      &document.path,
+      &path_vector
+    );
+    state.chunks.extend(chunks);
+    rechunked += 1;
+  }
+  recompute_stats(state, config);
+  rechunked
+}
+
+/// Concatenates a document's chunks in
+/// offset order, skipping the parts of
+/// overlapping chunks already emitted.
+pub(super) fn reconstruct_text(
+  chunks: &[Chunk],
+  doc_id: &str
+) -> Option<String> {
+  let mut own: Vec<&Chunk> = chunks
+    .iter()
+    .filter(|chunk| {
+      chunk.doc_id == doc_id
+    })
+    .collect();
+  if own.is_empty() {
+    return None;
+  }
+  own.sort_by_key(|chunk| chunk.start);
+  Some(merge_chunks(&own))
+}
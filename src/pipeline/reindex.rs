@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::mem;
+use std::path::Path;
+
+use super::embedding::EntryBuilder;
+use crate::chunk::Chunk;
+use crate::config::Config;
+use crate::embedder::{
+  Embedder,
+  SparseVector
+};
+use crate::index::{
+  IndexEntry,
+  VectorIndex
+};
+use crate::state::State;
+
+#[derive(Debug, Default)]
+pub(super) struct ReindexSummary {
+  pub reembedded: usize,
+  pub reused:     usize
+}
+
+/// Rebuilds the index from
+/// `State::chunks` with the configured
+/// embedder. When
+/// `stage1.embedder.skip_unchanged` is
+/// on and `force` is not, a chunk whose
+/// stored entry has the same embed
+/// input hash and recorded term counts
+/// keeps its vector instead of being
+/// re-embedded. The remaining chunks
+/// are embedded in one batch.
+pub(super) fn reindex(
+  config: &Config,
+  force: bool,
+  state: &State,
+  index: &mut VectorIndex,
+  embedder: &dyn Embedder
+) -> ReindexSummary {
+  let skip_unchanged = config
+    .stage1
+    .embedder
+    .skip_unchanged
+    && !force;
+  let mut previous: HashMap<
+    String,
+    IndexEntry
+  > = mem::replace(
+    index,
+    VectorIndex::from_entries(
+      Vec::new()
+    )
+  )
+  .into_entries()
+  .into_iter()
+  .map(|entry| {
+    (entry.chunk_id.clone(), entry)
+  })
+  .collect();
+  let entries =
+    EntryBuilder::new(config, embedder);
+  let doc_path = |chunk: &Chunk| {
+    state
+      .find_document(&chunk.doc_id)
+      .map(|doc| doc.path.as_str())
+      .unwrap_or_default()
+  };
+  let mut reused: Vec<
+    Option<IndexEntry>
+  > = Vec::with_capacity(
+    state.chunks.len()
+  );
+  let mut pending = Vec::new();
+  for chunk in &state.chunks {
+    let stored =
+      previous.remove(&chunk.id);
+    match stored {
+      | Some(stored)
+        if skip_unchanged
+          && stored
+            .term_counts
+            .is_some()
+          && stored
+            .embed_input_hash
+            == Some(
+              entries.input_hash(
+                doc_path(chunk),
+                chunk
+              )
+            ) =>
+      {
+        reused.push(Some(stored));
+      }
+      | _ => {
+        reused.push(None);
+        pending.push(chunk);
+      }
+    }
+  }
+  let mut vectors = entries
+    .embed_chunks(&pending)
+    .into_iter();
+  let mut path_vectors: HashMap<
+    &str,
+    SparseVector
+  > = HashMap::new();
+  let mut summary =
+    ReindexSummary::default();
+  for (chunk, stored) in
+    state.chunks.iter().zip(reused)
+  {
+    if let Some(stored) = stored {
+      index.add_chunk(
+        stored.chunk_id,
+        stored.doc_id,
+        stored.vector,
+        stored.embed_input_hash,
+        stored.term_counts
+      );
+      summary.reused += 1;
+      continue;
+    }
+    let doc_path = doc_path(chunk);
+    let path_vector = path_vectors
+      .entry(doc_path)
+      .or_insert_with(|| {
+        entries.path_vector(Path::new(
+          doc_path
+        ))
+      });
+    entries.add_embedded(
+      index,
+      chunk,
+      vectors
+        .next()
+        .unwrap_or_default(),
+      doc_path,
+      path_vector
+    );
+    summary.reembedded += 1;
+  }
+  summary
+}
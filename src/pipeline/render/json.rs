@@ -0,0 +1,297 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::DocumentGroup;
+use crate::chunk::Chunk;
+use crate::embedder::SparseVector;
+use crate::index::VectorIndex;
+use crate::search::{
+  DocumentHit,
+  SearchHit
+};
+use crate::stage3::RagRun;
+use crate::state::Document;
+
+/// A hit with flat `document_path`,
+/// `chunk_id` and `chunk_text` fields
+/// for scripts.
+#[derive(Serialize)]
+struct JsonHit<'a> {
+  rank:          usize,
+  score:         f32,
+  document_path: &'a str,
+  chunk_id:      &'a str,
+  chunk_text:    &'a str,
+  /// The chunk's stored vector, with
+  /// `--include-vectors`
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  vector: Option<Vec<JsonTerm<'a>>>,
+  /// Hits in the cluster this hit
+  /// represents, with
+  /// `--cluster-results`
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  cluster_size:  Option<usize>
+}
+
+#[derive(Serialize)]
+struct JsonGroup<'a> {
+  document:   &'a Document,
+  best_score: f32,
+  hits:       Vec<JsonGroupHit<'a>>
+}
+
+#[derive(Serialize)]
+struct JsonGroupHit<'a> {
+  score: f32,
+  chunk: &'a Chunk
+}
+
+#[derive(Serialize)]
+struct JsonDocumentHit<'a> {
+  rank:       usize,
+  score:      f32,
+  document:   &'a Document,
+  best_chunk: JsonGroupHit<'a>
+}
+
+#[derive(Serialize)]
+struct JsonTerm<'a> {
+  term:   &'a str,
+  weight: f32
+}
+
+#[derive(Serialize)]
+struct JsonRun<'a> {
+  strategy: &'a str,
+  hits:     Vec<JsonHit<'a>>,
+  context:  Option<&'a str>,
+  prompt:   Option<&'a str>
+}
+
+fn json_hit(
+  rank: usize,
+  hit: &SearchHit
+) -> JsonHit<'_> {
+  JsonHit {
+    rank,
+    score: hit.score,
+    document_path: &hit.document.path,
+    chunk_id: &hit.chunk.id,
+    chunk_text: &hit.chunk.text,
+    vector: None,
+    cluster_size: None
+  }
+}
+
+fn to_json(
+  value: &impl Serialize
+) -> Result<String> {
+  let mut out =
+    serde_json::to_string_pretty(
+      value
+    )?;
+  out.push('\n');
+  Ok(out)
+}
+
+/// A JSON array of hits, each carrying
+/// its rank, score, document path,
+/// chunk ID and text.
+pub(crate) fn json_hits(
+  hits: &[SearchHit],
+  ranks: &[usize]
+) -> Result<String> {
+  to_json(
+    &ranks
+      .iter()
+      .zip(hits)
+      .map(|(rank, hit)| {
+        json_hit(*rank, hit)
+      })
+      .collect::<Vec<_>>()
+  )
+}
+
+/// Like [`json_hits`], with each hit's
+/// stored vector attached, heaviest
+/// terms first. Vectors longer than
+/// `max_terms` are cut to their
+/// heaviest terms with a warning.
+pub(crate) fn json_hits_with_vectors(
+  hits: &[SearchHit],
+  ranks: &[usize],
+  index: &VectorIndex,
+  max_terms: usize
+) -> Result<String> {
+  to_json(
+    &ranks
+      .iter()
+      .zip(hits)
+      .map(|(rank, hit)| {
+        let mut json =
+          json_hit(*rank, hit);
+        json.vector = index
+          .vector_for(&hit.chunk.id)
+          .map(|vector| {
+            let mut terms =
+              sorted_terms(vector);
+            if terms.len() > max_terms {
+              eprintln!(
+                "Warning: vector of \
+                 chunk {} cut to its \
+                 {} heaviest of {} \
+                 terms",
+                hit.chunk.id,
+                max_terms,
+                terms.len()
+              );
+              terms.truncate(max_terms);
+            }
+            terms
+          });
+        json
+      })
+      .collect::<Vec<_>>()
+  )
+}
+
+/// Like [`json_hits`] for cluster
+/// representatives, each with its
+/// `cluster_size`.
+pub(crate) fn json_clusters(
+  hits: &[SearchHit],
+  ranks: &[usize],
+  sizes: &[usize]
+) -> Result<String> {
+  to_json(
+    &ranks
+      .iter()
+      .zip(hits)
+      .zip(sizes)
+      .map(|((rank, hit), size)| {
+        JsonHit {
+          cluster_size: Some(*size),
+          ..json_hit(*rank, hit)
+        }
+      })
+      .collect::<Vec<_>>()
+  )
+}
+
+/// A JSON array of documents, each with
+/// its best score and nested hits.
+pub(crate) fn json_groups(
+  groups: &[DocumentGroup]
+) -> Result<String> {
+  to_json(
+    &groups
+      .iter()
+      .map(|group| {
+        JsonGroup {
+          document:   group.document,
+          best_score: group
+            .best_score(),
+          hits:       group
+            .hits
+            .iter()
+            .map(|hit| {
+              JsonGroupHit {
+                score: hit.score,
+                chunk: &hit.chunk
+              }
+            })
+            .collect()
+        }
+      })
+      .collect::<Vec<_>>()
+  )
+}
+
+/// A JSON array of `{term, weight}`
+/// objects, heaviest first (ties by
+/// term).
+pub(crate) fn json_vector(
+  vector: &SparseVector
+) -> Result<String> {
+  to_json(&sorted_terms(vector))
+}
+
+fn sorted_terms(
+  vector: &SparseVector
+) -> Vec<JsonTerm<'_>> {
+  let mut terms: Vec<JsonTerm> = vector
+    .iter()
+    .map(|(term, weight)| {
+      JsonTerm {
+        term,
+        weight: *weight
+      }
+    })
+    .collect();
+  terms.sort_by(|a, b| {
+    b.weight
+      .total_cmp(&a.weight)
+      .then_with(|| a.term.cmp(b.term))
+  });
+  terms
+}
+
+/// A JSON array of strategy runs with
+/// their hits, context, and prompt.
+pub(crate) fn json_rag(
+  runs: &[RagRun]
+) -> Result<String> {
+  to_json(
+    &runs
+      .iter()
+      .map(|run| {
+        JsonRun {
+          strategy: &run.strategy,
+          hits:     run
+            .hits
+            .iter()
+            .enumerate()
+            .map(|(idx, hit)| {
+              json_hit(idx + 1, hit)
+            })
+            .collect(),
+          context:  run
+            .context
+            .as_deref(),
+          prompt:   run
+            .prompt
+            .as_deref()
+        }
+      })
+      .collect::<Vec<_>>()
+  )
+}
+
+/// A JSON array of ranked documents,
+/// each with its rank and best chunk.
+pub(crate) fn json_documents(
+  documents: &[DocumentHit],
+  ranks: &[usize]
+) -> Result<String> {
+  to_json(
+    &ranks
+      .iter()
+      .zip(documents)
+      .map(|(rank, doc)| {
+        JsonDocumentHit {
+          rank:       *rank,
+          score:      doc.score,
+          document:   &doc.document,
+          best_chunk: JsonGroupHit {
+            score: doc.best.score,
+            chunk: &doc.best.chunk
+          }
+        }
+      })
+      .collect::<Vec<_>>()
+  )
+}
@@ -0,0 +1,551 @@
+mod json;
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use self::json::{
+  json_clusters,
+  json_documents,
+  json_groups,
+  json_hits
+};
+pub(super) use self::json::{
+  json_hits_with_vectors,
+  json_rag,
+  json_vector
+};
+use crate::args::OutputFormat;
+use crate::embedder::SparseVector;
+use crate::search::{
+  DocumentHit,
+  SearchHit
+};
+use crate::stage3::RagRun;
+use crate::state::Document;
+
+/// Hits that share a source document,
+/// in rank order.
+pub(super) struct DocumentGroup<'a> {
+  pub(super) document: &'a Document,
+  pub(super) hits: Vec<&'a SearchHit>
+}
+
+impl DocumentGroup<'_> {
+  fn best_score(&self) -> f32 {
+    self
+      .hits
+      .iter()
+      .map(|hit| hit.score)
+      .fold(f32::NEG_INFINITY, f32::max)
+  }
+}
+
+/// Collects `hits` per document and
+/// orders the groups by each
+/// document's best hit score.
+pub(super) fn group_by_document(
+  hits: &[SearchHit]
+) -> Vec<DocumentGroup<'_>> {
+  let mut groups: Vec<DocumentGroup> =
+    Vec::new();
+  for hit in hits {
+    match groups.iter_mut().find(
+      |group| {
+        group.document.id
+          == hit.document.id
+      }
+    ) {
+      | Some(group) => {
+        group.hits.push(hit)
+      }
+      | None => {
+        groups.push(DocumentGroup {
+          document: &hit.document,
+          hits:     vec![hit]
+        })
+      }
+    }
+  }
+  groups.sort_by(|a, b| {
+    b.best_score()
+      .total_cmp(&a.best_score())
+  });
+  groups
+}
+
+/// Ranks `1..=len`, for output that
+/// shows every retrieved result.
+pub(super) fn sequential_ranks(
+  len: usize
+) -> Vec<usize> {
+  (1..=len).collect()
+}
+
+/// Renders search (or similar) hits in
+/// `format`, flat or grouped by
+/// document. Flat output numbers each
+/// hit with its entry in `ranks`.
+pub(super) fn render_hits(
+  hits: &[SearchHit],
+  ranks: &[usize],
+  format: OutputFormat,
+  group_by_doc: bool
+) -> Result<String> {
+  if !group_by_doc {
+    return match format {
+      | OutputFormat::Text => {
+        Ok(text_hits(hits, ranks))
+      }
+      | OutputFormat::Markdown => {
+        Ok(markdown_hits(hits, ranks))
+      }
+      | OutputFormat::Json => {
+        json_hits(hits, ranks)
+      }
+    };
+  }
+  let groups = group_by_document(hits);
+  match format {
+    | OutputFormat::Text => {
+      Ok(text_groups(&groups))
+    }
+    | OutputFormat::Markdown => {
+      Ok(markdown_groups(&groups))
+    }
+    | OutputFormat::Json => {
+      json_groups(&groups)
+    }
+  }
+}
+
+/// Renders one representative hit per
+/// cluster in `format`, numbered with
+/// its entry in `ranks` and labeled
+/// with its cluster's entry in
+/// `sizes`.
+pub(super) fn render_clusters(
+  hits: &[SearchHit],
+  ranks: &[usize],
+  sizes: &[usize],
+  format: OutputFormat
+) -> Result<String> {
+  match format {
+    | OutputFormat::Text => {
+      Ok(text_clusters(
+        hits, ranks, sizes
+      ))
+    }
+    | OutputFormat::Markdown => {
+      let mut out = String::new();
+      for ((rank, hit), size) in ranks
+        .iter()
+        .zip(hits)
+        .zip(sizes)
+      {
+        let _ = writeln!(
+          out,
+          "## Result {}\n\n- Cluster: \
+           {} hits",
+          rank, size
+        );
+        push_markdown_hit(
+          &mut out, hit
+        );
+      }
+      Ok(out)
+    }
+    | OutputFormat::Json => {
+      json_clusters(hits, ranks, sizes)
+    }
+  }
+}
+
+/// Renders documents ranked by
+/// aggregated score in `format`, each
+/// with its best chunk and numbered
+/// with its entry in `ranks`.
+pub(super) fn render_documents(
+  documents: &[DocumentHit],
+  ranks: &[usize],
+  format: OutputFormat
+) -> Result<String> {
+  let mut out = String::new();
+  match format {
+    | OutputFormat::Text => {
+      for (rank, doc) in
+        ranks.iter().zip(documents)
+      {
+        let _ = writeln!(
+          out,
+          "Document {} (score: \
+           {:.3}): {}",
+          rank,
+          doc.score,
+          doc.document.path
+        );
+        let _ = writeln!(
+          out,
+          " → Best chunk {} (score: \
+           {:.3}): {}",
+          doc.best.chunk.id,
+          doc.best.score,
+          doc.best.chunk.text.trim()
+        );
+        out.push_str("----------\n");
+      }
+    }
+    | OutputFormat::Markdown => {
+      for (rank, doc) in
+        ranks.iter().zip(documents)
+      {
+        let _ = writeln!(
+          out,
+          "## Document {}: \
+           [{}](<{}>)\n\n- **Score: \
+           {:.3}**",
+          rank,
+          doc.document.path,
+          doc.document.path,
+          doc.score
+        );
+        push_markdown_chunk(
+          &mut out, &doc.best
+        );
+      }
+    }
+    | OutputFormat::Json => {
+      return json_documents(
+        documents, ranks
+      );
+    }
+  }
+  Ok(out)
+}
+
+pub(super) fn text_hits(
+  hits: &[SearchHit],
+  ranks: &[usize]
+) -> String {
+  let mut out = String::new();
+  for (rank, hit) in
+    ranks.iter().zip(hits)
+  {
+    push_text_hit(&mut out, *rank, hit);
+    out.push_str("----------\n");
+  }
+  out
+}
+
+/// Like [`text_hits`] for cluster
+/// representatives, each followed by
+/// the size of its cluster.
+fn text_clusters(
+  hits: &[SearchHit],
+  ranks: &[usize],
+  sizes: &[usize]
+) -> String {
+  let mut out = String::new();
+  for ((rank, hit), size) in
+    ranks.iter().zip(hits).zip(sizes)
+  {
+    push_text_hit(&mut out, *rank, hit);
+    let _ = writeln!(
+      out,
+      " → Cluster: {} hits ({} \
+       collapsed)",
+      size,
+      size - 1
+    );
+    out.push_str("----------\n");
+  }
+  out
+}
+
+fn push_text_hit(
+  out: &mut String,
+  rank: usize,
+  hit: &SearchHit
+) {
+  let _ = writeln!(
+    out,
+    "Result {} (score: {:.3})",
+    rank, hit.score
+  );
+  let _ = writeln!(
+    out,
+    " → Document: {}",
+    hit.document.path
+  );
+  let _ = writeln!(
+    out,
+    " → Chunk ID: {}",
+    hit.chunk.id
+  );
+  let _ = writeln!(
+    out,
+    " → Chunk: {}",
+    hit.chunk.text.trim()
+  );
+}
+
+/// One `Document:` header per group
+/// with its chunks indented beneath.
+pub(super) fn text_groups(
+  groups: &[DocumentGroup]
+) -> String {
+  let mut out = String::new();
+  for group in groups {
+    let _ = writeln!(
+      out,
+      "Document: {} (best score: \
+       {:.3})",
+      group.document.path,
+      group.best_score()
+    );
+    for hit in &group.hits {
+      let _ = writeln!(
+        out,
+        " → Chunk {} (score: {:.3}): \
+         {}",
+        hit.chunk.id,
+        hit.score,
+        hit.chunk.text.trim()
+      );
+    }
+    out.push_str("----------\n");
+  }
+  out
+}
+
+/// Prints the strategy runs of a RAG
+/// query in `format`.
+pub(super) fn output_rag(
+  runs: &[RagRun],
+  format: OutputFormat
+) -> Result<()> {
+  match format {
+    | OutputFormat::Text => {
+      print_rag(runs)
+    }
+    | OutputFormat::Markdown => {
+      print!("{}", markdown_rag(runs))
+    }
+    | OutputFormat::Json => {
+      print!("{}", json_rag(runs)?)
+    }
+  }
+  Ok(())
+}
+
+fn print_rag(runs: &[RagRun]) {
+  for run in runs {
+    let Some(prompt) = &run.prompt
+    else {
+      println!(
+        "Strategy {} produced no \
+         reranked hits.",
+        run.strategy
+      );
+      continue;
+    };
+    println!(
+      "=== Strategy: {} ===",
+      run.strategy
+    );
+    for (rank, hit) in
+      run.hits.iter().enumerate()
+    {
+      println!(
+        "Result {} [score: {:.3}] → {}",
+        rank + 1,
+        hit.score,
+        hit
+          .chunk
+          .text
+          .lines()
+          .next()
+          .unwrap_or("")
+          .trim()
+      );
+      println!(
+        "  Document: {} [{}-{}/{}]",
+        hit.document.path,
+        hit.chunk.start,
+        hit.chunk.end,
+        hit.chunk.strategy
+      );
+    }
+    println!("Prompt:\n{}", prompt);
+  }
+}
+
+/// One `## Result` section per hit,
+/// with the chunk text fenced.
+pub(super) fn markdown_hits(
+  hits: &[SearchHit],
+  ranks: &[usize]
+) -> String {
+  let mut out = String::new();
+  for (rank, hit) in
+    ranks.iter().zip(hits)
+  {
+    let _ = writeln!(
+      out,
+      "## Result {}\n",
+      rank
+    );
+    push_markdown_hit(&mut out, hit);
+  }
+  out
+}
+
+/// One `## Document` section per group,
+/// each listing its hits.
+pub(super) fn markdown_groups(
+  groups: &[DocumentGroup]
+) -> String {
+  let mut out = String::new();
+  for group in groups {
+    let _ = writeln!(
+      out,
+      "## Document: [{}](<{}>)\n",
+      group.document.path,
+      group.document.path
+    );
+    for (rank, hit) in
+      group.hits.iter().enumerate()
+    {
+      let _ = writeln!(
+        out,
+        "### Result {}\n",
+        rank + 1
+      );
+      push_markdown_score(
+        &mut out, hit
+      );
+      push_markdown_chunk(
+        &mut out, hit
+      );
+    }
+  }
+  out
+}
+
+/// One `## Strategy` section per run,
+/// each listing its hits and the
+/// fenced prompt.
+fn markdown_rag(
+  runs: &[RagRun]
+) -> String {
+  let mut out = String::new();
+  for run in runs {
+    let _ = writeln!(
+      out,
+      "## Strategy: {}\n",
+      run.strategy
+    );
+    let Some(prompt) = &run.prompt
+    else {
+      out.push_str(
+        "_No reranked hits._\n\n"
+      );
+      continue;
+    };
+    for (rank, hit) in
+      run.hits.iter().enumerate()
+    {
+      let _ = writeln!(
+        out,
+        "### Result {}\n",
+        rank + 1
+      );
+      push_markdown_hit(&mut out, hit);
+    }
+    out.push_str("### Prompt\n\n");
+    push_fenced(&mut out, prompt);
+  }
+  out
+}
+
+fn push_markdown_hit(
+  out: &mut String,
+  hit: &SearchHit
+) {
+  push_markdown_score(out, hit);
+  let _ = writeln!(
+    out,
+    "- Document: [{}](<{}>)",
+    hit.document.path,
+    hit.document.path
+  );
+  push_markdown_chunk(out, hit);
+}
+
+fn push_markdown_score(
+  out: &mut String,
+  hit: &SearchHit
+) {
+  let _ = writeln!(
+    out,
+    "- **Score: {:.3}**",
+    hit.score
+  );
+}
+
+fn push_markdown_chunk(
+  out: &mut String,
+  hit: &SearchHit
+) {
+  let _ = writeln!(
+    out,
+    "- Chunk ID: `{}`\n",
+    hit.chunk.id
+  );
+  push_fenced(
+    out,
+    hit.chunk.text.trim()
+  );
+}
+
+/// Fences `text` with one more backtick
+/// than its longest backtick run, so
+/// embedded fences cannot close it.
+fn push_fenced(
+  out: &mut String,
+  text: &str
+) {
+  let longest = text
+    .split(|c| c != '`')
+    .map(str::len)
+    .max()
+    .unwrap_or(0);
+  let fence =
+    "`".repeat((longest + 1).max(3));
+  let _ = writeln!(
+    out,
+    "{}text\n{}\n{}\n",
+    fence, text, fence
+  );
+}
+
+/// `term:weight` pairs, heaviest first
+/// (ties by term).
+pub(super) fn text_vector(
+  vector: &SparseVector
+) -> String {
+  let mut terms: Vec<(&String, &f32)> =
+    vector.iter().collect();
+  terms.sort_by(|a, b| {
+    b.1
+      .total_cmp(a.1)
+      .then_with(|| a.0.cmp(b.0))
+  });
+  terms
+    .iter()
+    .map(|(term, weight)| {
+      format!("{}:{:.3}", term, weight)
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
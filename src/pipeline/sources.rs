@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::fs::{
+  self,
+  File
+};
+use std::io::Read;
+use std::ops::Deref;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use anyhow::{
+  Context,
+  Result,
+  bail
+};
+use memmap2::Mmap;
+use walkdir::WalkDir;
+
+use crate::config::{
+  Stage1Ingest,
+  Stage1Storage
+};
+use crate::frontmatter;
+
+pub(super) fn collect_sources(
+  path: &Path,
+  allowed_exts: &[String]
+) -> Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  if path.is_file() {
+    files.push(path.to_path_buf());
+  } else {
+    for entry in WalkDir::new(path)
+      .into_iter()
+      .filter_map(Result::ok)
+    {
+      if !entry.file_type().is_file() {
+        continue;
+      }
+      if has_allowed_extension(
+        entry.path(),
+        allowed_exts
+      ) {
+        files.push(entry.into_path());
+      }
+    }
+  }
+  Ok(files)
+}
+
+/// The files listed in `manifest`, one
+/// path per line; blank lines and `#`
+/// comments are skipped and relative
+/// paths resolve against the
+/// manifest's directory. Fails on a
+/// listed file that is missing or has
+/// a disallowed extension.
+pub(super) fn manifest_sources(
+  manifest: &Path,
+  allowed_exts: &[String]
+) -> Result<Vec<PathBuf>> {
+  let listing =
+    fs::read_to_string(manifest)
+      .with_context(|| {
+        format!(
+          "read manifest {:?}",
+          manifest
+        )
+      })?;
+  let base = manifest
+    .parent()
+    .unwrap_or_else(|| Path::new(""));
+  let mut files = Vec::new();
+  for line in listing.lines() {
+    let line = line.trim();
+    if line.is_empty()
+      || line.starts_with('#')
+    {
+      continue;
+    }
+    let file = base.join(line);
+    if !file.is_file() {
+      bail!(
+        "manifest {:?} lists missing \
+         file {:?}",
+        manifest,
+        file
+      );
+    }
+    if !has_allowed_extension(
+      &file,
+      allowed_exts
+    ) {
+      bail!(
+        "manifest {:?} lists {:?}, \
+         whose extension is not in \
+         stage1.ingest.extensions",
+        manifest,
+        file
+      );
+    }
+    files.push(file);
+  }
+  Ok(files)
+}
+
+pub(super) fn has_allowed_extension(
+  path: &Path,
+  allowed_exts: &[String]
+) -> bool {
+  let Some(ext) = path.extension()
+  else {
+    return false;
+  };
+  let candidate = ext
+    .to_string_lossy()
+    .to_lowercase();
+  allowed_exts.iter().any(|allowed| {
+    allowed.to_lowercase() == candidate
+  })
+}
+
+/// The form of `file` stored in
+/// [`Document::path`]: its canonical
+/// path, made relative to the root
+/// under `relative-to` path mode.
+pub(super) fn document_path(
+  file: &Path,
+  storage: &Stage1Storage
+) -> String {
+  storage
+    .stored_path(&canonical_path(file))
+}
+
+/// Canonical form of `file`. Files
+/// that no longer exist resolve
+/// through their parent directory.
+fn canonical_path(
+  file: &Path
+) -> String {
+  if let Ok(path) =
+    fs::canonicalize(file)
+  {
+    return path
+      .to_string_lossy()
+      .into();
+  }
+  file
+    .parent()
+    .zip(file.file_name())
+    .and_then(|(parent, name)| {
+      fs::canonicalize(parent)
+        .ok()
+        .map(|parent| parent.join(name))
+    })
+    .unwrap_or_else(|| {
+      file.to_path_buf()
+    })
+    .to_string_lossy()
+    .into()
+}
+
+/// Returns where the text to chunk
+/// starts and the front-matter tags,
+/// when `parse_frontmatter` is on and
+/// `content` opens with a well-formed
+/// block.
+pub(super) fn split_frontmatter(
+  content: &str,
+  file: &Path,
+  ingest_cfg: &Stage1Ingest
+) -> (usize, BTreeMap<String, String>) {
+  if !ingest_cfg.parse_frontmatter {
+    return (0, BTreeMap::new());
+  }
+  match frontmatter::parse(content) {
+    | Ok(Some(front)) => {
+      (front.body_start, front.tags)
+    }
+    | Ok(None) => (0, BTreeMap::new()),
+    | Err(err) => {
+      eprintln!(
+        "Warning: keeping \
+         front-matter of {:?} as \
+         content: {:#}",
+        file, err
+      );
+      (0, BTreeMap::new())
+    }
+  }
+}
+
+/// A source file's text: read into a
+/// `String`, or borrowed from a memory
+/// map that stays open while the text
+/// is in use.
+pub(super) enum Source {
+  Read(String),
+  Mapped(Mmap)
+}
+
+impl Deref for Source {
+  type Target = str;
+
+  fn deref(&self) -> &str {
+    match self {
+      | Self::Read(text) => text,
+      // SAFETY: `read_mapped` only
+      // builds a map whose bytes
+      // validated as UTF-8.
+      | Self::Mapped(map) => unsafe {
+        std::str::from_utf8_unchecked(
+          map
+        )
+      }
+    }
+  }
+}
+
+/// Maps `file` read-only, keeping the
+/// map (rather than a copy of it) once
+/// its bytes validate as UTF-8.
+fn read_mapped(
+  file: &Path
+) -> Result<Source> {
+  let handle = File::open(file)?;
+  if handle.metadata()?.len() == 0 {
+    return Ok(Source::Read(
+      String::new()
+    ));
+  }
+  // SAFETY: the map is read-only; a
+  // file truncated or rewritten
+  // concurrently can still fault or
+  // change under it, as with any
+  // mapped read.
+  let map =
+    unsafe { Mmap::map(&handle)? };
+  std::str::from_utf8(&map)?;
+  Ok(Source::Mapped(map))
+}
+
+/// Reads `file`, honoring
+/// `max_file_bytes`: oversized files
+/// are skipped (`None`) unless
+/// `truncate_large` is set, in which
+/// case only their leading bytes are
+/// read, cut back to a UTF-8 boundary.
+pub(super) fn read_source(
+  file: &Path,
+  ingest_cfg: &Stage1Ingest
+) -> Result<Option<Source>> {
+  let read_err =
+    || format!("read file {:?}", file);
+  let oversized =
+    match ingest_cfg.max_file_bytes {
+      | Some(max) => {
+        let size = fs::metadata(file)
+          .with_context(read_err)?
+          .len();
+        (size > max)
+          .then_some((max, size))
+      }
+      | None => None
+    };
+  let Some((max, size)) = oversized
+  else {
+    if ingest_cfg.mmap {
+      return read_mapped(file)
+        .with_context(read_err)
+        .map(Some);
+    }
+    return fs::read_to_string(file)
+      .with_context(read_err)
+      .map(|text| {
+        Some(Source::Read(text))
+      });
+  };
+  if !ingest_cfg.truncate_large {
+    eprintln!(
+      "Warning: skipping {:?}: {} \
+       bytes exceeds max_file_bytes \
+       ({})",
+      file, size, max
+    );
+    return Ok(None);
+  }
+  eprintln!(
+    "Warning: truncating {:?} to {} \
+     of {} bytes",
+    file, max, size
+  );
+  let mut bytes = Vec::new();
+  File::open(file)
+    .and_then(|handle| {
+      handle
+        .take(max)
+        .read_to_end(&mut bytes)
+    })
+    .with_context(read_err)?;
+  if let Err(err) =
+    std::str::from_utf8(&bytes)
+    && err.error_len().is_none()
+  {
+    bytes.truncate(err.valid_up_to());
+  }
+  String::from_utf8(bytes)
+    .with_context(read_err)
+    .map(|text| {
+      Some(Source::Read(text))
+    })
+}
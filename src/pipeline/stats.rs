@@ -0,0 +1,43 @@
+use crate::config::Config;
+use crate::corpus_stats::CorpusStats;
+use crate::index::VectorIndex;
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+/// Drops a document from the state,
+/// the index, and the corpus stats.
+pub(super) fn remove_document(
+  state: &mut State,
+  index: &mut VectorIndex,
+  doc_id: &str,
+  tokenizer: &Tokenizer
+) {
+  if let Some(document) =
+    state.find_document(doc_id).cloned()
+  {
+    state.stats.remove_document(
+      &document,
+      &state.chunks,
+      tokenizer
+    );
+  }
+  state.remove_document(doc_id);
+  index.remove_document(doc_id);
+}
+
+/// Rebuilds the corpus stats from every
+/// stored document, for when chunks
+/// change wholesale or the stats are
+/// missing.
+pub(super) fn recompute_stats(
+  state: &mut State,
+  config: &Config
+) {
+  state.stats = CorpusStats::recompute(
+    &state.documents,
+    &state.chunks,
+    &Tokenizer::from_config(
+      &config.stage1.embedder
+    )
+  );
+}
@@ -0,0 +1,294 @@
+use super::*;
+
+#[test]
+fn idf_smoothing_shapes_stored_and_query_vectors()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.embedder.kind =
+        EmbedderKind::TfIdf;
+      ingest_text(
+        path,
+        &config,
+        "a.txt",
+        "whale ship",
+        ChunkStrategy::Structured
+      )?;
+      ingest_text(
+        path,
+        &config,
+        "b.txt",
+        "whale ahab",
+        ChunkStrategy::Structured
+      )?;
+      let vectors = |smoothing| {
+        let mut config = config.clone();
+        config
+          .stage1
+          .embedder
+          .idf_smoothing = smoothing;
+        run(
+          Command::Reindex {
+            force: false
+          },
+          config.clone()
+        )?;
+        let embedder = build_embedder(
+          EmbedderKind::TfIdf,
+          &config
+        )?;
+        anyhow::Ok((
+          load_index(&config)?
+            .entries()[0]
+            .vector
+            .clone(),
+          embedder.embed_query("whale")
+        ))
+      };
+      let (plain_stored, plain_query) =
+        vectors(IdfSmoothing::None)?;
+      let (smooth_stored, smooth_query) =
+        vectors(IdfSmoothing::PlusOne)?;
+      // Every document has "whale", so
+      // unsmoothed IDF zeroes it out.
+      assert!(
+        !plain_stored
+          .contains_key("whale")
+      );
+      assert!(
+        smooth_stored
+          .contains_key("whale")
+      );
+      assert_ne!(
+        plain_stored,
+        smooth_stored
+      );
+      assert_ne!(
+        plain_query,
+        smooth_query
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn embed_prints_the_configured_vector()
+-> Result<()> {
+  let config = Config::default();
+  let embedder =
+    build_configured_embedder(&config)?;
+  let text = "whale ship whale sea";
+  let json: serde_json::Value =
+    serde_json::from_str(
+      &render::json_vector(
+        &embedder.embed(text)
+      )?
+    )?;
+  let terms =
+    json.as_array().expect("array");
+  let printed: HashMap<String, f32> =
+    terms
+      .iter()
+      .map(|term| {
+        (
+          term["term"]
+            .as_str()
+            .unwrap()
+            .to_string(),
+          term["weight"]
+            .as_f64()
+            .unwrap() as f32
+        )
+      })
+      .collect();
+  assert_eq!(
+    printed,
+    build_configured_embedder(&config)?
+      .embed(text)
+  );
+  assert_eq!(terms[0]["term"], "whale");
+  Ok(())
+}
+
+#[test]
+fn frozen_vocab_rejects_new_terms()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .embedder
+        .frozen_vocab = true;
+      ingest_text(
+        path,
+        &config,
+        "base.txt",
+        "whale ship sea",
+        ChunkStrategy::Structured
+      )?;
+      let state = ingest_text(
+        path,
+        &config,
+        "novel.txt",
+        "kraken whale",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      let vocabulary =
+        index.vocabulary();
+      assert!(
+        vocabulary.contains("whale")
+      );
+      assert!(
+        !vocabulary.contains("kraken")
+      );
+      assert!(
+        state
+          .stats
+          .document_frequency
+          .contains_key("whale")
+      );
+      assert!(
+        !state
+          .stats
+          .document_frequency
+          .contains_key("kraken")
+      );
+      assert!(state.chunks.iter().any(
+        |chunk| {
+          chunk.text.contains("kraken")
+        }
+      ));
+      let embedder =
+        FrozenVocabEmbedder::new(
+          build_configured_embedder(
+            &config
+          )?,
+          vocabulary
+        );
+      assert!(
+        embedder
+          .embed("kraken")
+          .is_empty()
+      );
+      Ok(())
+    }
+  )
+}
+
+/// Tags query and document vectors
+/// with different marker terms; plain
+/// `embed` must not be used.
+struct AsymmetricEmbedder;
+
+impl AsymmetricEmbedder {
+  fn tagged(
+    text: &str,
+    marker: &str
+  ) -> crate::embedder::SparseVector {
+    let mut vector =
+      crate::embedder::TfEmbedder::new(
+        1
+      )
+      .embed(text);
+    vector.insert(marker.into(), 1.0);
+    vector
+  }
+}
+
+impl Embedder for AsymmetricEmbedder {
+  fn name(&self) -> String {
+    "asymmetric".into()
+  }
+
+  fn embed(
+    &self,
+    _text: &str
+  ) -> crate::embedder::SparseVector {
+    unreachable!(
+      "query and document paths use \
+       their own hooks"
+    )
+  }
+
+  fn embed_query(
+    &self,
+    text: &str
+  ) -> crate::embedder::SparseVector {
+    Self::tagged(text, "query-side")
+  }
+
+  fn embed_document(
+    &self,
+    text: &str
+  ) -> crate::embedder::SparseVector {
+    Self::tagged(text, "document-side")
+  }
+
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize {
+    text.split_whitespace().count()
+  }
+}
+
+#[test]
+fn asymmetric_embedders_split_query_and_document_paths()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let file = path.join("asym.txt");
+    fs::write(&file, "whale ship")?;
+    let mut state = State::default();
+    let mut index =
+      VectorIndex::from_entries(
+        Vec::new()
+      );
+    ingest(
+      &file,
+      IngestOptions {
+        from_manifest:   false,
+        strategy:
+          ChunkStrategy::Structured,
+        emit_word_tally: false,
+        emit_normalized: false
+      },
+      &config,
+      &mut state,
+      &mut index,
+      &AsymmetricEmbedder
+    )?;
+    let vocabulary = index.vocabulary();
+    assert!(
+      vocabulary
+        .contains("document-side")
+    );
+    assert!(
+      !vocabulary
+        .contains("query-side")
+    );
+    let (hits, trace) =
+      crate::search::trace_search(
+        &AsymmetricEmbedder,
+        "whale",
+        1,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index
+      )?;
+    assert_eq!(hits.len(), 1);
+    assert!(
+      trace
+        .query_vector
+        .contains_key("query-side")
+    );
+    assert!(
+      !trace
+        .query_vector
+        .contains_key("document-side")
+    );
+    Ok(())
+  })
+}
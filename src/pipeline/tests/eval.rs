@@ -0,0 +1,547 @@
+use super::*;
+
+#[test]
+fn evaluation_logs_runs() -> Result<()>
+{
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage2.enabled = true;
+      config.stage2.log_evaluation =
+        true;
+      config.stage2.runs_dir = path
+        .join("runs")
+        .to_string_lossy()
+        .into_owned();
+      config.stage2.embedder_kinds =
+        vec![EmbedderKind::Tf];
+      config
+        .stage2
+        .evaluation
+        .queries =
+        vec![EvaluationQuery {
+          name:           "doc".into(),
+          query:          "alpha"
+            .into(),
+          expected_terms: vec![
+            "alpha".into(),
+          ],
+          top_k:          Some(1)
+        }];
+      let corpus =
+        path.join("doc3.txt");
+      let mut file =
+        File::create(&corpus)?;
+      writeln!(file, "alpha beta")?;
+      run(
+        Command::Ingest {
+          path:            Some(
+            corpus.clone()
+          ),
+          from_manifest:   None,
+          strategy:
+            ChunkStrategy::Fixed,
+          emit_word_tally: false,
+          emit_normalized: false
+        },
+        config.clone()
+      )?;
+      let state_path = PathBuf::from(
+        &config
+          .stage1
+          .storage
+          .state_file
+      );
+      let state =
+        State::load_from(&state_path)?;
+      let index = load_index(&config)?;
+      evaluation::run_evaluation(
+        &config, &state, &index, false
+      )?;
+      let mut found = false;
+      for entry in WalkDir::new(
+        &config.stage2.runs_dir
+      )
+      .into_iter()
+      .filter_map(Result::ok)
+      {
+        if entry
+          .path()
+          .extension()
+          .and_then(|ext| ext.to_str())
+          == Some("json")
+        {
+          found = true;
+          break;
+        }
+      }
+      assert!(found);
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn evaluation_sweeps_chunk_strategies()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage2.enabled = true;
+      config.stage2.runs_dir = path
+        .join("runs")
+        .to_string_lossy()
+        .into_owned();
+      config.stage2.embedder_kinds =
+        vec![EmbedderKind::Tf];
+      config.stage2.chunk_strategies = vec![
+        ChunkStrategy::Structured,
+        ChunkStrategy::Fixed,
+      ];
+      config
+        .stage2
+        .evaluation
+        .queries =
+        vec![EvaluationQuery {
+          name:           "whale"
+            .into(),
+          query:          "whale"
+            .into(),
+          expected_terms: vec![
+            "whale".into(),
+          ],
+          top_k:          Some(2)
+        }];
+      let state = ingest_text(
+        path,
+        &config,
+        "sweep.txt",
+        "the whale swam\n\nthe ship \
+         sailed",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      evaluation::run_evaluation(
+        &config, &state, &index, false
+      )?;
+      let mut strategies = Vec::new();
+      for entry in WalkDir::new(
+        &config.stage2.runs_dir
+      )
+      .into_iter()
+      .filter_map(Result::ok)
+      .filter(|entry| {
+        entry.file_type().is_file()
+      }) {
+        let run: serde_json::Value =
+          serde_json::from_str(
+            &fs::read_to_string(
+              entry.path()
+            )?
+          )?;
+        strategies.push(
+          run["chunk_strategy"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+        );
+      }
+      strategies.sort();
+      assert_eq!(strategies, vec![
+        "fixed",
+        "structured"
+      ]);
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn evaluation_compares_rankings()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage2.enabled = true;
+      config.stage2.runs_dir = path
+        .join("runs")
+        .to_string_lossy()
+        .into_owned();
+      config.stage2.embedder_kinds =
+        vec![EmbedderKind::Tf];
+      config.stage2.rankings = vec![
+        crate::config::Ranking::Cosine,
+        crate::config::Ranking::Bm25,
+      ];
+      config
+        .stage2
+        .evaluation
+        .queries =
+        vec![EvaluationQuery {
+          name:           "whale"
+            .into(),
+          query:          "whale"
+            .into(),
+          expected_terms: vec![
+            "whale".into(),
+          ],
+          top_k:          Some(2)
+        }];
+      let state = ingest_text(
+        path,
+        &config,
+        "rankings.txt",
+        "the whale swam\n\nthe ship \
+         sailed",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      evaluation::run_evaluation(
+        &config, &state, &index, false
+      )?;
+      let mut rankings = Vec::new();
+      for entry in WalkDir::new(
+        &config.stage2.runs_dir
+      )
+      .into_iter()
+      .filter_map(Result::ok)
+      .filter(|entry| {
+        entry.file_type().is_file()
+      }) {
+        let run: serde_json::Value =
+          serde_json::from_str(
+            &fs::read_to_string(
+              entry.path()
+            )?
+          )?;
+        assert_eq!(
+          run["metrics"]["recall"],
+          1.0
+        );
+        rankings.push(
+          run["ranking"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+        );
+      }
+      rankings.sort();
+      assert_eq!(rankings, vec![
+        "bm25", "cosine"
+      ]);
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn parallel_evaluation_matches_sequential()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage2.enabled = true;
+      config.stage2.embedder_kinds =
+        vec![EmbedderKind::Tf];
+      let words = [
+        "whale", "ship", "harbor",
+        "captain", "crew", "sea"
+      ];
+      config
+        .stage2
+        .evaluation
+        .queries = (0..40)
+        .map(|idx| {
+          let word =
+            words[idx % words.len()];
+          EvaluationQuery {
+            name:           format!(
+              "q{}",
+              idx
+            ),
+            query:          word.into(),
+            expected_terms: vec![
+              word.into(),
+            ],
+            top_k:          Some(
+              1 + idx % 3
+            )
+          }
+        })
+        .collect();
+      let state = ingest_text(
+        path,
+        &config,
+        "parallel.txt",
+        "the whale swam\n\nthe ship \
+         left the harbor\n\nthe \
+         captain and crew\n\nopen sea",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      let mut runs = Vec::new();
+      for parallel in [false, true] {
+        config
+          .stage2
+          .parallel_queries = parallel;
+        config.stage2.runs_dir = path
+          .join(format!(
+            "runs-{}",
+            parallel
+          ))
+          .to_string_lossy()
+          .into_owned();
+        evaluation::run_evaluation(
+          &config, &state, &index,
+          false
+        )?;
+        let file = WalkDir::new(
+          &config.stage2.runs_dir
+        )
+        .into_iter()
+        .filter_map(Result::ok)
+        .find(|entry| {
+          entry.file_type().is_file()
+        })
+        .expect("run file");
+        let run: serde_json::Value =
+          serde_json::from_str(
+            &fs::read_to_string(
+              file.path()
+            )?
+          )?;
+        let names: Vec<_> = run
+          ["queries"]
+          .as_array()
+          .expect("queries")
+          .iter()
+          .map(|query| {
+            query["name"].clone()
+          })
+          .collect();
+        let metrics =
+          ["recall", "mrr", "ndcg"]
+            .map(|key| {
+              run["metrics"][key]
+                .clone()
+            });
+        runs.push((names, metrics));
+      }
+      assert_eq!(runs[0], runs[1]);
+      assert_eq!(
+        runs[0].0.first(),
+        Some(&serde_json::json!("q0"))
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn threshold_sweep_reports_each_threshold()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage2.enabled = true;
+      config.stage2.runs_dir = path
+        .join("runs")
+        .to_string_lossy()
+        .into_owned();
+      config.stage2.embedder_kinds =
+        vec![EmbedderKind::Tf];
+      config.stage2.threshold_sweep =
+        vec![0.0, 0.3, 0.6, 0.95];
+      config
+        .stage2
+        .evaluation
+        .queries = vec![
+        EvaluationQuery {
+          name:           "whale"
+            .into(),
+          query:          "white whale"
+            .into(),
+          expected_terms: vec![
+            "whale".into(),
+            "white".into(),
+          ],
+          top_k:          Some(3)
+        },
+        EvaluationQuery {
+          name:           "ship".into(),
+          query:          "ship harbor"
+            .into(),
+          expected_terms: vec![
+            "ship".into(),
+          ],
+          top_k:          Some(3)
+        },
+      ];
+      let state = ingest_text(
+        path,
+        &config,
+        "sweep.txt",
+        "the white whale\n\na whale \
+         swam far away from every \
+         ship\n\nthe ship left the \
+         harbor",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      evaluation::run_evaluation(
+        &config, &state, &index, false
+      )?;
+      let sweep = WalkDir::new(
+        &config.stage2.runs_dir
+      )
+      .into_iter()
+      .filter_map(Result::ok)
+      .find(|entry| {
+        entry
+          .path()
+          .extension()
+          .and_then(|ext| ext.to_str())
+          == Some("csv")
+      })
+      .expect("sweep file");
+      let csv = fs::read_to_string(
+        sweep.path()
+      )?;
+      let rows: Vec<Vec<f32>> = csv
+        .lines()
+        .skip(1)
+        .map(|line| {
+          line
+            .split(',')
+            .map(|value| {
+              value.parse().unwrap()
+            })
+            .collect()
+        })
+        .collect();
+      assert_eq!(rows.len(), 4);
+      assert!(rows.windows(2).all(
+        |pair| {
+          pair[1][1] <= pair[0][1]
+        }
+      ));
+      assert!(rows[3][1] < rows[0][1]);
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn tune_prefers_chunks_that_keep_terms_together()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      let words: Vec<String> = (1..59)
+        .map(|n| format!("word{}", n))
+        .collect();
+      let state = ingest_text(
+        path,
+        &config,
+        "long.txt",
+        &format!(
+          "alpha {} omega",
+          words.join(" ")
+        ),
+        ChunkStrategy::Fixed
+      )?;
+      let index = load_index(&config)?;
+      config
+        .stage2
+        .evaluation
+        .queries =
+        vec![EvaluationQuery {
+          name:           "span".into(),
+          query:          "alpha"
+            .into(),
+          expected_terms: vec![
+            "alpha".into(),
+            "omega".into(),
+          ],
+          top_k:          Some(1)
+        }];
+      config.stage2.tuning.strategies =
+        vec![ChunkStrategy::Fixed];
+      config.stage2.tuning.max_tokens =
+        vec![10, 100];
+      config.stage2.tuning.overlap =
+        vec![0, 5];
+      let embedder =
+        build_configured_embedder(
+          &config
+        )?;
+      let results =
+        evaluation::tune::tune(
+          &config,
+          &state,
+          &index,
+          embedder.as_ref()
+        )?;
+      assert_eq!(results.len(), 4);
+      assert_eq!(
+        (
+          results[0].max_tokens,
+          results[0].recall
+        ),
+        (100, 1.0)
+      );
+      assert!(
+        results
+          .iter()
+          .filter(|result| {
+            result.max_tokens == 10
+          })
+          .all(|result| {
+            result.recall < 1.0
+          })
+      );
+      let written =
+        path.join("tuned.toml");
+      fs::write(
+        &written,
+        "[stage1]\n# Corpus \
+         language\nlanguage = \"en\"\n"
+      )?;
+      evaluation::run_tune(
+        &config,
+        &state,
+        &index,
+        embedder.as_ref(),
+        Some(&written)
+      )?;
+      let contents =
+        fs::read_to_string(&written)?;
+      assert!(
+        contents.contains(
+          "# Corpus language"
+        )
+      );
+      let table: toml::Table =
+        toml::from_str(&contents)?;
+      assert_eq!(
+        table["stage1"]["chunk"]
+          ["max_tokens"]
+          .as_integer(),
+        Some(100)
+      );
+      assert_eq!(
+        table["stage1"]["language"]
+          .as_str(),
+        Some("en")
+      );
+      config
+        .stage2
+        .tuning
+        .max_combinations = 3;
+      assert!(
+        evaluation::tune::tune(
+          &config,
+          &state,
+          &index,
+          embedder.as_ref()
+        )
+        .is_err()
+      );
+      Ok(())
+    }
+  )
+}
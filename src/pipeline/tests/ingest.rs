@@ -0,0 +1,500 @@
+use super::*;
+
+#[test]
+fn ingest_populates_state_and_chunks_jsonl()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let corpus = path.join("doc.txt");
+    let mut file =
+      File::create(&corpus)?;
+    writeln!(
+      file,
+      "alpha\n\nbeta\n\nalpha"
+    )?;
+    run(
+      Command::Ingest {
+        path:            Some(
+          corpus.clone()
+        ),
+        from_manifest:   None,
+        strategy:
+          ChunkStrategy::Structured,
+        emit_word_tally: false,
+        emit_normalized: false
+      },
+      config.clone()
+    )?;
+    let state_path = PathBuf::from(
+      &config.stage1.storage.state_file
+    );
+    let state =
+      State::load_from(&state_path)?;
+    assert_eq!(
+      state.documents.len(),
+      1
+    );
+    assert!(!state.chunks.is_empty());
+    let chunk_file = PathBuf::from(
+      &config
+        .stage1
+        .storage
+        .chunks_file
+    );
+    assert!(chunk_file.exists());
+    Ok(())
+  })
+}
+
+#[test]
+fn one_word_document_yields_one_chunk()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let state = ingest_text(
+      path,
+      &config,
+      "word.txt",
+      "whale\n",
+      ChunkStrategy::Fixed
+    )?;
+    assert_eq!(
+      state.documents.len(),
+      1
+    );
+    assert_eq!(state.chunks.len(), 1);
+    assert_eq!(
+      state.chunks[0].text,
+      "whale"
+    );
+    assert_eq!(
+      load_index(&config)?
+        .entries()
+        .len(),
+      1
+    );
+    let state = ingest_text(
+      path,
+      &config,
+      "blank.txt",
+      " \n\n ",
+      ChunkStrategy::Fixed
+    )?;
+    assert_eq!(
+      state.documents.len(),
+      1
+    );
+    assert_eq!(state.ingest_run_id, 1);
+    Ok(())
+  })
+}
+
+#[test]
+fn raw_offsets_point_into_source_text()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let raw = concat!(
+      "  alpha   beta",
+      "\n\n\n\n",
+      "gamma \t delta\n"
+    );
+    let state = ingest_text(
+      path,
+      &config,
+      "spaced.txt",
+      raw,
+      ChunkStrategy::Structured
+    )?;
+    let chunk = state
+      .chunks
+      .iter()
+      .find(|chunk| {
+        chunk.text == "gamma delta"
+      })
+      .expect("gamma chunk");
+    let raw_start =
+      chunk.raw_start.unwrap();
+    let raw_end =
+      chunk.raw_end.unwrap();
+    assert_eq!(
+      &raw[raw_start..raw_end],
+      "gamma \t delta"
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn watch_ingests_created_files()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .ingest
+        .watch_debounce_ms = 50;
+      let watched =
+        path.join("watched");
+      fs::create_dir_all(&watched)?;
+      let (done_tx, done_rx) =
+        mpsc::channel();
+      let watcher_config =
+        config.clone();
+      let watcher_dir = watched.clone();
+      thread::spawn(move || {
+        let mut state =
+          State::default();
+        let mut index =
+          VectorIndex::from_entries(
+            Vec::new()
+          );
+        let result = build_embedder(
+          EmbedderKind::Tf,
+          &watcher_config
+        )
+        .and_then(|embedder| {
+          watch::watch(
+            &watcher_dir,
+            ChunkStrategy::Structured,
+            &watcher_config,
+            &mut state,
+            &mut index,
+            embedder.as_ref(),
+            |state| {
+              state.documents.is_empty()
+            }
+          )
+        });
+        let _ = done_tx.send(result);
+      });
+      thread::sleep(
+        Duration::from_millis(300)
+      );
+      fs::write(
+        watched.join("fresh.txt"),
+        "fresh whale notes"
+      )?;
+      done_rx.recv_timeout(
+        Duration::from_secs(10)
+      )??;
+      let state = State::load_from(
+        &config
+          .stage1
+          .storage
+          .state_file
+      )?;
+      assert_eq!(
+        state.documents.len(),
+        1
+      );
+      assert!(
+        state.documents[0]
+          .path
+          .ends_with("fresh.txt")
+      );
+      assert!(!state.chunks.is_empty());
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn seeded_ingest_is_reproducible()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.seed = Some(7);
+      let corpus =
+        path.join("seeded.txt");
+      fs::write(
+        &corpus,
+        "alpha beta\n\ngamma delta"
+      )?;
+      let mut outputs = Vec::new();
+      for run_name in
+        ["first", "second"]
+      {
+        let mut config = config.clone();
+        config
+          .stage1
+          .storage
+          .state_file = path
+          .join(format!(
+            "{}.json",
+            run_name
+          ))
+          .to_string_lossy()
+          .into_owned();
+        config
+          .stage1
+          .storage
+          .chunks_file = path
+          .join(format!(
+            "{}.jsonl",
+            run_name
+          ))
+          .to_string_lossy()
+          .into_owned();
+        run(
+          Command::Ingest {
+            path:            Some(
+              corpus.clone()
+            ),
+            from_manifest:   None,
+            strategy:
+              ChunkStrategy::Structured,
+            emit_word_tally: false,
+            emit_normalized: false
+          },
+          config.clone()
+        )?;
+        outputs.push((
+          fs::read_to_string(
+            &config
+              .stage1
+              .storage
+              .state_file
+          )?,
+          fs::read_to_string(
+            &config
+              .stage1
+              .storage
+              .chunks_file
+          )?
+        ));
+      }
+      assert_eq!(
+        outputs[0],
+        outputs[1]
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn seeded_ingests_draw_distinct_ids()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.seed = Some(7);
+      ingest_text(
+        path,
+        &config,
+        "a.txt",
+        "alpha beta\n\ngamma delta",
+        ChunkStrategy::Structured
+      )?;
+      let state = ingest_text(
+        path,
+        &config,
+        "b.txt",
+        "epsilon zeta\n\neta theta",
+        ChunkStrategy::Structured
+      )?;
+      let doc_ids: HashSet<_> = state
+        .documents
+        .iter()
+        .map(|doc| &doc.id)
+        .collect();
+      let chunk_ids: HashSet<_> = state
+        .chunks
+        .iter()
+        .map(|chunk| &chunk.id)
+        .collect();
+      assert_eq!(doc_ids.len(), 2);
+      assert_eq!(
+        chunk_ids.len(),
+        state.chunks.len()
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn max_chunks_per_doc_caps_stored_chunks()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .chunk
+        .max_chunks_per_doc = Some(3);
+      let paragraphs: Vec<String> = (0
+        ..10)
+        .map(|idx| {
+          format!("paragraph {}", idx)
+        })
+        .collect();
+      let first = ingest_text(
+        path,
+        &config,
+        "capped.txt",
+        &paragraphs.join("\n\n"),
+        ChunkStrategy::Structured
+      )?;
+      let texts: Vec<_> = first
+        .chunks
+        .iter()
+        .map(|chunk| {
+          chunk.text.as_str()
+        })
+        .collect();
+      assert_eq!(texts, vec![
+        "paragraph 0",
+        "paragraph 1",
+        "paragraph 2"
+      ]);
+      config
+        .stage1
+        .chunk
+        .chunk_cap_sampling =
+        ChunkCapSampling::Even;
+      let state = ingest_text(
+        path,
+        &config,
+        "sampled.txt",
+        &paragraphs
+          .join("\n\n")
+          .replace(
+            "paragraph",
+            "section"
+          ),
+        ChunkStrategy::Structured
+      )?;
+      let sampled: Vec<_> = state
+        .chunks
+        .iter()
+        .skip(3)
+        .map(|chunk| {
+          chunk.text.as_str()
+        })
+        .collect();
+      assert_eq!(sampled, vec![
+        "section 0",
+        "section 3",
+        "section 6"
+      ]);
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn frontmatter_becomes_document_tags()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .ingest
+        .parse_frontmatter = true;
+      let contents =
+        "---\ntitle: Moby Dick\ntags: \
+         [sea, whales]\n---\nCall me \
+         Ishmael.";
+      let state = ingest_text(
+        path,
+        &config,
+        "novel.md",
+        contents,
+        ChunkStrategy::Structured
+      )?;
+      let tags =
+        &state.documents[0].tags;
+      assert_eq!(
+        tags["title"],
+        "Moby Dick"
+      );
+      assert_eq!(
+        tags["tags"],
+        "sea, whales"
+      );
+      assert!(state.chunks.iter().all(
+        |chunk| {
+          !chunk.text.contains("title")
+            && !chunk
+              .text
+              .contains("---")
+        }
+      ));
+      let chunk = &state.chunks[0];
+      assert_eq!(
+        &contents[chunk
+          .raw_start
+          .unwrap()
+          ..chunk.raw_end.unwrap()],
+        "Call me Ishmael."
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn corpus_stats_track_incremental_changes()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let tokenizer =
+      Tokenizer::from_config(
+        &config.stage1.embedder
+      );
+    let recomputed = |state: &State| {
+      CorpusStats::recompute(
+        &state.documents,
+        &state.chunks,
+        &tokenizer
+      )
+    };
+    ingest_text(
+      path,
+      &config,
+      "first.txt",
+      "whale ship\n\nwhale sea",
+      ChunkStrategy::Structured
+    )?;
+    let mut state = ingest_text(
+      path,
+      &config,
+      "second.txt",
+      "ship harbor\n\nsand dunes",
+      ChunkStrategy::Structured
+    )?;
+    assert_eq!(
+      state.stats,
+      recomputed(&state)
+    );
+    assert_eq!(
+      state.stats.documents,
+      2
+    );
+    assert_eq!(
+      state.stats.document_frequency
+        ["ship"],
+      2
+    );
+    assert_eq!(
+      state.stats.document_frequency
+        ["whale"],
+      1
+    );
+    let mut index =
+      load_index(&config)?;
+    let first =
+      state.documents[0].id.clone();
+    stats::remove_document(
+      &mut state, &mut index, &first,
+      &tokenizer
+    );
+    assert_eq!(
+      state.stats,
+      recomputed(&state)
+    );
+    assert!(
+      !state
+        .stats
+        .document_frequency
+        .contains_key("whale")
+    );
+    Ok(())
+  })
+}
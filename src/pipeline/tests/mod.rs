@@ -0,0 +1,229 @@
+mod embedding;
+mod eval;
+mod ingest;
+mod output;
+mod query;
+mod reindex;
+mod search;
+mod sources;
+mod storage;
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+use std::fs::{
+  self,
+  File
+};
+use std::io::Write;
+use std::path::{
+  Path,
+  PathBuf
+};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+use super::list::{
+  Overlap,
+  list_lines,
+  overlap
+};
+use super::render::json_rag;
+use super::*;
+use crate::args::{
+  ListTarget,
+  VocabFormat
+};
+use crate::chunk::{
+  Chunk,
+  ChunkStrategy
+};
+use crate::config::{
+  ChunkCapSampling,
+  Config,
+  DuplicateContent,
+  EmbedderKind,
+  EvaluationQuery,
+  IdfSmoothing,
+  IngestErrorPolicy,
+  PathMode,
+  Summarizer
+};
+use crate::corpus_stats::CorpusStats;
+use crate::embedder::{
+  Embedder,
+  FrozenVocabEmbedder,
+  build_configured_embedder,
+  build_embedder
+};
+use crate::evaluation;
+use crate::index::VectorIndex;
+use crate::section::expand_sections;
+use crate::state::State;
+
+fn with_temp_data_dir(
+  test: impl FnOnce(
+    &Path,
+    Config
+  ) -> Result<()>
+) -> Result<()> {
+  let temp = TempDir::new()?;
+  let mut config = Config::default();
+  config.stage1.storage.state_file =
+    temp
+      .path()
+      .join("state.json")
+      .to_string_lossy()
+      .into_owned();
+  config.stage1.storage.chunks_file =
+    temp
+      .path()
+      .join("chunks.jsonl")
+      .to_string_lossy()
+      .into_owned();
+  test(temp.path(), config)
+}
+
+fn ingest_text(
+  dir: &Path,
+  config: &Config,
+  name: &str,
+  contents: &str,
+  strategy: ChunkStrategy
+) -> Result<State> {
+  let corpus = dir.join(name);
+  fs::write(&corpus, contents)?;
+  run(
+    Command::Ingest {
+      path: Some(corpus),
+      from_manifest: None,
+      strategy,
+      emit_word_tally: false,
+      emit_normalized: false
+    },
+    config.clone()
+  )?;
+  State::load_from(
+    &config.stage1.storage.state_file
+  )
+}
+
+/// The vector index saved with the
+/// state `config` points at.
+fn load_index(
+  config: &Config
+) -> Result<VectorIndex> {
+  let path =
+    config.stage1.storage.state_path();
+  Ok(
+    State::load_from(&path)?
+      .load_index(&path)
+  )
+}
+
+fn chunk_id_containing(
+  state: &State,
+  needle: &str
+) -> String {
+  state
+    .chunks
+    .iter()
+    .find(|chunk| {
+      chunk.text.contains(needle)
+    })
+    .map(|chunk| chunk.id.clone())
+    .expect("chunk with text")
+}
+
+/// Starts a `Command::Search` (or its
+/// `QueryOptions`) for `query` with
+/// every flag at its CLI default.
+fn search_command(
+  query: &str
+) -> SearchCommand {
+  SearchCommand {
+    query: query.into(),
+    top_k: None,
+    trace: None
+  }
+}
+
+/// The `search` flags tests set; the
+/// rest stay at their defaults.
+struct SearchCommand {
+  query: String,
+  top_k: Option<usize>,
+  trace: Option<PathBuf>
+}
+
+impl SearchCommand {
+  fn top_k(
+    mut self,
+    top_k: usize
+  ) -> Self {
+    self.top_k = Some(top_k);
+    self
+  }
+
+  fn trace(
+    mut self,
+    path: PathBuf
+  ) -> Self {
+    self.trace = Some(path);
+    self
+  }
+
+  /// The options `search` runs with
+  /// for tests that call it directly.
+  fn options(self) -> QueryOptions {
+    QueryOptions {
+      top_k:           self
+        .top_k
+        .unwrap_or(
+          Config::default()
+            .stage1
+            .search
+            .top_k
+        ),
+      filter:
+        SearchFilter::default(),
+      format:
+        OutputFormat::Text,
+      group_by_doc:    false,
+      trace:           self.trace,
+      expand_section:  false,
+      by_document:     false,
+      display:
+        DisplayBand::default(),
+      include_vectors: false,
+      cluster_results: false
+    }
+  }
+
+  fn build(self) -> Command {
+    Command::Search {
+      query:             self.query,
+      top_k:             self.top_k,
+      language:          None,
+      in_docs:           None,
+      format:
+        OutputFormat::Text,
+      json:              false,
+      group_by_doc:      false,
+      trace:             self.trace,
+      expand_section:    false,
+      by_document:       false,
+      min_display_score: None,
+      max_display_score: None,
+      include_vectors:   false,
+      not:               None,
+      cluster_results:   false,
+      ranking:           None
+    }
+  }
+}
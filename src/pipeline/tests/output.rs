@@ -0,0 +1,479 @@
+use super::*;
+
+#[test]
+fn markdown_output_fences_each_chunk()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let state = ingest_text(
+      path,
+      &config,
+      "fenced.txt",
+      "whale ship\n\nwhale \
+       sea\n\nwhale sand",
+      ChunkStrategy::Structured
+    )?;
+    let index = load_index(&config)?;
+    let embedder = build_embedder(
+      config
+        .stage1
+        .embedder
+        .kind
+        .clone(),
+      &config
+    )?;
+    let hits =
+      crate::search::search_hits(
+        embedder.as_ref(),
+        "whale",
+        5,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index
+      )?;
+    assert_eq!(hits.len(), 3);
+    let markdown =
+      render::markdown_hits(
+        &hits,
+        &render::sequential_ranks(3)
+      );
+    assert_eq!(
+      markdown
+        .lines()
+        .filter(|line| {
+          line.starts_with("## Result ")
+        })
+        .count(),
+      3
+    );
+    assert_eq!(
+      markdown
+        .lines()
+        .filter(|line| {
+          *line == "```text"
+        })
+        .count(),
+      3
+    );
+    assert!(
+      markdown.contains("fenced.txt>)")
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn grouped_output_nests_chunks_under_documents()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "pair.txt",
+      "whale ship\n\nwhale sea",
+      ChunkStrategy::Structured
+    )?;
+    let state = ingest_text(
+      path,
+      &config,
+      "single.txt",
+      "whale sand and dunes",
+      ChunkStrategy::Structured
+    )?;
+    let index = load_index(&config)?;
+    let embedder = build_embedder(
+      config
+        .stage1
+        .embedder
+        .kind
+        .clone(),
+      &config
+    )?;
+    let hits =
+      crate::search::search_hits(
+        embedder.as_ref(),
+        "whale",
+        5,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index
+      )?;
+    assert_eq!(hits.len(), 3);
+    let text = render::render_hits(
+      &hits,
+      &[],
+      OutputFormat::Text,
+      true
+    )?;
+    let headers: Vec<&str> = text
+      .lines()
+      .filter(|line| {
+        line.starts_with("Document: ")
+      })
+      .collect();
+    assert_eq!(headers.len(), 2);
+    let pair_chunks = text
+      .split("----------")
+      .find(|group| {
+        group.contains("pair.txt")
+      })
+      .expect("pair.txt group")
+      .lines()
+      .filter(|line| {
+        line.starts_with(" → Chunk ")
+      })
+      .count();
+    assert_eq!(pair_chunks, 2);
+
+    let json: serde_json::Value =
+      serde_json::from_str(
+        &render::render_hits(
+          &hits,
+          &[],
+          OutputFormat::Json,
+          true
+        )?
+      )?;
+    let groups =
+      json.as_array().expect("array");
+    assert_eq!(groups.len(), 2);
+    let pair = groups
+      .iter()
+      .find(|group| {
+        group["document"]["path"]
+          .as_str()
+          .is_some_and(|path| {
+            path.ends_with("pair.txt")
+          })
+      })
+      .expect("pair.txt group");
+    assert_eq!(
+      pair["hits"]
+        .as_array()
+        .map(Vec::len),
+      Some(2)
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn rag_json_carries_context_and_prompt()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage3.enabled = true;
+      let state = ingest_text(
+        path,
+        &config,
+        "whales.txt",
+        "The white whale swam past \
+         the ship.",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      let embedder = build_embedder(
+        config
+          .stage1
+          .embedder
+          .kind
+          .clone(),
+        &config
+      )?;
+      let runs = stage3::run_stage3(
+        "white whale",
+        3,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index,
+        embedder.as_ref()
+      )?;
+      let parsed: serde_json::Value =
+        serde_json::from_str(
+          &json_rag(&runs)?
+        )?;
+      let first = &parsed[0];
+      assert_eq!(
+        first["strategy"],
+        runs[0].strategy.as_str()
+      );
+      assert!(
+        first["hits"][0]["chunk_id"]
+          .is_string()
+      );
+      let context = first["context"]
+        .as_str()
+        .expect("context");
+      assert!(
+        context.contains("whale")
+      );
+      let prompt = first["prompt"]
+        .as_str()
+        .expect("prompt");
+      assert!(prompt.contains(
+        "Question: white whale"
+      ));
+      assert!(prompt.contains(context));
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn display_band_hides_results_but_keeps_ranks()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let corpus = path.join("corpus");
+    fs::create_dir_all(&corpus)?;
+    for (name, text) in [
+      ("a.txt", "whale whale whale"),
+      ("b.txt", "whale ship ship"),
+      (
+        "c.txt",
+        "whale sea sea sea sea"
+      ),
+      ("d.txt", "rope deck")
+    ] {
+      fs::write(
+        corpus.join(name),
+        text
+      )?;
+    }
+    run(
+      Command::Ingest {
+        path:            Some(corpus),
+        from_manifest:   None,
+        strategy:
+          ChunkStrategy::Structured,
+        emit_word_tally: false,
+        emit_normalized: false
+      },
+      config.clone()
+    )?;
+    let state = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    let index = load_index(&config)?;
+    let embedder = build_embedder(
+      config
+        .stage1
+        .embedder
+        .kind
+        .clone(),
+      &config
+    )?;
+    let hits =
+      crate::search::search_hits(
+        embedder.as_ref(),
+        "whale",
+        5,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index
+      )?;
+    assert_eq!(hits.len(), 3);
+    let band = DisplayBand {
+      min: Some(hits[2].score),
+      max: Some(
+        (hits[1].score + hits[0].score)
+          / 2.0
+      )
+    };
+    let (shown, ranks) = band
+      .apply(hits.clone(), |hit| {
+        hit.score
+      });
+    assert_eq!(ranks, vec![2, 3]);
+    assert_eq!(hits.len(), 3);
+    let json: serde_json::Value =
+      serde_json::from_str(
+        &render::render_hits(
+          &shown,
+          &ranks,
+          OutputFormat::Json,
+          false
+        )?
+      )?;
+    let printed =
+      json.as_array().expect("array");
+    assert_eq!(printed.len(), 2);
+    assert_eq!(printed[0]["rank"], 2);
+    assert_eq!(
+      printed[0]["chunk_id"],
+      hits[1].chunk.id.as_str()
+    );
+    let text = render::render_hits(
+      &shown,
+      &ranks,
+      OutputFormat::Text,
+      false
+    )?;
+    assert!(
+      !text.contains("Result 1 ")
+    );
+    assert!(text.contains("Result 3 "));
+    Ok(())
+  })
+}
+
+#[test]
+fn json_flag_selects_json_format() {
+  use clap::Parser;
+
+  use crate::args::Cli;
+
+  let cli = Cli::try_parse_from([
+    "oxbed", "search", "whale",
+    "--json"
+  ])
+  .unwrap();
+  assert!(matches!(
+    cli.command,
+    Command::Search {
+      json: true,
+      ..
+    }
+  ));
+  assert!(
+    Cli::try_parse_from([
+      "oxbed", "search", "whale",
+      "--json", "--format", "markdown"
+    ])
+    .is_err()
+  );
+}
+
+#[test]
+fn json_hits_carry_vectors_on_request()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "moby.txt",
+      "whale ship sea harpoon",
+      ChunkStrategy::Structured
+    )?;
+    let (state, index, embedder) =
+      load_corpus(&config)?;
+    let hits =
+      crate::search::search_hits(
+        embedder.as_ref(),
+        "whale",
+        5,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index
+      )?;
+    let ranks =
+      render::sequential_ranks(1);
+    let plain: serde_json::Value =
+      serde_json::from_str(
+        &render::render_hits(
+          &hits,
+          &ranks,
+          OutputFormat::Json,
+          false
+        )?
+      )?;
+    assert!(
+      plain[0].get("vector").is_none()
+    );
+    let with_vectors: serde_json::Value =
+      serde_json::from_str(
+        &render::json_hits_with_vectors(
+          &hits, &ranks, &index, 256
+        )?
+      )?;
+    let vector = with_vectors[0]
+      ["vector"]
+      .as_array()
+      .expect("vector");
+    assert!(!vector.is_empty());
+    assert!(vector.iter().any(
+      |term| {
+        term["term"] == "whale"
+      }
+    ));
+    let capped: serde_json::Value =
+      serde_json::from_str(
+        &render::json_hits_with_vectors(
+          &hits, &ranks, &index, 1
+        )?
+      )?;
+    assert_eq!(
+      capped[0]["vector"]
+        .as_array()
+        .map(Vec::len),
+      Some(1)
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn json_hits_carry_flat_fields()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let state = ingest_text(
+      path,
+      &config,
+      "moby.txt",
+      "whale ship\n\nsea harpoon",
+      ChunkStrategy::Structured
+    )?;
+    let index = load_index(&config)?;
+    let embedder = build_embedder(
+      EmbedderKind::Tf,
+      &config
+    )?;
+    let hits =
+      crate::search::search_hits(
+        embedder.as_ref(),
+        "whale",
+        5,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index
+      )?;
+    let printed: serde_json::Value =
+      serde_json::from_str(
+        &render::render_hits(
+          &hits,
+          &render::sequential_ranks(1),
+          OutputFormat::Json,
+          false
+        )?
+      )?;
+    let hit = &printed[0];
+    assert_eq!(hit["rank"], 1);
+    assert!(hit["score"].is_f64());
+    assert_eq!(
+      hit["document_path"],
+      state.documents[0].path.as_str()
+    );
+    assert_eq!(
+      hit["chunk_id"],
+      chunk_id_containing(
+        &state, "whale"
+      )
+      .as_str()
+    );
+    assert_eq!(
+      hit["chunk_text"],
+      "whale ship"
+    );
+    assert_eq!(
+      hit
+        .as_object()
+        .map(|hit| hit.len()),
+      Some(5)
+    );
+    Ok(())
+  })
+}
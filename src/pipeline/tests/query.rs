@@ -0,0 +1,469 @@
+use super::*;
+
+#[test]
+fn missing_custom_model_falls_back()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage4.models_dir = path
+        .join("models")
+        .to_string_lossy()
+        .into_owned();
+      config.stage1.embedder.kind =
+        EmbedderKind::Custom {
+          name:    "missing".into(),
+          version: None
+        };
+      let search = || {
+        search_command("whale")
+          .top_k(1)
+          .build()
+      };
+      assert!(
+        run(search(), config.clone())
+          .is_err()
+      );
+      config.stage1.embedder.fallback =
+        Some(EmbedderKind::Tf);
+      ingest_text(
+        path,
+        &config,
+        "fallback.txt",
+        "the whale swam",
+        ChunkStrategy::Structured
+      )?;
+      run(search(), config)
+    }
+  )
+}
+
+/// Sleeps before every embedding to
+/// stand in for an expensive model.
+struct SlowEmbedder;
+
+impl Embedder for SlowEmbedder {
+  fn name(&self) -> String {
+    "slow".into()
+  }
+
+  fn embed(
+    &self,
+    text: &str
+  ) -> crate::embedder::SparseVector {
+    thread::sleep(
+      Duration::from_millis(500)
+    );
+    crate::embedder::TfEmbedder::new(1)
+      .embed(text)
+  }
+
+  fn token_count(
+    &self,
+    text: &str
+  ) -> usize {
+    text.split_whitespace().count()
+  }
+}
+
+#[test]
+fn search_timeout_fires_for_slow_embedder()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let state = ingest_text(
+      path,
+      &config,
+      "slow.txt",
+      "whale ship",
+      ChunkStrategy::Structured
+    )?;
+    let index = load_index(&config)?;
+    let err =
+      timeout::run_with_timeout(
+        "search",
+        Some(50),
+        move || {
+          search(
+            "whale",
+            &search_command("whale")
+              .top_k(1)
+              .options(),
+            &state,
+            &index,
+            &SlowEmbedder,
+            &config
+          )
+        }
+      )
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "search timed out after 50 ms"
+    );
+    assert_eq!(
+      timeout::run_with_timeout(
+        "search",
+        Some(5_000),
+        || Ok(7)
+      )?,
+      7
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn saved_collection_matches_search_results()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "collected.txt",
+      "whale ship\n\nwhale \
+       sea\n\nsand dunes",
+      ChunkStrategy::Structured
+    )?;
+    run(
+      Command::SaveCollection {
+        name:  "whales".into(),
+        query: "whale".into(),
+        top_k: Some(5)
+      },
+      config.clone()
+    )?;
+    let state = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    let index = load_index(&config)?;
+    let expected: Vec<String> =
+      crate::search::search_hits(
+        build_configured_embedder(
+          &config
+        )?
+        .as_ref(),
+        "whale",
+        5,
+        &SearchFilter::default(),
+        &config,
+        &state,
+        &index
+      )?
+      .into_iter()
+      .map(|hit| hit.chunk.id)
+      .collect();
+    let collection =
+      &state.collections["whales"];
+    assert_eq!(
+      collection.query,
+      "whale"
+    );
+    assert_eq!(expected.len(), 2);
+    assert_eq!(
+      collection.chunk_ids,
+      expected
+    );
+    run(
+      Command::ShowCollection {
+        name: "whales".into()
+      },
+      config.clone()
+    )?;
+    assert!(
+      run(
+        Command::ShowCollection {
+          name: "missing".into()
+        },
+        config.clone()
+      )
+      .is_err()
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn query_log_appends_one_record_per_search()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      ingest_text(
+        path,
+        &config,
+        "logged.txt",
+        "whale ship\n\nsand dunes",
+        ChunkStrategy::Structured
+      )?;
+      let log_path =
+        path.join("logs/queries.jsonl");
+      config.stage1.search.query_log =
+        Some(log_path.clone());
+      for query in ["whale", "dunes"] {
+        run(
+          search_command(query)
+            .top_k(2)
+            .build(),
+          config.clone()
+        )?;
+      }
+      let records: Vec<
+        serde_json::Value
+      > =
+        fs::read_to_string(&log_path)?
+          .lines()
+          .map(serde_json::from_str)
+          .collect::<Result<_, _>>()?;
+      assert_eq!(records.len(), 2);
+      assert_eq!(
+        records[0]["query"],
+        "whale"
+      );
+      assert_eq!(
+        records[1]["query"],
+        "dunes"
+      );
+      for record in &records {
+        assert_eq!(record["top_k"], 2);
+        assert_eq!(
+          record["results"],
+          1
+        );
+        assert!(
+          record["top_score"]
+            .as_f64()
+            .is_some_and(|score| {
+              score > 0.0
+            })
+        );
+        assert!(
+          record["timestamp"]
+            .is_string()
+        );
+      }
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn search_trace_records_candidate_scores()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "traced.txt",
+      "whale ship\n\nwhale \
+       sea\n\nsand dunes",
+      ChunkStrategy::Structured
+    )?;
+    let trace_path =
+      path.join("traces/whale.json");
+    run(
+      search_command("Whale")
+        .top_k(1)
+        .trace(trace_path.clone())
+        .build(),
+      config.clone()
+    )?;
+    let trace: serde_json::Value =
+      serde_json::from_str(
+        &fs::read_to_string(
+          &trace_path
+        )?
+      )?;
+    assert_eq!(trace["query"], "Whale");
+    assert!(
+      trace["normalized_query"]
+        .is_string()
+    );
+    assert!(
+      trace["query_vector"]["whale"]
+        .as_f64()
+        .is_some_and(|weight| {
+          weight > 0.0
+        })
+    );
+    let candidates = trace
+      ["candidates"]
+      .as_array()
+      .expect("candidates");
+    assert_eq!(candidates.len(), 1);
+    assert!(candidates.iter().all(
+      |candidate| {
+        candidate["score"]
+          .as_f64()
+          .is_some_and(|score| {
+            score > 0.0
+          })
+      }
+    ));
+    assert_eq!(
+      trace["score_threshold"],
+      0.0
+    );
+    assert_eq!(
+      trace["hits"]
+        .as_array()
+        .map(Vec::len),
+      Some(1)
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn expanded_hit_returns_its_whole_section()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.chunk.max_tokens =
+        4;
+      config.stage1.chunk.overlap = 0;
+      let state = ingest_text(
+        path,
+        &config,
+        "guide.md",
+        "# Ships\n\nwhale ship \
+         hull\n\ncargo deck mast\n\n# \
+         Deserts\n\nsand dunes heat",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      let hits =
+        crate::search::search_hits(
+          build_configured_embedder(
+            &config
+          )?
+          .as_ref(),
+          "whale",
+          1,
+          &SearchFilter::default(),
+          &config,
+          &state,
+          &index
+        )?;
+      assert_eq!(hits.len(), 1);
+      assert_eq!(
+        hits[0]
+          .chunk
+          .heading
+          .as_deref(),
+        Some("Ships")
+      );
+      assert!(
+        !hits[0]
+          .chunk
+          .text
+          .contains("cargo")
+      );
+      let expanded =
+        expand_sections(hits, &state);
+      let text =
+        &expanded[0].chunk.text;
+      assert!(
+        text
+          .contains("whale ship hull")
+      );
+      assert!(
+        text
+          .contains("cargo deck mast")
+      );
+      assert!(!text.contains("sand"));
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn federated_search_merges_labeled_corpora()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let corpus = |name: &str| {
+      let dir = path.join(name);
+      let mut config = config.clone();
+      config
+        .stage1
+        .storage
+        .state_file = dir
+        .join("state.json")
+        .to_string_lossy()
+        .into_owned();
+      config
+        .stage1
+        .storage
+        .chunks_file = dir
+        .join("chunks.jsonl")
+        .to_string_lossy()
+        .into_owned();
+      (dir, config)
+    };
+    let (whales, whale_config) =
+      corpus("whales");
+    let (ships, ship_config) =
+      corpus("ships");
+    fs::create_dir_all(&whales)?;
+    fs::create_dir_all(&ships)?;
+    ingest_text(
+      &whales,
+      &whale_config,
+      "whale.txt",
+      "the white whale",
+      ChunkStrategy::Structured
+    )?;
+    ingest_text(
+      &ships,
+      &ship_config,
+      "ship.txt",
+      "a whale ship sails far from \
+       port",
+      ChunkStrategy::Structured
+    )?;
+    let hits = federated_search(
+      "whale",
+      &[ships.clone(), whales.clone()],
+      5,
+      &config
+    )?;
+    let labels: Vec<_> = hits
+      .iter()
+      .map(|federated| {
+        (
+          federated.corpus.clone(),
+          Path::new(
+            &federated
+              .hit
+              .document
+              .path
+          )
+          .file_name()
+          .unwrap()
+          .to_owned()
+        )
+      })
+      .collect();
+    assert_eq!(labels, vec![
+      (
+        whales.clone(),
+        "whale.txt".into()
+      ),
+      (
+        ships.clone(),
+        "ship.txt".into()
+      )
+    ]);
+    assert!(
+      hits[0].hit.score
+        > hits[1].hit.score
+    );
+    assert!(
+      federated_search(
+        "whale",
+        &[path.join("missing")],
+        5,
+        &config
+      )
+      .is_err()
+    );
+    Ok(())
+  })
+}
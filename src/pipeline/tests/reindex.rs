@@ -0,0 +1,278 @@
+use super::*;
+
+#[test]
+fn rechunk_splits_into_smaller_chunks()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      let words: Vec<String> = (0..60)
+        .map(|n| format!("word{}", n))
+        .collect();
+      let state = ingest_text(
+        path,
+        &config,
+        "long.txt",
+        &words.join(" "),
+        ChunkStrategy::Fixed
+      )?;
+      assert_eq!(state.chunks.len(), 1);
+      config.stage1.chunk.max_tokens =
+        10;
+      config.stage1.chunk.overlap = 0;
+      run(
+        Command::Rechunk {
+          strategy:
+            ChunkStrategy::Fixed
+        },
+        config.clone()
+      )?;
+      let state = State::load_from(
+        &config
+          .stage1
+          .storage
+          .state_file
+      )?;
+      assert_eq!(state.chunks.len(), 6);
+      assert_eq!(
+        load_index(&config)?
+          .entries()
+          .len(),
+        6
+      );
+      assert!(state.chunks.iter().all(
+        |chunk| {
+          chunk.text.split(' ').count()
+            == 10
+        }
+      ));
+      let rebuilt: Vec<_> = state
+        .chunks
+        .iter()
+        .map(|chunk| {
+          chunk.text.as_str()
+        })
+        .collect();
+      assert_eq!(
+        rebuilt.join(" "),
+        words.join(" ")
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn reindex_reuses_vectors_until_embedder_changes()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      let state = ingest_text(
+        path,
+        &config,
+        "memo.txt",
+        "alpha beta\n\ngamma delta",
+        ChunkStrategy::Structured
+      )?;
+      let mut index =
+        load_index(&config)?;
+      let embedder = build_embedder(
+        EmbedderKind::Tf,
+        &config
+      )?;
+      let summary = reindex::reindex(
+        &config,
+        false,
+        &state,
+        &mut index,
+        embedder.as_ref()
+      );
+      assert_eq!(summary.reembedded, 0);
+      assert_eq!(summary.reused, 2);
+      let summary = reindex::reindex(
+        &config,
+        true,
+        &state,
+        &mut index,
+        embedder.as_ref()
+      );
+      assert_eq!(summary.reembedded, 2);
+      assert_eq!(summary.reused, 0);
+      config.stage1.embedder.kind =
+        EmbedderKind::BagOfWords;
+      let embedder = build_embedder(
+        EmbedderKind::BagOfWords,
+        &config
+      )?;
+      let summary = reindex::reindex(
+        &config,
+        false,
+        &state,
+        &mut index,
+        embedder.as_ref()
+      );
+      assert_eq!(summary.reembedded, 2);
+      assert_eq!(summary.reused, 0);
+      assert_eq!(
+        index.entries().len(),
+        2
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn reindex_refreshes_tfidf_weights()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.embedder.kind =
+        EmbedderKind::TfIdf;
+      let first = ingest_text(
+        path,
+        &config,
+        "a.txt",
+        "whale ship",
+        ChunkStrategy::Structured
+      )?;
+      let first_index =
+        load_index(&config)?;
+      ingest_text(
+        path,
+        &config,
+        "b.txt",
+        "whale ahab",
+        ChunkStrategy::Structured
+      )?;
+      run(
+        Command::Reindex {
+          force: false
+        },
+        config.clone()
+      )?;
+      let vector =
+        |index: &VectorIndex| {
+          index
+            .vector_for(
+              &first.chunks[0].id
+            )
+            .cloned()
+            .expect("first chunk entry")
+        };
+      assert_ne!(
+        vector(&load_index(&config)?),
+        vector(&first_index)
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn reindex_recovers_from_mismatched_index_file()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let state = ingest_text(
+      path,
+      &config,
+      "memo.txt",
+      "alpha beta\n\ngamma delta",
+      ChunkStrategy::Structured
+    )?;
+    let state_path = config
+      .stage1
+      .storage
+      .state_path();
+    fs::write(
+      State::index_path(&state_path),
+      b"garbage"
+    )?;
+    run(
+      Command::Reindex {
+        force: false
+      },
+      config.clone()
+    )?;
+    assert_eq!(
+      load_index(&config)?
+        .entries()
+        .len(),
+      state.chunks.len()
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn kept_normalized_text_drives_rechunk()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .storage
+        .keep_normalized = true;
+      config.stage1.chunk.max_tokens =
+        4;
+      config.stage1.chunk.overlap = 1;
+      let source = "Whale   ship\tsea \
+                    harpoon\n\n\nSand  \
+                    dunes heat wind \
+                    storm\n\nEnd.";
+      let state = ingest_text(
+        path,
+        &config,
+        "kept.txt",
+        source,
+        ChunkStrategy::Fixed
+      )?;
+      assert_eq!(
+      state.documents[0]
+        .normalized
+        .as_deref(),
+      Some(
+        crate::normalization::Normalizer::from_config(
+          &config.stage1.normalization
+        )
+        .normalize(source)
+        .as_str()
+      )
+    );
+      let boundaries =
+        |state: &State| {
+          let mut spans: Vec<(
+            usize,
+            usize
+          )> = state
+            .chunks
+            .iter()
+            .map(|chunk| {
+              (chunk.start, chunk.end)
+            })
+            .collect();
+          spans.sort();
+          spans
+        };
+      let before = boundaries(&state);
+      assert!(before.len() > 1);
+      run(
+        Command::Rechunk {
+          strategy:
+            ChunkStrategy::Fixed
+        },
+        config.clone()
+      )?;
+      let state = State::load_from(
+        &config
+          .stage1
+          .storage
+          .state_file
+      )?;
+      assert_eq!(
+        boundaries(&state),
+        before
+      );
+      Ok(())
+    }
+  )
+}
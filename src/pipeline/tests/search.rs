@@ -0,0 +1,413 @@
+use super::*;
+
+#[test]
+fn search_finds_matching_results()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let corpus = path.join("doc2.txt");
+    let mut file =
+      File::create(&corpus)?;
+    writeln!(file, "gamma delta")?;
+    run(
+      Command::Ingest {
+        path:            Some(
+          corpus.clone()
+        ),
+        from_manifest:   None,
+        strategy:
+          ChunkStrategy::Fixed,
+        emit_word_tally: false,
+        emit_normalized: false
+      },
+      config.clone()
+    )?;
+    let state_path = PathBuf::from(
+      &config.stage1.storage.state_file
+    );
+    let state =
+      State::load_from(&state_path)?;
+    let index = load_index(&config)?;
+    let embedder = build_embedder(
+      config
+        .stage1
+        .embedder
+        .kind
+        .clone(),
+      &config
+    )?;
+    search(
+      "gamma",
+      &search_command("gamma")
+        .top_k(3)
+        .options(),
+      &state,
+      &index,
+      embedder.as_ref(),
+      &config
+    )?;
+    Ok(())
+  })
+}
+
+#[test]
+fn similar_ranks_related_chunks()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let state = ingest_text(
+      path,
+      &config,
+      "similar.txt",
+      "whale ship harpoon \
+       sea\n\nwhale ship sea\n\nwhale \
+       desert\n\ncactus sand",
+      ChunkStrategy::Structured
+    )?;
+    let source = chunk_id_containing(
+      &state, "harpoon"
+    );
+    let index = load_index(&config)?;
+    let hits =
+      crate::search::similar_hits(
+        &source, 5, &config, &state,
+        &index
+      )?;
+    let texts: Vec<_> = hits
+      .iter()
+      .map(|hit| {
+        hit.chunk.text.as_str()
+      })
+      .collect();
+    assert_eq!(texts, vec![
+      "whale ship sea",
+      "whale desert"
+    ]);
+    assert!(
+      hits[0].score > hits[1].score
+    );
+    Ok(())
+  })
+}
+
+#[cfg(feature = "language-detection")]
+#[test]
+fn language_tags_restrict_search()
+-> Result<()> {
+  use crate::search::{
+    SearchFilter,
+    search_hits
+  };
+
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .ingest
+        .detect_language = true;
+      let state = ingest_text(
+        path,
+        &config,
+        "mixed.txt",
+        "The old captain watched the \
+         sea every morning and told \
+         stories about the \
+         whale.\n\nEl viejo capitán \
+         miraba el mar cada mañana y \
+         contaba historias sobre la \
+         ballena.",
+        ChunkStrategy::Structured
+      )?;
+      let languages: Vec<_> = state
+        .chunks
+        .iter()
+        .map(|chunk| {
+          chunk.language.clone()
+        })
+        .collect();
+      assert_eq!(languages, vec![
+        Some("eng".to_string()),
+        Some("spa".to_string()),
+      ]);
+      for (entry, stopword) in
+        load_index(&config)?
+          .entries()
+          .iter()
+          .zip(["the", "el"])
+      {
+        let counts = entry
+          .term_counts
+          .as_ref()
+          .expect("term counts");
+        assert!(
+          !counts
+            .contains_key(stopword)
+        );
+      }
+      let index = load_index(&config)?;
+      let embedder = build_embedder(
+        config
+          .stage1
+          .embedder
+          .kind
+          .clone(),
+        &config
+      )?;
+      let hits = search_hits(
+        embedder.as_ref(),
+        "captain capitán",
+        5,
+        &SearchFilter {
+          language: Some("spa".into()),
+          ..Default::default()
+        },
+        &config,
+        &state,
+        &index
+      )?;
+      assert_eq!(hits.len(), 1);
+      assert!(
+        hits[0]
+          .chunk
+          .text
+          .contains("ballena")
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn bm25_search_scores_raw_term_counts()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .embedder
+        .tf_scheme =
+        crate::config::TfScheme::Log;
+      config.stage1.search.ranking =
+        crate::config::Ranking::Bm25;
+      let state = ingest_text(
+        path,
+        &config,
+        "counts.txt",
+        "whale whale ship\n\nsea sand",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      let embedder = build_embedder(
+        EmbedderKind::Tf,
+        &config
+      )?;
+      let hits =
+        crate::search::search_hits(
+          embedder.as_ref(),
+          "whale",
+          5,
+          &SearchFilter::default(),
+          &config,
+          &state,
+          &index
+        )?;
+      assert_eq!(hits.len(), 1);
+      // idf = ln 2; tf = 2 over a
+      // length of 3 against a mean of
+      // 2.5, with k1 = 1.2, b = 0.75.
+      let norm =
+        1.2 * (0.25 + 0.75 * 3.0 / 2.5);
+      let expected =
+        2f32.ln() * 2.0 * 2.2
+          / (2.0 + norm);
+      assert!(
+        (hits[0].score - expected)
+          .abs()
+          < 1e-4,
+        "{} != {}",
+        hits[0].score,
+        expected
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn path_tokens_boost_matching_documents()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .ingest
+        .duplicate_content =
+        DuplicateContent::AddFull;
+      config
+        .stage1
+        .embedder
+        .path_token_weight = 0.5;
+      ingest_text(
+        path,
+        &config,
+        "other_notes.txt",
+        "ship harpoon sea",
+        ChunkStrategy::Structured
+      )?;
+      let state = ingest_text(
+        path,
+        &config,
+        "whale_notes.txt",
+        "ship harpoon sea",
+        ChunkStrategy::Structured
+      )?;
+      let index = load_index(&config)?;
+      let embedder = build_embedder(
+        config
+          .stage1
+          .embedder
+          .kind
+          .clone(),
+        &config
+      )?;
+      let hits =
+        crate::search::search_hits(
+          embedder.as_ref(),
+          "whale harpoon",
+          2,
+          &SearchFilter::default(),
+          &config,
+          &state,
+          &index
+        )?;
+      assert_eq!(hits.len(), 2);
+      assert!(
+        hits[0]
+          .document
+          .path
+          .ends_with("whale_notes.txt")
+      );
+      assert!(
+        hits[0].score > hits[1].score
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn in_docs_scopes_search_to_listed_documents()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    for name in [
+      "one.txt",
+      "two.txt",
+      "three.txt"
+    ] {
+      ingest_text(
+        path,
+        &config,
+        name,
+        &format!(
+          "whale notes from \
+           {}\n\nmore whale notes \
+           from {}",
+          name, name
+        ),
+        ChunkStrategy::Structured
+      )?;
+    }
+    let state = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    let index = load_index(&config)?;
+    let scoped: Vec<String> = state
+      .documents
+      .iter()
+      .filter(|doc| {
+        !doc.path.ends_with("two.txt")
+      })
+      .map(|doc| doc.id.clone())
+      .collect();
+    let embedder = build_embedder(
+      EmbedderKind::Tf,
+      &config
+    )?;
+    let hits =
+      crate::search::search_hits(
+        embedder.as_ref(),
+        "whale",
+        3,
+        &SearchFilter {
+          doc_ids: Some(scoped.clone()),
+          ..Default::default()
+        },
+        &config,
+        &state,
+        &index
+      )?;
+    assert_eq!(hits.len(), 3);
+    assert!(hits.iter().all(|hit| {
+      scoped.contains(&hit.chunk.doc_id)
+    }));
+    Ok(())
+  })
+}
+
+#[test]
+fn summary_search_returns_full_chunks()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.chunk.summarizer =
+        Summarizer::Extractive;
+      config
+        .stage1
+        .search
+        .search_summaries = true;
+      let state = ingest_text(
+        path,
+        &config,
+        "summaries.txt",
+        "Harpoons are sharp. Sailors \
+         sharpen harpoons. The cook \
+         stirs stew in the \
+         galley.\n\nStew simmers all \
+         day. Stew feeds the crew. \
+         Nobody mentions harpoons \
+         here.",
+        ChunkStrategy::Structured
+      )?;
+      assert!(state.chunks.iter().all(
+        |chunk| chunk.summary.is_some()
+      ));
+      let index = load_index(&config)?;
+      let embedder = build_embedder(
+        EmbedderKind::Tf,
+        &config
+      )?;
+      let hits =
+        crate::search::search_hits(
+          embedder.as_ref(),
+          "harpoons",
+          5,
+          &SearchFilter::default(),
+          &config,
+          &state,
+          &index
+        )?;
+      assert_eq!(hits.len(), 1);
+      assert_eq!(
+        hits[0].chunk.id,
+        chunk_id_containing(
+          &state, "galley"
+        )
+      );
+      assert!(
+        hits[0]
+          .chunk
+          .text
+          .ends_with("galley.")
+      );
+      Ok(())
+    }
+  )
+}
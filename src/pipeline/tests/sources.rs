@@ -0,0 +1,447 @@
+use super::*;
+
+#[test]
+fn ingest_skips_unreadable_files_when_configured()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      let corpus = path.join("corpus");
+      fs::create_dir_all(&corpus)?;
+      fs::write(
+        corpus.join("good.txt"),
+        "readable text"
+      )?;
+      fs::write(
+        corpus.join("bad.txt"),
+        [0xff, 0xfe, 0xfd]
+      )?;
+      let command = || {
+        Command::Ingest {
+          path:            Some(
+            corpus.clone()
+          ),
+          from_manifest:   None,
+          strategy:
+            ChunkStrategy::Fixed,
+          emit_word_tally: false,
+          emit_normalized: false
+        }
+      };
+      assert!(
+        run(command(), config.clone())
+          .is_err()
+      );
+      config.stage1.ingest.on_error =
+        IngestErrorPolicy::Skip;
+      run(command(), config.clone())?;
+      let state = State::load_from(
+        &config
+          .stage1
+          .storage
+          .state_file
+      )?;
+      assert_eq!(
+        state.documents.len(),
+        1
+      );
+      assert!(
+        state.documents[0]
+          .path
+          .ends_with("good.txt")
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn oversized_files_are_skipped()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .ingest
+        .max_file_bytes = Some(32);
+      let corpus = path.join("corpus");
+      fs::create_dir_all(&corpus)?;
+      fs::write(
+        corpus.join("small.txt"),
+        "short note"
+      )?;
+      fs::write(
+        corpus.join("huge.txt"),
+        "log line ".repeat(100)
+      )?;
+      run(
+        Command::Ingest {
+          path:            Some(corpus),
+          from_manifest:   None,
+          strategy:
+            ChunkStrategy::Fixed,
+          emit_word_tally: false,
+          emit_normalized: false
+        },
+        config.clone()
+      )?;
+      let state = State::load_from(
+        &config
+          .stage1
+          .storage
+          .state_file
+      )?;
+      assert_eq!(
+        state.documents.len(),
+        1
+      );
+      assert!(
+        state.documents[0]
+          .path
+          .ends_with("small.txt")
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn near_duplicate_documents_are_flagged()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      let paragraphs = [
+        "the whale rose from the grey \
+         water and the crew watched \
+         it circle the ship in silence",
+        "ahab paced the deck through \
+         the night muttering about \
+         the white whale and his lost \
+         leg",
+        "at dawn the harpooners \
+         sharpened their irons and \
+         the mates checked every line \
+         and boat"
+      ];
+      let shuffled =
+        |order: [usize; 3]| {
+          order
+            .map(|idx| paragraphs[idx])
+            .join("\n\n")
+        };
+      ingest_text(
+        path,
+        &config,
+        "original.txt",
+        &shuffled([0, 1, 2]),
+        ChunkStrategy::Structured
+      )?;
+      let state = ingest_text(
+        path,
+        &config,
+        "reshuffled.txt",
+        &shuffled([2, 0, 1]),
+        ChunkStrategy::Structured
+      )?;
+      assert_eq!(
+        state.documents.len(),
+        2
+      );
+      let found =
+        dedupe::near_duplicates(
+          &config.stage1.ingest,
+          &state
+        );
+      assert_eq!(found.len(), 1);
+      assert_eq!(
+        found[0].doc_id,
+        state.documents[1].id
+      );
+      assert_eq!(
+        found[0].duplicate_of,
+        state.documents[0].id
+      );
+
+      config
+        .stage1
+        .ingest
+        .skip_near_duplicates = true;
+      let state = ingest_text(
+        path,
+        &config,
+        "again.txt",
+        &shuffled([1, 2, 0]),
+        ChunkStrategy::Structured
+      )?;
+      assert_eq!(
+        state.documents.len(),
+        2
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn duplicate_content_becomes_a_path_alias()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config
+        .stage1
+        .ingest
+        .duplicate_content =
+        DuplicateContent::AddPathOnly;
+      ingest_text(
+        path,
+        &config,
+        "first.txt",
+        "whale ship sea",
+        ChunkStrategy::Structured
+      )?;
+      let chunks_before =
+        State::load_from(
+          &config
+            .stage1
+            .storage
+            .state_file
+        )?
+        .chunks
+        .len();
+      let state = ingest_text(
+        path,
+        &config,
+        "second.txt",
+        "whale ship sea",
+        ChunkStrategy::Structured
+      )?;
+      assert_eq!(
+        state.documents.len(),
+        1
+      );
+      assert_eq!(
+        state.chunks.len(),
+        chunks_before
+      );
+      let document =
+        &state.documents[0];
+      assert!(
+        document
+          .path
+          .ends_with("first.txt")
+      );
+      assert_eq!(
+        document.aliases.len(),
+        1
+      );
+      assert!(
+        document.aliases[0]
+          .ends_with("second.txt")
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn manifest_ingests_only_listed_files()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    let corpus = path.join("corpus");
+    fs::create_dir_all(&corpus)?;
+    for (name, text) in [
+      ("a.txt", "whale ship"),
+      ("b.txt", "harpoon rope"),
+      ("c.txt", "sea voyage")
+    ] {
+      fs::write(
+        corpus.join(name),
+        text
+      )?;
+    }
+    let manifest =
+      path.join("manifest.txt");
+    fs::write(
+      &manifest,
+      "corpus/a.txt\n\n# \
+       skipped\ncorpus/c.txt\n"
+    )?;
+    run(
+      Command::Ingest {
+        path:            None,
+        from_manifest:   Some(
+          manifest.clone()
+        ),
+        strategy:
+          ChunkStrategy::Structured,
+        emit_word_tally: false,
+        emit_normalized: false
+      },
+      config.clone()
+    )?;
+    let state = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    let mut names: Vec<&str> = state
+      .documents
+      .iter()
+      .map(|doc| {
+        Path::new(&doc.path)
+          .file_name()
+          .unwrap()
+          .to_str()
+          .unwrap()
+      })
+      .collect();
+    names.sort();
+    assert_eq!(names, vec![
+      "a.txt", "c.txt"
+    ]);
+    fs::write(
+      &manifest,
+      "corpus/missing.txt\n"
+    )?;
+    assert!(
+      run(
+        Command::Ingest {
+          path:            None,
+          from_manifest:   Some(
+            manifest
+          ),
+          strategy:
+            ChunkStrategy::Structured,
+          emit_word_tally: false,
+          emit_normalized: false
+        },
+        config.clone()
+      )
+      .is_err()
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn relative_path_mode_stores_portable_paths()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.storage.path_mode =
+        PathMode::RelativeTo;
+      config.stage1.storage.path_root =
+        path.to_string_lossy().into();
+      let notes = path.join("notes");
+      fs::create_dir_all(&notes)?;
+      let file = notes.join("a.txt");
+      fs::write(&file, "whale ship")?;
+      run(
+        Command::Ingest {
+          path:            Some(
+            file.clone()
+          ),
+          from_manifest:   None,
+          strategy:
+            ChunkStrategy::Structured,
+          emit_word_tally: false,
+          emit_normalized: false
+        },
+        config.clone()
+      )?;
+      let state = State::load_from(
+        &config
+          .stage1
+          .storage
+          .state_file
+      )?;
+      let stored =
+        &state.documents[0].path;
+      assert_eq!(
+        Path::new(stored),
+        Path::new("notes")
+          .join("a.txt")
+      );
+      let resolved = config
+        .stage1
+        .storage
+        .resolve_path(stored);
+      assert_eq!(
+        fs::canonicalize(resolved)?,
+        fs::canonicalize(&file)?
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn mmap_ingest_matches_buffered_reads()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      let text = (0..2000)
+        .map(|n| {
+          format!(
+            "Paragraph {} über the \
+             whale.\n\n",
+            n
+          )
+        })
+        .collect::<String>();
+      let chunks = |state: State| {
+        state
+          .chunks
+          .into_iter()
+          .map(|chunk| {
+            (chunk.text, chunk.start)
+          })
+          .collect::<Vec<_>>()
+      };
+      let buffered =
+        chunks(ingest_text(
+          path,
+          &config,
+          "big.txt",
+          &text,
+          ChunkStrategy::Structured
+        )?);
+      config.stage1.ingest.mmap = true;
+      config
+        .stage1
+        .storage
+        .state_file = path
+        .join("mapped.json")
+        .to_string_lossy()
+        .into_owned();
+      let mapped = chunks(ingest_text(
+        path,
+        &config,
+        "big.txt",
+        &text,
+        ChunkStrategy::Structured
+      )?);
+      assert!(buffered.len() > 1);
+      assert_eq!(mapped, buffered);
+      let invalid =
+        path.join("bad.txt");
+      fs::write(&invalid, [
+        0xff, 0xfe
+      ])?;
+      assert!(
+        run(
+          Command::Ingest {
+            path:            Some(
+              invalid
+            ),
+            from_manifest:   None,
+            strategy:
+              ChunkStrategy::Structured,
+            emit_word_tally: false,
+            emit_normalized: false
+          },
+          config
+        )
+        .is_err()
+      );
+      Ok(())
+    }
+  )
+}
@@ -0,0 +1,461 @@
+use super::*;
+
+#[test]
+fn export_dedupe_skips_repeated_chunks()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "first.txt",
+      "shared boilerplate\n\nwhale \
+       notes",
+      ChunkStrategy::Structured
+    )?;
+    let state = ingest_text(
+      path,
+      &config,
+      "second.txt",
+      "Shared boilerplate\n\nship \
+       notes",
+      ChunkStrategy::Structured
+    )?;
+    let out = path.join("export.jsonl");
+    run(
+      Command::Export {
+        path:   out.clone(),
+        dedupe: true
+      },
+      config.clone()
+    )?;
+    let exported =
+      fs::read_to_string(&out)?;
+    assert_eq!(
+      exported.lines().count(),
+      3
+    );
+    let duplicates =
+      fs::read_to_string(path.join(
+        "export.duplicates.jsonl"
+      ))?;
+    let records: Vec<
+      serde_json::Value
+    > = duplicates
+      .lines()
+      .map(serde_json::from_str)
+      .collect::<Result<_, _>>()?;
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+      records[0]["chunk_id"],
+      chunk_id_containing(
+        &state,
+        "Shared boilerplate"
+      )
+    );
+    assert_eq!(
+      records[0]["duplicate_of"],
+      chunk_id_containing(
+        &state,
+        "shared boilerplate"
+      )
+    );
+    let reloaded = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    assert_eq!(
+      reloaded.chunks.len(),
+      4
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn show_overlap_reports_shared_range()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.chunk.max_tokens =
+        4;
+      config.stage1.chunk.overlap = 2;
+      let state = ingest_text(
+        path,
+        &config,
+        "overlap.txt",
+        "one two three four five six",
+        ChunkStrategy::Fixed
+      )?;
+      let doc_id =
+        &state.documents[0].id;
+      let mut chunks: Vec<&Chunk> =
+        state.chunks.iter().collect();
+      chunks.sort_by_key(|chunk| {
+        chunk.start
+      });
+      assert_eq!(chunks.len(), 2);
+      assert_eq!(
+        chunks[0].text,
+        "one two three four"
+      );
+      assert_eq!(
+        chunks[1].text,
+        "three four five six"
+      );
+      assert_eq!(
+        overlap(chunks[0], chunks[1]),
+        Some(Overlap {
+          start:  8,
+          end:    18,
+          shared: "three four".into()
+        })
+      );
+      let lines = list_lines(
+        ListTarget::Chunks,
+        Some(doc_id),
+        true,
+        &state
+      )?;
+      assert_eq!(lines.len(), 3);
+      assert_eq!(
+        lines[1],
+        "  overlap [8..18]: \"three \
+         four\""
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn delete_removes_a_document_by_id_or_path()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "first.txt",
+      "whale ship sea",
+      ChunkStrategy::Structured
+    )?;
+    let state = ingest_text(
+      path,
+      &config,
+      "second.txt",
+      "harpoon rope deck",
+      ChunkStrategy::Structured
+    )?;
+    let second = chunk_id_containing(
+      &state, "harpoon"
+    );
+    let second_doc = state
+      .find_chunk(&second)
+      .unwrap()
+      .doc_id
+      .clone();
+    let missing = run(
+      Command::Delete {
+        doc_id: Some("nope".into()),
+        path:   None
+      },
+      config.clone()
+    );
+    assert!(missing.is_err());
+    run(
+      Command::Delete {
+        doc_id: None,
+        path:   Some(
+          path.join("first.txt")
+        )
+      },
+      config.clone()
+    )?;
+    let state = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    assert_eq!(
+      state.documents.len(),
+      1
+    );
+    assert_eq!(
+      state.documents[0].id,
+      second_doc
+    );
+    assert!(state.chunks.iter().all(
+      |chunk| {
+        chunk.doc_id == second_doc
+      }
+    ));
+    assert!(
+      load_index(&config)?
+        .entries()
+        .iter()
+        .all(|entry| {
+          entry.doc_id == second_doc
+        })
+    );
+    run(
+      Command::Delete {
+        doc_id: Some(second_doc),
+        path:   None
+      },
+      config.clone()
+    )?;
+    let state = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    assert!(state.documents.is_empty());
+    assert!(state.chunks.is_empty());
+    assert!(
+      load_index(&config)?
+        .entries()
+        .is_empty()
+    );
+    assert!(
+      !State::index_path(
+        &config
+          .stage1
+          .storage
+          .state_path()
+      )
+      .exists()
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn undo_removes_only_the_latest_run()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "first.txt",
+      "whale ship sea",
+      ChunkStrategy::Structured
+    )?;
+    let state = ingest_text(
+      path,
+      &config,
+      "second.txt",
+      "harpoon rope deck",
+      ChunkStrategy::Structured
+    )?;
+    assert_eq!(state.ingest_run_id, 2);
+    let first = chunk_id_containing(
+      &state, "whale"
+    );
+    let second = chunk_id_containing(
+      &state, "harpoon"
+    );
+    let run_of = |id: &str| {
+      state
+        .find_chunk(id)
+        .unwrap()
+        .run_id
+    };
+    assert_eq!(run_of(&first), 1);
+    assert_eq!(run_of(&second), 2);
+    run(Command::Undo, config.clone())?;
+    let state = State::load_from(
+      &config.stage1.storage.state_file
+    )?;
+    assert_eq!(
+      state.documents.len(),
+      1
+    );
+    assert!(
+      state
+        .chunks
+        .iter()
+        .all(|chunk| chunk.run_id == 1)
+    );
+    assert!(
+      state
+        .find_chunk(&first)
+        .is_some()
+    );
+    assert!(
+      load_index(&config)?
+        .entries()
+        .iter()
+        .all(|entry| {
+          entry.chunk_id != second
+        })
+    );
+    assert_eq!(state.ingest_run_id, 2);
+    Ok(())
+  })
+}
+
+#[test]
+fn markdown_export_lists_documents_and_chunks()
+-> Result<()> {
+  with_temp_data_dir(
+    |path, mut config| {
+      config.stage1.chunk.max_tokens =
+        4;
+      config.stage1.chunk.overlap = 0;
+      ingest_text(
+        path,
+        &config,
+        "voyage.txt",
+        "Call me Ishmael \
+         today.\n\nThe Pequod sails \
+         at dawn.",
+        ChunkStrategy::Fixed
+      )?;
+      let state = ingest_text(
+        path,
+        &config,
+        "crew.txt",
+        "Queequeg throws the harpoon.",
+        ChunkStrategy::Fixed
+      )?;
+      assert!(state.chunks.len() > 2);
+      let out = path.join("corpus.md");
+      run(
+        Command::ExportMarkdown {
+          path: out.clone()
+        },
+        config.clone()
+      )?;
+      let exported =
+        fs::read_to_string(&out)?;
+      let headings: Vec<&str> =
+        exported
+          .lines()
+          .filter(|line| {
+            line.starts_with("# ")
+          })
+          .collect();
+      assert_eq!(headings.len(), 2);
+      assert!(
+        headings[0]
+          .ends_with("voyage.txt")
+      );
+      assert!(
+        headings[1]
+          .ends_with("crew.txt")
+      );
+      let position = |needle: &str| {
+        exported.find(needle).unwrap()
+      };
+      assert!(
+        position("Ishmael")
+          < position("Pequod")
+      );
+      assert!(
+        position("Pequod")
+          < position("Queequeg")
+      );
+      Ok(())
+    }
+  )
+}
+
+#[test]
+fn markdown_export_nests_chunk_headings()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "notes.md",
+      "# Voyage\n\nCall me \
+       Ishmael.\n\nThe \
+       Crew\n--------\n\n```sh\n# not \
+       a heading\n```",
+      ChunkStrategy::Structured
+    )?;
+    let out = path.join("corpus.md");
+    run(
+      Command::ExportMarkdown {
+        path: out.clone()
+      },
+      config.clone()
+    )?;
+    let exported =
+      fs::read_to_string(&out)?;
+    let headings: Vec<&str> = exported
+      .lines()
+      .filter(|line| {
+        line.starts_with('#')
+      })
+      .collect();
+    let top: Vec<&str> = headings
+      .iter()
+      .copied()
+      .filter(|line| {
+        line.starts_with("# ")
+      })
+      .collect();
+    assert_eq!(top.len(), 2);
+    assert!(
+      top[0].ends_with("notes.md")
+    );
+    assert_eq!(
+      top[1],
+      "# not a heading"
+    );
+    assert!(
+      headings.contains(&"### Voyage")
+    );
+    assert!(
+      headings
+        .contains(&"#### The Crew")
+    );
+    Ok(())
+  })
+}
+
+#[test]
+fn vocabulary_counts_document_frequency()
+-> Result<()> {
+  with_temp_data_dir(|path, config| {
+    ingest_text(
+      path,
+      &config,
+      "a.txt",
+      "whale whale ahab",
+      ChunkStrategy::Structured
+    )?;
+    let state = ingest_text(
+      path,
+      &config,
+      "b.txt",
+      "the whale sank",
+      ChunkStrategy::Structured
+    )?;
+    let tokenizer =
+      Tokenizer::from_config(
+        &config.stage1.embedder
+      );
+    let terms =
+      vocabulary(&state, &tokenizer, 1);
+    let whale = terms
+      .iter()
+      .find(|term| term.term == "whale")
+      .expect("whale term");
+    assert_eq!(
+      (
+        whale.document_frequency,
+        whale.total_frequency
+      ),
+      (2, 3)
+    );
+    assert_eq!(terms[0].term, "whale");
+    let common =
+      vocabulary(&state, &tokenizer, 2);
+    assert_eq!(common.len(), 1);
+    assert_eq!(
+      render_vocabulary(
+        &common,
+        VocabFormat::Csv
+      )?,
+      "term,document_frequency,\
+       total_frequency\nwhale,2,3\n"
+    );
+    Ok(())
+  })
+}
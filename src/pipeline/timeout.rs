@@ -0,0 +1,54 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{
+  Result,
+  bail
+};
+
+/// Runs `work` on a worker thread and
+/// waits at most `timeout_ms` for it,
+/// failing with a "timed out" error
+/// otherwise. A timed-out worker is
+/// left to finish in the background
+/// and its result is discarded. Without
+/// a timeout `work` runs inline.
+pub(super) fn run_with_timeout<T>(
+  label: &str,
+  timeout_ms: Option<u64>,
+  work: impl FnOnce() -> Result<T>
+  + Send
+  + 'static
+) -> Result<T>
+where
+  T: Send + 'static
+{
+  let Some(timeout_ms) = timeout_ms
+  else {
+    return work();
+  };
+  let (tx, rx) = mpsc::channel();
+  thread::spawn(move || {
+    let _ = tx.send(work());
+  });
+  match rx.recv_timeout(
+    Duration::from_millis(timeout_ms)
+  ) {
+    | Ok(result) => result,
+    | Err(
+      mpsc::RecvTimeoutError::Timeout
+    ) => {
+      bail!(
+        "{} timed out after {} ms",
+        label,
+        timeout_ms
+      )
+    }
+    | Err(
+      mpsc::RecvTimeoutError::Disconnected
+    ) => {
+      bail!("{} worker panicked", label)
+    }
+  }
+}
@@ -0,0 +1,50 @@
+use std::collections::BTreeSet;
+
+use super::stats::remove_document;
+use crate::config::Config;
+use crate::index::VectorIndex;
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+/// What an undo removed.
+pub(super) struct UndoSummary {
+  pub run_id:    u64,
+  pub documents: usize,
+  pub chunks:    usize
+}
+
+/// Removes every document with chunks
+/// from the latest ingest run, along
+/// with their chunks, index entries,
+/// and corpus stats. Returns `None`
+/// when no stored chunk records a run.
+pub(super) fn undo(
+  config: &Config,
+  state: &mut State,
+  index: &mut VectorIndex
+) -> Option<UndoSummary> {
+  let run_id = state.latest_run()?;
+  let doc_ids: BTreeSet<String> = state
+    .chunks
+    .iter()
+    .filter(|chunk| {
+      chunk.run_id == run_id
+    })
+    .map(|chunk| chunk.doc_id.clone())
+    .collect();
+  let before = state.chunks.len();
+  let tokenizer =
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    );
+  for doc_id in &doc_ids {
+    remove_document(
+      state, index, doc_id, &tokenizer
+    );
+  }
+  Some(UndoSummary {
+    run_id,
+    documents: doc_ids.len(),
+    chunks: before - state.chunks.len()
+  })
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::args::VocabFormat;
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+/// A corpus term with the number of
+/// documents containing it and its
+/// occurrences over all stored chunks.
+#[derive(Debug, Serialize)]
+pub(super) struct VocabTerm {
+  pub term:               String,
+  pub document_frequency: usize,
+  pub total_frequency:    usize
+}
+
+/// Every term of the corpus stats found
+/// in at least `min_df` documents, most
+/// widespread first. Total frequencies
+/// count chunk tokens, so text shared
+/// by overlapping chunks counts once
+/// per chunk.
+pub(super) fn vocabulary(
+  state: &State,
+  tokenizer: &Tokenizer,
+  min_df: usize
+) -> Vec<VocabTerm> {
+  let mut totals: HashMap<
+    String,
+    usize
+  > = HashMap::new();
+  for chunk in &state.chunks {
+    for token in
+      tokenizer.tokenize(&chunk.text)
+    {
+      *totals
+        .entry(token)
+        .or_insert(0) += 1;
+    }
+  }
+  let mut terms: Vec<VocabTerm> = state
+    .stats
+    .document_frequency
+    .iter()
+    .filter(|(_, df)| **df >= min_df)
+    .map(|(term, df)| {
+      VocabTerm {
+        term:               term
+          .clone(),
+        document_frequency: *df,
+        total_frequency:    totals
+          .get(term)
+          .copied()
+          .unwrap_or(0)
+      }
+    })
+    .collect();
+  terms.sort_by(|a, b| {
+    b.document_frequency
+      .cmp(&a.document_frequency)
+      .then_with(|| {
+        b.total_frequency
+          .cmp(&a.total_frequency)
+      })
+      .then_with(|| a.term.cmp(&b.term))
+  });
+  terms
+}
+
+pub(super) fn render_vocabulary(
+  terms: &[VocabTerm],
+  format: VocabFormat
+) -> Result<String> {
+  Ok(match format {
+    | VocabFormat::Json => {
+      serde_json::to_string_pretty(
+        terms
+      )?
+    }
+    | VocabFormat::Csv => {
+      let mut out = String::from(
+        "term,document_frequency,\
+         total_frequency\n"
+      );
+      for term in terms {
+        let _ = writeln!(
+          out,
+          "{},{},{}",
+          term.term,
+          term.document_frequency,
+          term.total_frequency
+        );
+      }
+      out
+    }
+  })
+}
@@ -0,0 +1,215 @@
+use std::collections::BTreeSet;
+use std::path::{
+  Path,
+  PathBuf
+};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::{
+  Context,
+  Result
+};
+use notify::{
+  Event,
+  EventKind,
+  RecursiveMode,
+  Watcher
+};
+
+use super::ingest::{
+  IngestOptions,
+  ingest
+};
+use super::save_corpus;
+use super::sources::{
+  document_path,
+  has_allowed_extension
+};
+use super::stats::remove_document;
+use crate::chunk::ChunkStrategy;
+use crate::config::Config;
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+/// Watches `path` and applies file
+/// changes incrementally: created or
+/// modified files are (re-)ingested and
+/// deleted files are removed from the
+/// corpus. Events are batched until
+/// `watch_debounce_ms` passes without a
+/// new one, and the corpus is saved
+/// after every batch. Runs until the
+/// watcher shuts down or `keep_going`
+/// returns false after a batch.
+pub(super) fn watch(
+  path: &Path,
+  strategy: ChunkStrategy,
+  config: &Config,
+  state: &mut State,
+  index: &mut VectorIndex,
+  embedder: &dyn Embedder,
+  mut keep_going: impl FnMut(&State) -> bool
+) -> Result<()> {
+  let (tx, rx) = mpsc::channel();
+  let mut watcher =
+    notify::recommended_watcher(tx)
+      .context("create file watcher")?;
+  watcher
+    .watch(
+      path,
+      RecursiveMode::Recursive
+    )
+    .with_context(|| {
+      format!("watch {:?}", path)
+    })?;
+  let debounce = Duration::from_millis(
+    config
+      .stage1
+      .ingest
+      .watch_debounce_ms
+  );
+  let state_path =
+    config.stage1.storage.state_path();
+  println!(
+    "Watching {} for changes…",
+    path.display()
+  );
+  while let Ok(first) = rx.recv() {
+    let mut changed = BTreeSet::new();
+    collect_paths(first, &mut changed);
+    while let Ok(event) =
+      rx.recv_timeout(debounce)
+    {
+      collect_paths(
+        event,
+        &mut changed
+      );
+    }
+    let applied = apply_changes(
+      &changed, strategy, config,
+      state, index, embedder
+    )?;
+    if applied {
+      save_corpus(
+        state,
+        index,
+        config,
+        &state_path
+      )?;
+    }
+    if !keep_going(state) {
+      break;
+    }
+  }
+  Ok(())
+}
+
+fn collect_paths(
+  event: notify::Result<Event>,
+  changed: &mut BTreeSet<PathBuf>
+) {
+  match event {
+    | Ok(event) => {
+      if matches!(
+        event.kind,
+        EventKind::Create(_)
+          | EventKind::Modify(_)
+          | EventKind::Remove(_)
+      ) {
+        changed.extend(event.paths);
+      }
+    }
+    | Err(err) => {
+      eprintln!(
+        "Warning: watch error: {}",
+        err
+      );
+    }
+  }
+}
+
+/// Removes the stored document for
+/// every changed path, then re-ingests
+/// the paths that still exist. Returns
+/// whether anything was touched.
+fn apply_changes(
+  changed: &BTreeSet<PathBuf>,
+  strategy: ChunkStrategy,
+  config: &Config,
+  state: &mut State,
+  index: &mut VectorIndex,
+  embedder: &dyn Embedder
+) -> Result<bool> {
+  let extensions =
+    &config.stage1.ingest.extensions;
+  let tokenizer =
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    );
+  let mut applied = false;
+  for file in changed {
+    if !has_allowed_extension(
+      file, extensions
+    ) {
+      continue;
+    }
+    let doc_path = document_path(
+      file,
+      &config.stage1.storage
+    );
+    let stale: Vec<String> = state
+      .documents
+      .iter()
+      .filter(|doc| {
+        doc.path == doc_path
+      })
+      .map(|doc| doc.id.clone())
+      .collect();
+    for doc_id in &stale {
+      remove_document(
+        state, index, doc_id,
+        &tokenizer
+      );
+      applied = true;
+    }
+    if !file.is_file() {
+      if !stale.is_empty() {
+        println!(
+          "Removed {}",
+          doc_path
+        );
+      }
+      continue;
+    }
+    let ingested = ingest(
+      file,
+      IngestOptions {
+        from_manifest: false,
+        strategy,
+        emit_word_tally: false,
+        emit_normalized: false
+      },
+      config,
+      state,
+      index,
+      embedder
+    );
+    match ingested {
+      | Ok(summary) => {
+        applied |=
+          summary.failed.is_empty()
+      }
+      | Err(err) => {
+        eprintln!(
+          "Warning: failed to ingest \
+           {:?}: {:#}",
+          file, err
+        );
+      }
+    }
+  }
+  Ok(applied)
+}
@@ -0,0 +1,42 @@
+use std::cell::RefCell;
+
+use rand::rngs::StdRng;
+use rand::{
+  Rng,
+  SeedableRng
+};
+use uuid::Uuid;
+
+thread_local! {
+  static SEEDED: RefCell<Option<StdRng>> =
+    const { RefCell::new(None) };
+}
+
+/// Switches this thread to a
+/// deterministic generator seeded with
+/// `seed`, or back to OS randomness for
+/// `None`. Every random draw in oxbed
+/// goes through this module so seeded
+/// runs are reproducible.
+pub fn seed(seed: Option<u64>) {
+  SEEDED.with(|rng| {
+    *rng.borrow_mut() =
+      seed.map(StdRng::seed_from_u64);
+  });
+}
+
+/// A random (v4) UUID, drawn from the
+/// seeded generator when one is set.
+pub fn uuid() -> Uuid {
+  SEEDED.with(|rng| {
+    match rng.borrow_mut().as_mut() {
+      | Some(rng) => {
+        uuid::Builder::from_random_bytes(
+          rng.random()
+        )
+        .into_uuid()
+      }
+      | None => Uuid::new_v4()
+    }
+  })
+}
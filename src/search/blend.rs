@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+
+use crate::config::{
+  Config,
+  EmbedderKind
+};
+use crate::embedder::build_embedder;
+use crate::index::{
+  IndexEntry,
+  VectorIndex,
+  cosine_similarity
+};
+use crate::state::State;
+
+/// Scores every indexed chunk passing
+/// `keep` by the weighted sum of its
+/// cosine similarity to `query` under
+/// each blended embedder. Chunks are
+/// re-embedded at search time, so the
+/// cost grows with the corpus and the
+/// number of blended embedders. Returns
+/// the `fetch` best positive scores.
+pub(super) fn blend_matches(
+  blend: &[(EmbedderKind, f32)],
+  query: &str,
+  fetch: usize,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex,
+  keep: impl Fn(&IndexEntry) -> bool
+) -> Result<Vec<(usize, f32)>> {
+  let embedders = blend
+    .iter()
+    .map(|(kind, weight)| {
+      let embedder = build_embedder(
+        kind.clone(),
+        config
+      )?;
+      let query_vector =
+        embedder.embed_query(query);
+      Ok((
+        embedder,
+        query_vector,
+        *weight
+      ))
+    })
+    .collect::<Result<Vec<_>>>()?;
+  let search_summaries = config
+    .stage1
+    .search
+    .search_summaries;
+  let mut scored: Vec<(usize, f32)> =
+    index
+      .entries()
+      .iter()
+      .enumerate()
+      .filter(|(_, entry)| keep(entry))
+      .filter_map(|(idx, entry)| {
+        let chunk = state.find_chunk(
+          &entry.chunk_id
+        )?;
+        let text = match &chunk.summary
+        {
+          | Some(summary)
+            if search_summaries =>
+          {
+            summary
+          }
+          | _ => &chunk.text
+        };
+        let score = embedders
+          .iter()
+          .map(
+            |(
+              embedder,
+              query_vector,
+              weight
+            )| {
+              weight
+                * cosine_similarity(
+                  query_vector,
+                  &embedder
+                    .embed_document(
+                      text
+                    )
+                )
+            }
+          )
+          .sum::<f32>();
+        (score > 0.0)
+          .then_some((idx, score))
+      })
+      .collect();
+  scored.sort_by(|a, b| {
+    b.1
+      .partial_cmp(&a.1)
+      .unwrap_or(Ordering::Equal)
+  });
+  scored.truncate(fetch);
+  Ok(scored)
+}
@@ -0,0 +1,77 @@
+use super::SearchHit;
+use crate::index::{
+  VectorIndex,
+  cosine_similarity
+};
+
+/// Groups `hits` by single linkage: two
+/// hits share a cluster when a chain of
+/// hits, each with vector cosine above
+/// `threshold` to the next, joins them.
+/// Returns each cluster's positions in
+/// `hits`, ascending, with clusters
+/// ordered by their best-ranked member
+/// (the representative). Hits without
+/// a stored vector stay on their own.
+pub fn cluster_hits(
+  hits: &[SearchHit],
+  index: &VectorIndex,
+  threshold: f32
+) -> Vec<Vec<usize>> {
+  let vectors: Vec<_> = hits
+    .iter()
+    .map(|hit| {
+      index.vector_for(&hit.chunk.id)
+    })
+    .collect();
+  let mut parent: Vec<usize> =
+    (0..hits.len()).collect();
+  for a in 0..hits.len() {
+    for b in a + 1..hits.len() {
+      let (Some(va), Some(vb)) =
+        (vectors[a], vectors[b])
+      else {
+        continue;
+      };
+      if cosine_similarity(va, vb)
+        > threshold
+      {
+        let (ra, rb) = (
+          root(&mut parent, a),
+          root(&mut parent, b)
+        );
+        // Keep the lower position as
+        // the root so it represents
+        // the cluster.
+        parent[ra.max(rb)] = ra.min(rb);
+      }
+    }
+  }
+  let mut clusters: Vec<Vec<usize>> =
+    Vec::new();
+  let mut cluster_of =
+    vec![usize::MAX; hits.len()];
+  for pos in 0..hits.len() {
+    let root = root(&mut parent, pos);
+    if cluster_of[root] == usize::MAX {
+      cluster_of[root] = clusters.len();
+      clusters.push(Vec::new());
+    }
+    clusters[cluster_of[root]]
+      .push(pos);
+  }
+  clusters
+}
+
+/// The union-find root of `node`,
+/// halving the path on the way.
+fn root(
+  parent: &mut [usize],
+  mut node: usize
+) -> usize {
+  while parent[node] != node {
+    parent[node] = parent[parent[node]];
+    node = parent[node];
+  }
+  node
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+
+use anyhow::{
+  Context,
+  Result
+};
+use serde::Serialize;
+
+use super::{
+  SearchFilter,
+  above_threshold,
+  trace_search
+};
+use crate::config::Config;
+use crate::embedder::{
+  Embedder,
+  SparseVector
+};
+use crate::index::{
+  VectorIndex,
+  cosine_fn
+};
+use crate::state::State;
+
+/// Why a chunk was or was not returned
+/// for a query.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  PartialEq,
+  Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+  Returned,
+  /// The query and chunk vectors share
+  /// no term
+  NoSharedTerms,
+  BelowThreshold,
+  /// Outranked by `top_k` other chunks
+  /// or dropped by a reranking step
+  NotInTopK
+}
+
+impl fmt::Display for Verdict {
+  fn fmt(
+    &self,
+    f: &mut fmt::Formatter<'_>
+  ) -> fmt::Result {
+    f.write_str(match self {
+      | Verdict::Returned => "returned",
+      | Verdict::NoSharedTerms => {
+        "no query term occurs in the \
+         chunk"
+      }
+      | Verdict::BelowThreshold => {
+        "score is below the threshold"
+      }
+      | Verdict::NotInTopK => {
+        "outranked by other chunks"
+      }
+    })
+  }
+}
+
+/// How one chunk fared against a query.
+#[derive(Debug, Serialize)]
+pub struct Diagnosis {
+  pub query_vector:     SparseVector,
+  pub chunk_vector:     SparseVector,
+  pub cosine:           f32,
+  pub score_threshold:  f32,
+  /// Whether the chunk would clear the
+  /// threshold; never for a chunk
+  /// sharing no term with the query
+  pub passes_threshold: bool,
+  /// 1-based rank among the fetched
+  /// candidates, when fetched
+  pub candidate_rank:   Option<usize>,
+  /// 1-based rank among the returned
+  /// hits, when returned
+  pub hit_rank:         Option<usize>,
+  /// Query terms the chunk vector
+  /// lacks
+  pub missing_terms:    Vec<String>,
+  pub verdict:          Verdict
+}
+
+/// Runs `query` as a search would and
+/// explains where `chunk_id` ended up.
+/// The cosine is the raw vector score,
+/// before any score adjustments.
+pub fn diagnose(
+  embedder: &dyn Embedder,
+  query: &str,
+  chunk_id: &str,
+  top_k: usize,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex
+) -> Result<Diagnosis> {
+  let chunk_vector = index
+    .vector_for(chunk_id)
+    .with_context(|| {
+      format!(
+        "chunk {} is not indexed",
+        chunk_id
+      )
+    })?
+    .clone();
+  let (_, trace) = trace_search(
+    embedder,
+    query,
+    top_k,
+    &SearchFilter::default(),
+    config,
+    state,
+    index
+  )?;
+  let cosine = cosine_fn(
+    config.stage1.search.high_precision
+  )(
+    &trace.query_vector,
+    &chunk_vector
+  );
+  let candidate_rank = trace
+    .candidates
+    .iter()
+    .position(|candidate| {
+      candidate.chunk_id == chunk_id
+    });
+  let hit_rank =
+    trace.hits.iter().position(|hit| {
+      hit.chunk_id == chunk_id
+    });
+  let mut scores: Vec<(usize, f32)> =
+    trace
+      .candidates
+      .iter()
+      .map(|candidate| candidate.score)
+      .enumerate()
+      .collect();
+  let target = candidate_rank
+    .unwrap_or_else(|| {
+      scores
+        .push((scores.len(), cosine));
+      scores.len() - 1
+    });
+  // Search never matches a chunk that
+  // scores zero, whatever the threshold
+  let passes_threshold = (candidate_rank
+    .is_some()
+    || cosine > 0.0)
+    && above_threshold(scores, config)
+      .iter()
+      .any(|(idx, _)| *idx == target);
+  let mut missing_terms: Vec<String> =
+    trace
+      .query_vector
+      .keys()
+      .filter(|term| {
+        !chunk_vector
+          .contains_key(*term)
+      })
+      .cloned()
+      .collect();
+  missing_terms.sort();
+  let verdict = if hit_rank.is_some() {
+    Verdict::Returned
+  } else if missing_terms.len()
+    == trace.query_vector.len()
+  {
+    Verdict::NoSharedTerms
+  } else if !passes_threshold {
+    Verdict::BelowThreshold
+  } else {
+    Verdict::NotInTopK
+  };
+  Ok(Diagnosis {
+    query_vector: trace.query_vector,
+    chunk_vector,
+    cosine,
+    score_threshold: trace
+      .score_threshold,
+    passes_threshold,
+    candidate_rank: candidate_rank
+      .map(|rank| rank + 1),
+    hit_rank: hit_rank
+      .map(|rank| rank + 1),
+    missing_terms,
+    verdict
+  })
+}
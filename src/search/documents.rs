@@ -0,0 +1,171 @@
+use super::SearchHit;
+use crate::config::DocumentScore;
+use crate::state::Document;
+
+/// Chunk hits fetched per requested
+/// document, so documents can collect
+/// several chunks before aggregation.
+pub const DOCUMENT_OVERFETCH: usize = 4;
+
+/// A document ranked by the aggregated
+/// score of its chunk hits.
+#[derive(Clone, Debug)]
+pub struct DocumentHit {
+  pub document: Document,
+  pub score:    f32,
+  /// The document's highest-scoring
+  /// chunk hit
+  pub best:     SearchHit
+}
+
+/// Aggregates chunk `hits` per document
+/// with `aggregation` and returns the
+/// `top_k` best documents, highest
+/// score first.
+pub fn rank_documents(
+  hits: Vec<SearchHit>,
+  aggregation: DocumentScore,
+  top_k: usize
+) -> Vec<DocumentHit> {
+  let mut documents: Vec<DocumentHit> =
+    Vec::new();
+  for hit in hits {
+    match documents.iter_mut().find(
+      |doc| {
+        doc.document.id
+          == hit.document.id
+      }
+    ) {
+      | Some(doc) => {
+        doc.score = match aggregation {
+          | DocumentScore::Max => {
+            doc.score.max(hit.score)
+          }
+          | DocumentScore::Sum => {
+            doc.score + hit.score
+          }
+        };
+        if hit.score > doc.best.score {
+          doc.best = hit;
+        }
+      }
+      | None => {
+        documents.push(DocumentHit {
+          document: hit
+            .document
+            .clone(),
+          score:    hit.score,
+          best:     hit
+        })
+      }
+    }
+  }
+  documents.sort_by(|a, b| {
+    b.score.total_cmp(&a.score)
+  });
+  documents.truncate(top_k);
+  documents
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::chunk::{
+    Chunk,
+    ChunkStrategy
+  };
+
+  fn hit(
+    doc_id: &str,
+    chunk_id: &str,
+    score: f32
+  ) -> SearchHit {
+    SearchHit {
+      chunk: Chunk {
+        id:        chunk_id.into(),
+        doc_id:    doc_id.into(),
+        text:      String::new(),
+        start:     0,
+        end:       0,
+        strategy:
+          ChunkStrategy::Structured,
+        language:  None,
+        raw_start: None,
+        raw_end:   None,
+        summary:   None,
+        heading:   None,
+        run_id:    0
+      },
+      document: Document {
+        id:          doc_id.into(),
+        path:        format!(
+          "{}.txt",
+          doc_id
+        ),
+        hash:        doc_id.into(),
+        token_count: 0,
+        tags:        Default::default(),
+        aliases:     Vec::new(),
+        normalized:  None
+      },
+      score
+    }
+  }
+
+  #[test]
+  fn documents_rank_by_aggregated_score()
+   {
+    let hits = || {
+      vec![
+        hit("b", "b1", 0.6),
+        hit("a", "a1", 0.5),
+        hit("a", "a2", 0.4),
+        hit("c", "c1", 0.1),
+      ]
+    };
+    let ids =
+      |docs: &[DocumentHit]| {
+        docs
+          .iter()
+          .map(|doc| {
+            (
+              doc.document.id.clone(),
+              doc.best.chunk.id.clone()
+            )
+          })
+          .collect::<Vec<_>>()
+      };
+    let max = rank_documents(
+      hits(),
+      DocumentScore::Max,
+      2
+    );
+    assert_eq!(ids(&max), vec![
+      (
+        "b".to_string(),
+        "b1".to_string()
+      ),
+      (
+        "a".to_string(),
+        "a1".to_string()
+      )
+    ]);
+    let sum = rank_documents(
+      hits(),
+      DocumentScore::Sum,
+      3
+    );
+    assert_eq!(
+      sum
+        .iter()
+        .map(|doc| {
+          doc.document.id.as_str()
+        })
+        .collect::<Vec<_>>(),
+      vec!["a", "b", "c"]
+    );
+    assert!(
+      (sum[0].score - 0.9).abs() < 1e-6
+    );
+  }
+}
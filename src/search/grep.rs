@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+
+use super::SearchHit;
+use crate::state::State;
+
+/// Score given to chunks found by the
+/// substring fallback; vector hits
+/// always score above zero.
+pub(super) const GREP_FALLBACK_SCORE:
+  f32 = 0.0;
+
+/// Chunks whose text contains `query`
+/// case-insensitively, in corpus
+/// order, scored
+/// `GREP_FALLBACK_SCORE`. Chunks of
+/// `short_documents` are skipped.
+pub(super) fn grep_hits(
+  query: &str,
+  top_k: usize,
+  allowed: Option<&HashSet<&str>>,
+  short_documents: &HashSet<&str>,
+  state: &State
+) -> Vec<SearchHit> {
+  let needle =
+    query.trim().to_lowercase();
+  if needle.is_empty() {
+    return Vec::new();
+  }
+  state
+    .chunks
+    .iter()
+    .filter(|chunk| {
+      allowed.is_none_or(|allowed| {
+        allowed
+          .contains(chunk.id.as_str())
+      }) && !short_documents
+        .contains(chunk.doc_id.as_str())
+        && chunk
+          .text
+          .to_lowercase()
+          .contains(&needle)
+    })
+    .filter_map(|chunk| {
+      let document = state
+        .find_document(&chunk.doc_id)?;
+      Some(SearchHit {
+        chunk:    chunk.clone(),
+        document: document.clone(),
+        score:    GREP_FALLBACK_SCORE
+      })
+    })
+    .take(top_k)
+    .collect()
+}
@@ -0,0 +1,512 @@
+mod blend;
+mod cluster;
+mod diagnose;
+mod documents;
+mod grep;
+mod query_log;
+mod rerank;
+mod spelling;
+mod term_weights;
+mod threshold;
+mod trace;
+
+use std::collections::HashSet;
+
+use anyhow::{
+  Context,
+  Result
+};
+
+use self::blend::blend_matches;
+pub use self::cluster::cluster_hits;
+pub use self::diagnose::{
+  Verdict,
+  diagnose
+};
+pub use self::documents::{
+  DOCUMENT_OVERFETCH,
+  DocumentHit,
+  rank_documents
+};
+use self::grep::grep_hits;
+pub use self::query_log::log_query;
+use self::rerank::{
+  RERANK_OVERFETCH,
+  adjust_scores,
+  drop_near_duplicates,
+  mmr_select,
+  penalize_negative,
+  pin_documents,
+  prefer_strategy,
+  reorders_candidates
+};
+use self::spelling::correct_query;
+use self::term_weights::{
+  apply_term_weights,
+  parse_term_weights
+};
+use self::threshold::above_threshold;
+pub use self::trace::{
+  SearchTrace,
+  TracedScore
+};
+use crate::chunk::Chunk;
+use crate::config::{
+  Config,
+  Ranking,
+  ThresholdMode
+};
+use crate::embedder::Embedder;
+use crate::index::{
+  IndexEntry,
+  VectorIndex
+};
+use crate::normalization::Normalizer;
+use crate::state::{
+  Document,
+  State
+};
+use crate::tokenizer::Tokenizer;
+
+/// Per-query restrictions applied
+/// before ranking.
+#[derive(Debug, Default)]
+pub struct SearchFilter {
+  pub language: Option<String>,
+  pub doc_ids:  Option<Vec<String>>,
+  /// Text whose topic hits are pushed
+  /// away from (`search --not`); a
+  /// penalty rather than a restriction
+  pub negative: Option<String>
+}
+
+impl SearchFilter {
+  fn allowed_chunks<'a>(
+    &self,
+    state: &'a State
+  ) -> Option<HashSet<&'a str>> {
+    if self.language.is_none()
+      && self.doc_ids.is_none()
+    {
+      return None;
+    }
+    Some(
+      state
+        .chunks
+        .iter()
+        .filter(|chunk| {
+          self
+            .language
+            .as_deref()
+            .is_none_or(|language| {
+              chunk.language.as_deref()
+                == Some(language)
+            })
+            && self.allows_document(
+              &chunk.doc_id
+            )
+        })
+        .map(|chunk| chunk.id.as_str())
+        .collect()
+    )
+  }
+
+  fn allows_document(
+    &self,
+    doc_id: &str
+  ) -> bool {
+    self.doc_ids.as_ref().is_none_or(
+      |ids| {
+        ids
+          .iter()
+          .any(|id| id == doc_id)
+      }
+    )
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+  pub chunk:    Chunk,
+  pub document: Document,
+  pub score:    f32
+}
+
+pub fn search_hits(
+  embedder: &dyn Embedder,
+  query: &str,
+  top_k: usize,
+  filter: &SearchFilter,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex
+) -> Result<Vec<SearchHit>> {
+  trace_search(
+    embedder, query, top_k, filter,
+    config, state, index
+  )
+  .map(|(hits, _)| hits)
+}
+
+/// Like [`search_hits`], also
+/// returning the query's
+/// [`SearchTrace`].
+pub fn trace_search(
+  embedder: &dyn Embedder,
+  query: &str,
+  top_k: usize,
+  filter: &SearchFilter,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex
+) -> Result<(Vec<SearchHit>, SearchTrace)>
+{
+  let search_cfg =
+    &config.stage1.search;
+  let routed = filter
+    .language
+    .as_deref()
+    .and_then(|language| {
+      embedder.for_language(language)
+    });
+  let embedder = routed
+    .as_deref()
+    .unwrap_or(embedder);
+  let (unweighted, term_weights) =
+    if search_cfg.parse_term_weights {
+      parse_term_weights(query)
+    } else {
+      (query.to_string(), Vec::new())
+    };
+  let normalize = |text: String| {
+    if config
+      .stage1
+      .embedder
+      .normalize_query
+    {
+      Normalizer::from_config(
+        &config.stage1.normalization
+      )
+      .normalize(&text)
+    } else {
+      text
+    }
+  };
+  let query_text =
+    normalize(unweighted);
+  let negative_vector = filter
+    .negative
+    .as_ref()
+    .filter(|_| {
+      search_cfg.negative_weight > 0.0
+    })
+    .map(|text| {
+      embedder.embed_query(&normalize(
+        text.clone()
+      ))
+    });
+  let tokenizer =
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    )
+    .for_language(
+      filter.language.as_deref()
+    );
+  let (query_text, corrections) =
+    if search_cfg.spell_correct {
+      correct_query(
+        &query_text,
+        &tokenizer,
+        &state.stats.document_frequency
+      )
+    } else {
+      (query_text, Vec::new())
+    };
+  for (typo, term) in &corrections {
+    eprintln!(
+      "Corrected query term {:?} to \
+       {:?}",
+      typo, term
+    );
+  }
+  let mut query_vector =
+    embedder.embed_query(&query_text);
+  apply_term_weights(
+    &mut query_vector,
+    &term_weights,
+    embedder
+  );
+  let query_terms: HashSet<String> =
+    if search_cfg.proximity_boost > 0.0
+    {
+      tokenizer
+        .tokenize(&query_text)
+        .into_iter()
+        .collect()
+    } else {
+      HashSet::new()
+    };
+  let fetch =
+    if reorders_candidates(search_cfg)
+      || negative_vector.is_some()
+    {
+      top_k * RERANK_OVERFETCH
+    } else {
+      top_k
+    };
+  let allowed =
+    filter.allowed_chunks(state);
+  let short_documents: HashSet<&str> =
+    state
+      .documents
+      .iter()
+      .filter(|doc| {
+        doc.token_count
+          < search_cfg.min_doc_tokens
+      })
+      .map(|doc| doc.id.as_str())
+      .collect();
+  let top_documents: Option<
+    HashSet<&str>
+  > = search_cfg.two_stage.then(|| {
+    index
+      .top_documents(
+        &query_vector,
+        fetch,
+        search_cfg.high_precision,
+        |doc_id| {
+          filter.allows_document(doc_id)
+            && !short_documents
+              .contains(doc_id)
+        }
+      )
+      .into_iter()
+      .collect()
+  });
+  let keep = |entry: &IndexEntry| {
+    !short_documents
+      .contains(entry.doc_id.as_str())
+      && allowed.as_ref().is_none_or(
+        |allowed| {
+          allowed.contains(
+            entry.chunk_id.as_str()
+          )
+        }
+      )
+      && top_documents
+        .as_ref()
+        .is_none_or(|docs| {
+          docs.contains(
+            entry.doc_id.as_str()
+          )
+        })
+  };
+  let matches =
+    if !search_cfg.blend.is_empty() {
+      blend_matches(
+        &search_cfg.blend,
+        &query_text,
+        fetch,
+        config,
+        state,
+        index,
+        keep
+      )?
+    } else {
+      match search_cfg.ranking {
+        | Ranking::Cosine => {
+          index.search_where(
+            &query_vector,
+            fetch,
+            search_cfg.high_precision,
+            keep
+          )
+        }
+        | Ranking::Bm25 => {
+          index.search_bm25_where(
+            &query_vector,
+            fetch,
+            search_cfg.bm25_k1,
+            search_cfg.bm25_b,
+            keep
+          )
+        }
+      }
+    };
+  let mut trace = SearchTrace {
+    query: query.to_string(),
+    normalized_query: query_text,
+    corrections,
+    query_vector,
+    candidates:
+      TracedScore::from_matches(
+        &matches, index
+      ),
+    score_threshold: search_cfg
+      .score_threshold,
+    hits: Vec::new()
+  };
+  let matches = adjust_scores(
+    matches,
+    embedder,
+    &tokenizer,
+    &query_terms,
+    search_cfg,
+    state,
+    index
+  );
+  let mut matches =
+    above_threshold(matches, config);
+  if let Some(negative) =
+    &negative_vector
+  {
+    matches = penalize_negative(
+      matches,
+      negative,
+      search_cfg.negative_weight,
+      index
+    );
+  }
+  if let Some(preferred) =
+    search_cfg.prefer_strategy
+  {
+    matches = prefer_strategy(
+      matches, preferred, state, index
+    );
+  }
+  if let Some(threshold) =
+    search_cfg.dedup_similarity
+  {
+    matches = drop_near_duplicates(
+      matches, index, threshold
+    );
+  }
+  if let Some(lambda) =
+    search_cfg.mmr_lambda
+  {
+    matches = mmr_select(
+      matches, index, lambda, top_k
+    );
+  }
+  if !search_cfg
+    .pinned_doc_ids
+    .is_empty()
+  {
+    matches = pin_documents(
+      matches,
+      index,
+      &search_cfg.pinned_doc_ids
+    );
+  }
+  matches.truncate(top_k);
+  let hits = if matches.is_empty()
+    && search_cfg.grep_fallback
+  {
+    grep_hits(
+      query,
+      top_k,
+      allowed.as_ref(),
+      &short_documents,
+      state
+    )
+  } else {
+    resolve_hits(matches, state, index)?
+  };
+  trace.hits = hits
+    .iter()
+    .map(|hit| {
+      TracedScore {
+        chunk_id: hit.chunk.id.clone(),
+        doc_id:   hit
+          .document
+          .id
+          .clone(),
+        score:    hit.score
+      }
+    })
+    .collect();
+  Ok((hits, trace))
+}
+
+/// Ranks chunks by similarity to the
+/// stored vector of `chunk_id`,
+/// excluding that chunk itself. A
+/// percentile threshold is taken over
+/// an overfetched candidate list, as in
+/// [`search`].
+pub fn similar_hits(
+  chunk_id: &str,
+  top_k: usize,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex
+) -> Result<Vec<SearchHit>> {
+  let vector = index
+    .vector_for(chunk_id)
+    .with_context(|| {
+      format!(
+        "chunk {} is not indexed",
+        chunk_id
+      )
+    })?;
+  let fetch = if config
+    .stage1
+    .search
+    .threshold_mode
+    == ThresholdMode::Percentile
+  {
+    top_k * RERANK_OVERFETCH
+  } else {
+    top_k
+  };
+  let mut matches =
+    index.search(vector, fetch + 1);
+  matches.retain(|(idx, _)| {
+    index.entries()[*idx].chunk_id
+      != chunk_id
+  });
+  let mut matches =
+    above_threshold(matches, config);
+  matches.truncate(top_k);
+  resolve_hits(matches, state, index)
+}
+
+fn resolve_hits(
+  matches: Vec<(usize, f32)>,
+  state: &State,
+  index: &VectorIndex
+) -> Result<Vec<SearchHit>> {
+  let mut results = Vec::new();
+  for (idx, score) in matches {
+    let entry = index
+      .entries()
+      .get(idx)
+      .context(
+        "missing index entry for \
+         search result"
+      )?;
+    let chunk = state
+      .find_chunk(
+        entry.chunk_id.as_str()
+      )
+      .context(
+        "chunk metadata missing"
+      )?;
+    let document = state
+      .find_document(
+        entry.doc_id.as_str()
+      )
+      .context(
+        "document metadata missing"
+      )?;
+    results.push(SearchHit {
+      chunk: chunk.clone(),
+      document: document.clone(),
+      score
+    });
+  }
+  Ok(results)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,88 @@
+use std::fs::{
+  self,
+  OpenOptions
+};
+use std::io::{
+  BufWriter,
+  Write
+};
+use std::path::Path;
+
+use anyhow::{
+  Context,
+  Result
+};
+use chrono::Utc;
+use serde::Serialize;
+
+use super::SearchHit;
+use crate::config::Config;
+
+#[derive(Serialize)]
+struct QueryLogRecord<'a> {
+  timestamp: String,
+  query:     &'a str,
+  top_k:     usize,
+  results:   usize,
+  top_score: Option<f32>
+}
+
+/// Appends the query and a summary of
+/// its hits to
+/// `stage1.search.query_log`, when set.
+/// A failed write only warns, so
+/// logging never fails the search.
+pub fn log_query(
+  query: &str,
+  top_k: usize,
+  hits: &[SearchHit],
+  config: &Config
+) {
+  let Some(path) =
+    &config.stage1.search.query_log
+  else {
+    return;
+  };
+  let record = QueryLogRecord {
+    timestamp: Utc::now().to_rfc3339(),
+    query,
+    top_k,
+    results: hits.len(),
+    top_score: hits
+      .first()
+      .map(|hit| hit.score)
+  };
+  if let Err(err) =
+    append_record(path, &record)
+  {
+    eprintln!(
+      "Warning: could not log query \
+       to {:?}: {:#}",
+      path, err
+    );
+  }
+}
+
+fn append_record(
+  path: &Path,
+  record: &QueryLogRecord<'_>
+) -> Result<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(path)
+    .with_context(|| {
+      format!("open {:?}", path)
+    })?;
+  let mut writer = BufWriter::new(file);
+  serde_json::to_writer(
+    &mut writer,
+    record
+  )?;
+  writeln!(writer)?;
+  writer.flush()?;
+  Ok(())
+}
@@ -0,0 +1,392 @@
+use std::cmp::Ordering;
+use std::collections::{
+  HashMap,
+  HashSet
+};
+
+use crate::chunk::{
+  Chunk,
+  ChunkStrategy
+};
+use crate::config::{
+  Stage1Search,
+  ThresholdMode
+};
+use crate::embedder::{
+  Embedder,
+  SparseVector
+};
+use crate::index::{
+  VectorIndex,
+  cosine_similarity
+};
+use crate::state::State;
+use crate::tokenizer::Tokenizer;
+
+/// Candidates fetched per requested
+/// hit when score adjustments, MMR,
+/// pinning, strategy preference,
+/// near-duplicate dropping, or a
+/// percentile threshold may reorder or
+/// discard some of them.
+pub(super) const RERANK_OVERFETCH:
+  usize = 4;
+
+pub(super) fn reorders_candidates(
+  search_cfg: &Stage1Search
+) -> bool {
+  search_cfg.mmr_lambda.is_some()
+    || search_cfg
+      .dedup_similarity
+      .is_some()
+    || search_cfg.length_penalty > 0.0
+    || search_cfg.position_boost > 0.0
+    || search_cfg.proximity_boost > 0.0
+    || !search_cfg
+      .pinned_doc_ids
+      .is_empty()
+    || search_cfg
+      .prefer_strategy
+      .is_some()
+    || search_cfg.threshold_mode
+      == ThresholdMode::Percentile
+}
+
+/// Applies per-chunk score
+/// adjustments and re-sorts. The length
+/// penalty scales each score by `1 -
+/// penalty / (tokens + 1)`, demoting
+/// fragments whose few terms inflate
+/// their cosine. The position boost
+/// scales it by
+/// `1 + boost / (position + 1)`, where
+/// position is the chunk's rank by
+/// `start` within its document. The
+/// proximity boost scales it by
+/// `1 + boost * terms / window` for
+/// chunks holding every distinct query
+/// term, where window is the fewest
+/// consecutive tokens covering them.
+pub(super) fn adjust_scores(
+  mut matches: Vec<(usize, f32)>,
+  embedder: &dyn Embedder,
+  tokenizer: &Tokenizer,
+  query_terms: &HashSet<String>,
+  search_cfg: &Stage1Search,
+  state: &State,
+  index: &VectorIndex
+) -> Vec<(usize, f32)> {
+  let penalty =
+    search_cfg.length_penalty;
+  let boost = search_cfg.position_boost;
+  let proximity =
+    if query_terms.len() >= 2 {
+      search_cfg.proximity_boost
+    } else {
+      0.0
+    };
+  if penalty <= 0.0
+    && boost <= 0.0
+    && proximity <= 0.0
+  {
+    return matches;
+  }
+  let entries = index.entries();
+  let chunks = chunk_ordinals(state);
+  for (idx, score) in &mut matches {
+    let Some(&(chunk, position)) =
+      chunks.get(
+        entries[*idx].chunk_id.as_str()
+      )
+    else {
+      continue;
+    };
+    if penalty > 0.0 {
+      let tokens = embedder
+        .token_count(&chunk.text);
+      *score *= (1.0
+        - penalty
+          / (tokens as f32 + 1.0))
+        .max(0.0);
+    }
+    if boost > 0.0 {
+      *score *= 1.0
+        + boost
+          / (position as f32 + 1.0);
+    }
+    if proximity > 0.0
+      && let Some(window) = min_window(
+        &tokenizer
+          .tokenize(&chunk.text),
+        query_terms
+      )
+    {
+      *score *= 1.0
+        + proximity
+          * query_terms.len() as f32
+          / window as f32;
+    }
+  }
+  matches.sort_by(|a, b| {
+    b.1
+      .partial_cmp(&a.1)
+      .unwrap_or(Ordering::Equal)
+  });
+  matches
+}
+
+/// Every chunk by ID, with how many
+/// chunks of its document start before
+/// it.
+fn chunk_ordinals(
+  state: &State
+) -> HashMap<&str, (&Chunk, usize)> {
+  let mut by_document: HashMap<
+    &str,
+    Vec<&Chunk>
+  > = HashMap::new();
+  for chunk in &state.chunks {
+    by_document
+      .entry(chunk.doc_id.as_str())
+      .or_default()
+      .push(chunk);
+  }
+  let mut ordinals = HashMap::new();
+  for mut chunks in
+    by_document.into_values()
+  {
+    chunks
+      .sort_by_key(|chunk| chunk.start);
+    for chunk in &chunks {
+      let ordinal = chunks
+        .partition_point(|other| {
+          other.start < chunk.start
+        });
+      ordinals.insert(
+        chunk.id.as_str(),
+        (*chunk, ordinal)
+      );
+    }
+  }
+  ordinals
+}
+
+/// Length of the shortest run of
+/// `tokens` containing every term of
+/// `terms`, if all occur.
+fn min_window(
+  tokens: &[String],
+  terms: &HashSet<String>
+) -> Option<usize> {
+  let mut counts: HashMap<&str, usize> =
+    HashMap::new();
+  let mut best: Option<usize> = None;
+  let mut start = 0;
+  for (end, token) in
+    tokens.iter().enumerate()
+  {
+    if !terms.contains(token) {
+      continue;
+    }
+    *counts
+      .entry(token.as_str())
+      .or_insert(0) += 1;
+    while counts.len() == terms.len() {
+      let first = &tokens[start];
+      if let Some(count) =
+        counts.get_mut(first.as_str())
+      {
+        let window = end - start + 1;
+        best = Some(
+          best.map_or(window, |best| {
+            best.min(window)
+          })
+        );
+        *count -= 1;
+        if *count == 0 {
+          counts.remove(first.as_str());
+        }
+      }
+      start += 1;
+    }
+  }
+  best
+}
+
+/// Drops candidates chunked with
+/// another strategy than `preferred`
+/// whose byte range overlaps a
+/// `preferred` candidate from the same
+/// document.
+pub(super) fn prefer_strategy(
+  matches: Vec<(usize, f32)>,
+  preferred: ChunkStrategy,
+  state: &State,
+  index: &VectorIndex
+) -> Vec<(usize, f32)> {
+  let entries = index.entries();
+  let chunks: Vec<Option<&Chunk>> =
+    matches
+      .iter()
+      .map(|(idx, _)| {
+        state.find_chunk(
+          &entries[*idx].chunk_id
+        )
+      })
+      .collect();
+  let winners: Vec<&Chunk> = chunks
+    .iter()
+    .flatten()
+    .filter(|chunk| {
+      chunk.strategy == preferred
+    })
+    .copied()
+    .collect();
+  matches
+    .into_iter()
+    .zip(chunks)
+    .filter(|(_, chunk)| {
+      chunk.is_none_or(|chunk| {
+        chunk.strategy == preferred
+          || !winners.iter().any(
+            |winner| {
+              winner.doc_id
+                == chunk.doc_id
+                && winner.start
+                  < chunk.end
+                && chunk.start
+                  < winner.end
+            }
+          )
+      })
+    })
+    .map(|(candidate, _)| candidate)
+    .collect()
+}
+
+/// Subtracts `weight` times each
+/// candidate's cosine to the `negative`
+/// query vector and re-sorts, so hits
+/// about the unwanted topic sink
+/// without being dropped. Runs after
+/// the score threshold, which keeps
+/// its meaning as a similarity floor.
+pub(super) fn penalize_negative(
+  mut candidates: Vec<(usize, f32)>,
+  negative: &SparseVector,
+  weight: f32,
+  index: &VectorIndex
+) -> Vec<(usize, f32)> {
+  let entries = index.entries();
+  for (idx, score) in &mut candidates {
+    *score -= weight
+      * cosine_similarity(
+        &entries[*idx].vector,
+        negative
+      );
+  }
+  candidates.sort_by(|a, b| {
+    b.1.total_cmp(&a.1)
+  });
+  candidates
+}
+
+/// Keeps ranked candidates in order,
+/// skipping any whose cosine to an
+/// already kept candidate exceeds
+/// `threshold`.
+pub(super) fn drop_near_duplicates(
+  candidates: Vec<(usize, f32)>,
+  index: &VectorIndex,
+  threshold: f32
+) -> Vec<(usize, f32)> {
+  let entries = index.entries();
+  let mut kept: Vec<(usize, f32)> =
+    Vec::with_capacity(
+      candidates.len()
+    );
+  for (idx, score) in candidates {
+    let duplicate =
+      kept.iter().any(|(chosen, _)| {
+        cosine_similarity(
+          &entries[idx].vector,
+          &entries[*chosen].vector
+        ) > threshold
+      });
+    if !duplicate {
+      kept.push((idx, score));
+    }
+  }
+  kept
+}
+
+/// Greedy Maximal Marginal Relevance:
+/// repeatedly picks the candidate
+/// maximizing `lambda * relevance -
+/// (1 - lambda) * max_similarity` to
+/// the chunks already selected.
+pub(super) fn mmr_select(
+  mut candidates: Vec<(usize, f32)>,
+  index: &VectorIndex,
+  lambda: f32,
+  top_k: usize
+) -> Vec<(usize, f32)> {
+  let entries = index.entries();
+  let mut selected: Vec<(usize, f32)> =
+    Vec::with_capacity(top_k);
+  while selected.len() < top_k
+    && !candidates.is_empty()
+  {
+    let marginal =
+      |&(idx, score): &(usize, f32)| {
+        let redundancy = selected
+          .iter()
+          .map(|(chosen, _)| {
+            cosine_similarity(
+              &entries[idx].vector,
+              &entries[*chosen].vector
+            )
+          })
+          .fold(0.0, f32::max);
+        lambda * score
+          - (1.0 - lambda) * redundancy
+      };
+    let best = candidates
+      .iter()
+      .enumerate()
+      .max_by(|(_, a), (_, b)| {
+        marginal(a)
+          .partial_cmp(&marginal(b))
+          .unwrap_or(Ordering::Equal)
+      })
+      .map(|(pos, _)| pos)
+      .unwrap_or(0);
+    selected
+      .push(candidates.remove(best));
+  }
+  selected
+}
+
+/// Moves candidates from pinned
+/// documents ahead of the rest,
+/// keeping the relative order within
+/// both groups. Only documents that
+/// already matched are affected.
+pub(super) fn pin_documents(
+  matches: Vec<(usize, f32)>,
+  index: &VectorIndex,
+  pinned: &[String]
+) -> Vec<(usize, f32)> {
+  let entries = index.entries();
+  let (mut ranked, rest): (
+    Vec<_>,
+    Vec<_>
+  ) = matches.into_iter().partition(
+    |(idx, _)| {
+      pinned
+        .contains(&entries[*idx].doc_id)
+    }
+  );
+  ranked.extend(rest);
+  ranked
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use crate::tokenizer::Tokenizer;
+
+/// Query tokens shorter than this are
+/// never corrected.
+const MIN_CORRECTED_LEN: usize = 4;
+
+/// Replaces query tokens missing from
+/// `vocabulary` with the closest known
+/// term, returning the corrected text
+/// and each `(typo, correction)`. Text
+/// without corrections is returned
+/// unchanged; otherwise it is rebuilt
+/// from its tokens.
+pub(super) fn correct_query(
+  text: &str,
+  tokenizer: &Tokenizer,
+  vocabulary: &BTreeMap<String, usize>
+) -> (String, Vec<(String, String)>) {
+  if vocabulary.is_empty() {
+    return (
+      text.to_string(),
+      Vec::new()
+    );
+  }
+  let mut corrections = Vec::new();
+  let tokens: Vec<String> = tokenizer
+    .tokenize(text)
+    .into_iter()
+    .map(|token| {
+      match closest_term(
+        &token, vocabulary
+      ) {
+        | Some(term) => {
+          corrections.push((
+            token,
+            term.clone()
+          ));
+          term
+        }
+        | None => token
+      }
+    })
+    .collect();
+  if corrections.is_empty() {
+    (text.to_string(), corrections)
+  } else {
+    (tokens.join(" "), corrections)
+  }
+}
+
+/// The known term nearest to an unknown
+/// `token`: within one edit for tokens
+/// of up to five characters and two
+/// otherwise, preferring the smallest
+/// distance, then the most frequent
+/// term. Known, short, and numeric
+/// tokens are kept.
+fn closest_term(
+  token: &str,
+  vocabulary: &BTreeMap<String, usize>
+) -> Option<String> {
+  let len = token.chars().count();
+  if len < MIN_CORRECTED_LEN
+    || vocabulary.contains_key(token)
+    || token
+      .chars()
+      .any(|c| c.is_numeric())
+  {
+    return None;
+  }
+  let max_edits = if len <= 5 {
+    1
+  } else {
+    2
+  };
+  vocabulary
+    .iter()
+    .filter_map(|(term, frequency)| {
+      let distance = edit_distance(
+        token, term, max_edits
+      )?;
+      Some((distance, *frequency, term))
+    })
+    .min_by(|a, b| {
+      a.0
+        .cmp(&b.0)
+        .then_with(|| b.1.cmp(&a.1))
+        .then_with(|| a.2.cmp(b.2))
+    })
+    .map(|(_, _, term)| term.clone())
+}
+
+/// Levenshtein distance between `a`
+/// and `b`, or `None` once it exceeds
+/// `max`.
+fn edit_distance(
+  a: &str,
+  b: &str,
+  max: usize
+) -> Option<usize> {
+  let a: Vec<char> =
+    a.chars().collect();
+  let b: Vec<char> =
+    b.chars().collect();
+  if a.len().abs_diff(b.len()) > max {
+    return None;
+  }
+  let mut previous: Vec<usize> =
+    (0..=b.len()).collect();
+  for (i, ca) in a.iter().enumerate() {
+    let mut current = vec![i + 1];
+    for (j, cb) in b.iter().enumerate()
+    {
+      let substitution = previous[j]
+        + usize::from(ca != cb);
+      current.push(
+        substitution
+          .min(previous[j + 1] + 1)
+          .min(current[j] + 1)
+      );
+    }
+    if current.iter().min()? > &max {
+      return None;
+    }
+    previous = current;
+  }
+  previous
+    .last()
+    .copied()
+    .filter(|distance| *distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn distant_and_short_tokens_are_kept()
+  {
+    let vocabulary = BTreeMap::from([
+      ("whale".to_string(), 3),
+      ("harpoon".to_string(), 1)
+    ]);
+    let tokenizer =
+      Tokenizer::default();
+    let (text, corrections) =
+      correct_query(
+        "harpon whael cat",
+        &tokenizer,
+        &vocabulary
+      );
+    assert_eq!(corrections, vec![(
+      "harpon".to_string(),
+      "harpoon".to_string()
+    )]);
+    assert_eq!(
+      text,
+      "harpoon whael cat"
+    );
+    assert_eq!(
+      edit_distance(
+        "kitten", "sitting", 3
+      ),
+      Some(3)
+    );
+    assert_eq!(
+      edit_distance(
+        "kitten", "sitting", 2
+      ),
+      None
+    );
+  }
+}
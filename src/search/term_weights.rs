@@ -0,0 +1,64 @@
+use crate::embedder::{
+  Embedder,
+  SparseVector
+};
+
+/// Separates a term from its weight in
+/// `term^weight`.
+const WEIGHT_MARKER: char = '^';
+
+/// Splits `term^weight` words out of
+/// `query`, returning the query with
+/// the weights stripped and each
+/// weighted term. Words whose weight
+/// does not parse as a non-negative
+/// number are kept verbatim.
+pub(super) fn parse_term_weights(
+  query: &str
+) -> (String, Vec<(String, f32)>) {
+  let mut words = Vec::new();
+  let mut weights = Vec::new();
+  for word in query.split_whitespace() {
+    let weighted = word
+      .rsplit_once(WEIGHT_MARKER)
+      .and_then(|(term, weight)| {
+        let weight: f32 =
+          weight.parse().ok()?;
+        (!term.is_empty()
+          && weight.is_finite()
+          && weight >= 0.0)
+          .then_some((term, weight))
+      });
+    match weighted {
+      | Some((term, weight)) => {
+        words.push(term);
+        weights.push((
+          term.to_string(),
+          weight
+        ));
+      }
+      | None => words.push(word)
+    }
+  }
+  (words.join(" "), weights)
+}
+
+/// Scales the components of `vector`
+/// produced by each weighted term.
+pub(super) fn apply_term_weights(
+  vector: &mut SparseVector,
+  weights: &[(String, f32)],
+  embedder: &dyn Embedder
+) {
+  for (term, weight) in weights {
+    for token in
+      embedder.embed_query(term).keys()
+    {
+      if let Some(value) =
+        vector.get_mut(token)
+      {
+        *value *= weight;
+      }
+    }
+  }
+}
@@ -0,0 +1,94 @@
+mod rerank;
+mod retrieval;
+
+use super::*;
+use crate::chunk::ChunkStrategy;
+use crate::config::{
+  EmbedderKind,
+  ThresholdMode
+};
+use crate::corpus_stats::CorpusStats;
+use crate::embedder::TfEmbedder;
+
+/// One document per text, one chunk
+/// per document.
+fn corpus(
+  texts: &[&str]
+) -> (State, VectorIndex) {
+  let embedder = TfEmbedder::new(1);
+  let mut state = State::default();
+  let mut index =
+    VectorIndex::from_entries(
+      Vec::new()
+    );
+  for (idx, text) in
+    texts.iter().enumerate()
+  {
+    let doc_id = format!("d{}", idx);
+    let chunk_id = format!("c{}", idx);
+    state.documents.push(Document {
+      id:          doc_id.clone(),
+      path:        format!(
+        "{}.txt",
+        doc_id
+      ),
+      hash:        doc_id.clone(),
+      token_count: embedder
+        .token_count(text),
+      tags:        Default::default(),
+      aliases:     Vec::new(),
+      normalized:  None
+    });
+    state.chunks.push(Chunk {
+      id:        chunk_id.clone(),
+      doc_id:    doc_id.clone(),
+      text:      text.to_string(),
+      start:     0,
+      end:       text.len(),
+      strategy:
+        ChunkStrategy::Structured,
+      language:  None,
+      raw_start: None,
+      raw_end:   None,
+      summary:   None,
+      heading:   None,
+      run_id:    0
+    });
+    index.add_chunk(
+      chunk_id,
+      doc_id,
+      embedder.embed(text),
+      None,
+      None
+    );
+  }
+  (state, index)
+}
+
+fn hit_ids(
+  hits: &[SearchHit]
+) -> Vec<&str> {
+  hits
+    .iter()
+    .map(|hit| hit.chunk.id.as_str())
+    .collect()
+}
+
+fn run_search(
+  query: &str,
+  top_k: usize,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex
+) -> Vec<SearchHit> {
+  search_hits(
+    &TfEmbedder::new(1),
+    query,
+    top_k,
+    &SearchFilter::default(),
+    config,
+    state,
+    index
+  )
+  .unwrap()
+}
@@ -0,0 +1,329 @@
+use super::*;
+
+#[test]
+fn mmr_prefers_diverse_chunks() {
+  let (state, index) = corpus(&[
+    "whale ship",
+    "whale ship ship",
+    "whale sea"
+  ]);
+  let mut config = Config::default();
+  let plain = run_search(
+    "whale ship",
+    2,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(hit_ids(&plain), vec![
+    "c0", "c1"
+  ]);
+  config.stage1.search.mmr_lambda =
+    Some(0.3);
+  let diverse = run_search(
+    "whale ship",
+    2,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(hit_ids(&diverse), vec![
+    "c0", "c2"
+  ]);
+}
+
+#[test]
+fn dedup_keeps_higher_scored_near_duplicate()
+ {
+  let (state, index) = corpus(&[
+    "whale ship harpoon sea",
+    "whale ship harpoon",
+    "whale desert"
+  ]);
+  let mut config = Config::default();
+  let plain = run_search(
+    "whale ship harpoon",
+    3,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(hit_ids(&plain), vec![
+    "c1", "c0", "c2"
+  ]);
+  config
+    .stage1
+    .search
+    .dedup_similarity = Some(0.8);
+  let deduped = run_search(
+    "whale ship harpoon",
+    3,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(hit_ids(&deduped), vec![
+    "c1", "c2"
+  ]);
+}
+
+#[test]
+fn negative_query_demotes_unwanted_topic()
+ {
+  let (state, index) = corpus(&[
+    "whale ship",
+    "whale sea harpoon",
+    "ship deck"
+  ]);
+  let config = Config::default();
+  let plain = run_search(
+    "whale", 2, &config, &state, &index
+  );
+  assert_eq!(hit_ids(&plain), vec![
+    "c0", "c1"
+  ]);
+  let steered = search_hits(
+    &TfEmbedder::new(1),
+    "whale",
+    2,
+    &SearchFilter {
+      negative: Some("ship".into()),
+      ..Default::default()
+    },
+    &config,
+    &state,
+    &index
+  )
+  .unwrap();
+  assert_eq!(hit_ids(&steered), vec![
+    "c1", "c0"
+  ]);
+  assert!(
+    steered[1].score < plain[0].score
+  );
+}
+
+#[test]
+fn near_identical_hits_share_a_cluster()
+{
+  let (state, index) = corpus(&[
+    "whale ship sea",
+    "Whale, ship, sea.",
+    "whale ship sea!",
+    "harpoon rope whale"
+  ]);
+  let hits = run_search(
+    "whale ship sea harpoon",
+    4,
+    &Config::default(),
+    &state,
+    &index
+  );
+  assert_eq!(hits.len(), 4);
+  let clusters =
+    cluster_hits(&hits, &index, 0.9);
+  let sizes: Vec<usize> = clusters
+    .iter()
+    .map(Vec::len)
+    .collect();
+  assert_eq!(sizes, vec![3, 1]);
+  assert_eq!(
+    hits[clusters[1][0]].chunk.id,
+    "c3"
+  );
+}
+
+#[test]
+fn length_penalty_demotes_fragments() {
+  let (state, index) = corpus(&[
+    "whale",
+    "whale whale whale"
+  ]);
+  let mut config = Config::default();
+  let plain = run_search(
+    "whale", 2, &config, &state, &index
+  );
+  assert_eq!(
+    plain[0].score,
+    plain[1].score
+  );
+  config.stage1.search.length_penalty =
+    0.5;
+  let penalized = run_search(
+    "whale", 2, &config, &state, &index
+  );
+  assert_eq!(
+    hit_ids(&penalized),
+    vec!["c1", "c0"]
+  );
+  assert!(
+    penalized[0].score
+      > penalized[1].score
+  );
+}
+
+#[test]
+fn position_boost_favors_earlier_chunks()
+ {
+  let (mut state, index) = corpus(&[
+    "whale song",
+    "whale song"
+  ]);
+  state.chunks[0].start = 20;
+  state.chunks[1].doc_id = "d0".into();
+  let mut config = Config::default();
+  config.stage1.search.position_boost =
+    0.5;
+  let boosted = run_search(
+    "whale", 2, &config, &state, &index
+  );
+  assert_eq!(hit_ids(&boosted), vec![
+    "c1", "c0"
+  ]);
+  assert!(
+    boosted[0].score > boosted[1].score
+  );
+}
+
+#[test]
+fn pinned_documents_rank_first() {
+  let (state, index) = corpus(&[
+    "whale whale whale",
+    "whale song under the sea today",
+    "ship harbor"
+  ]);
+  let mut config = Config::default();
+  config.stage1.search.pinned_doc_ids =
+    vec!["d1".into(), "d2".into()];
+  let hits = run_search(
+    "whale", 3, &config, &state, &index
+  );
+  assert_eq!(hit_ids(&hits), vec![
+    "c1", "c0"
+  ]);
+  assert!(
+    hits[0].score < hits[1].score
+  );
+}
+
+#[test]
+fn preferred_strategy_wins_overlapping_hits()
+ {
+  let (mut state, index) = corpus(&[
+    "whale ship harbor",
+    "whale ship",
+    "harbor",
+    "whale sand"
+  ]);
+  let mut entries =
+    index.into_entries();
+  for (idx, start, end) in
+    [(1, 0, 10), (2, 11, 17)]
+  {
+    let chunk = &mut state.chunks[idx];
+    chunk.doc_id = "d0".into();
+    chunk.strategy =
+      ChunkStrategy::Fixed;
+    chunk.start = start;
+    chunk.end = end;
+    entries[idx].doc_id = "d0".into();
+  }
+  state.chunks[3].strategy =
+    ChunkStrategy::Fixed;
+  let index =
+    VectorIndex::from_entries(entries);
+  let mut config = Config::default();
+  let mixed = run_search(
+    "whale ship harbor",
+    4,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(mixed.len(), 4);
+  config
+    .stage1
+    .search
+    .prefer_strategy =
+    Some(ChunkStrategy::Structured);
+  let structured = run_search(
+    "whale ship harbor",
+    4,
+    &config,
+    &state,
+    &index
+  );
+  let mut structured =
+    hit_ids(&structured);
+  structured.sort();
+  assert_eq!(structured, vec![
+    "c0", "c3"
+  ]);
+  config
+    .stage1
+    .search
+    .prefer_strategy =
+    Some(ChunkStrategy::Fixed);
+  let fixed = run_search(
+    "whale ship harbor",
+    4,
+    &config,
+    &state,
+    &index
+  );
+  assert!(
+    !hit_ids(&fixed).contains(&"c0")
+  );
+  assert_eq!(fixed.len(), 3);
+}
+
+#[test]
+fn blend_ranks_compromise_chunk_first()
+{
+  let (state, index) = corpus(&[
+    "whale whale harbor harbor",
+    "whale whale ship ship ship",
+    "whale whale whale ship ship ship \
+     harbor harbor"
+  ]);
+  let mut config = Config::default();
+  config
+    .stage1
+    .embedder
+    .tfidf_min_freq = 2;
+  let mut ranking = |blend| {
+    config.stage1.search.blend = blend;
+    let hits = run_search(
+      "whale whale ship",
+      3,
+      &config,
+      &state,
+      &index
+    );
+    hit_ids(&hits)
+      .into_iter()
+      .map(String::from)
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(
+    ranking(vec![(
+      EmbedderKind::Tf,
+      1.0
+    )]),
+    ["c0", "c2", "c1"]
+  );
+  assert_eq!(
+    ranking(vec![(
+      EmbedderKind::BagOfWords,
+      1.0
+    )]),
+    ["c1", "c2", "c0"]
+  );
+  assert_eq!(
+    ranking(vec![
+      (EmbedderKind::Tf, 0.5),
+      (EmbedderKind::BagOfWords, 0.5)
+    ]),
+    ["c2", "c1", "c0"]
+  );
+}
@@ -0,0 +1,373 @@
+use super::*;
+use crate::search::diagnose::Verdict;
+use crate::search::grep::GREP_FALLBACK_SCORE;
+
+#[test]
+fn two_stage_searches_top_documents_only()
+ {
+  let (mut state, index) = corpus(&[
+    "whale whale ship",
+    "whale sea",
+    "ship harbor",
+    "harbor town",
+    "desert sand"
+  ]);
+  let mut entries =
+    index.into_entries();
+  for (idx, doc_id) in
+    ["d0", "d0", "d1", "d1", "d2"]
+      .iter()
+      .enumerate()
+  {
+    state.chunks[idx].doc_id =
+      doc_id.to_string();
+    entries[idx].doc_id =
+      doc_id.to_string();
+  }
+  let index =
+    VectorIndex::from_entries(entries);
+  let mut config = Config::default();
+  let single = run_search(
+    "whale", 3, &config, &state, &index
+  );
+  config.stage1.search.two_stage = true;
+  let staged = run_search(
+    "whale", 3, &config, &state, &index
+  );
+  assert_eq!(
+    hit_ids(&staged),
+    hit_ids(&single)
+  );
+  let top = index.top_documents(
+    &TfEmbedder::new(1).embed("harbor"),
+    1,
+    false,
+    |_| true
+  );
+  assert_eq!(top, vec!["d1"]);
+  let harbor = run_search(
+    "ship harbor",
+    1,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(hit_ids(&harbor), vec![
+    "c2"
+  ]);
+  assert!(harbor.iter().all(|hit| {
+    hit.chunk.doc_id == "d1"
+  }));
+}
+
+#[test]
+fn percentile_threshold_keeps_same_share_per_query()
+ {
+  let (state, index) = corpus(&[
+    "whale",
+    "whale alpha",
+    "whale alpha beta",
+    "whale alpha beta gamma",
+    "ship ballast ballast ballast",
+    "ship ballast ballast ballast \
+     ballast",
+    "ship ballast ballast ballast \
+     ballast ballast",
+    "ship ballast ballast ballast \
+     ballast ballast ballast"
+  ]);
+  let mut config = Config::default();
+  config
+    .stage1
+    .search
+    .score_threshold = 0.4;
+  let absolute = |query| {
+    run_search(
+      query, 4, &config, &state, &index
+    )
+    .len()
+  };
+  assert_eq!(absolute("whale"), 4);
+  assert_eq!(absolute("ship"), 0);
+  config.stage1.search.threshold_mode =
+    ThresholdMode::Percentile;
+  config
+    .stage1
+    .search
+    .score_threshold = 0.5;
+  let whale = run_search(
+    "whale", 4, &config, &state, &index
+  );
+  let ship = run_search(
+    "ship", 4, &config, &state, &index
+  );
+  assert_eq!(hit_ids(&whale), vec![
+    "c0", "c1"
+  ]);
+  assert_eq!(hit_ids(&ship), vec![
+    "c4", "c5"
+  ]);
+  assert!(ship[0].score < 0.4);
+}
+
+#[test]
+fn similar_applies_percentiles_to_overfetched_candidates()
+ {
+  let (state, index) = corpus(&[
+    "whale ship sea",
+    "whale ship sea harpoon",
+    "whale ship",
+    "whale",
+    "sea cactus dune"
+  ]);
+  let mut config = Config::default();
+  config.stage1.search.threshold_mode =
+    ThresholdMode::Percentile;
+  config
+    .stage1
+    .search
+    .score_threshold = 0.5;
+  let hits = similar_hits(
+    "c0", 1, &config, &state, &index
+  )
+  .unwrap();
+  assert_eq!(hit_ids(&hits), vec![
+    "c1"
+  ]);
+}
+
+#[test]
+fn term_weights_shift_the_top_hit() {
+  let (state, index) = corpus(&[
+    "rust rust rust async",
+    "rust async async async"
+  ]);
+  let mut config = Config::default();
+  config
+    .stage1
+    .search
+    .parse_term_weights = true;
+  let top = |query| {
+    run_search(
+      query, 2, &config, &state, &index
+    )[0]
+      .chunk
+      .id
+      .clone()
+  };
+  assert_eq!(top("rust^3 async"), "c0");
+  assert_eq!(top("rust async^3"), "c1");
+  assert_eq!(
+    parse_term_weights(
+      "rust^2 c^x a^-1"
+    ),
+    (
+      "rust c^x a^-1".to_string(),
+      vec![("rust".to_string(), 2.0)]
+    )
+  );
+}
+
+#[test]
+fn grep_fallback_finds_exact_identifiers()
+ {
+  let (state, index) = corpus(&[
+    "whale ship",
+    "error code x7f3 raised"
+  ]);
+  let mut config = Config::default();
+  // Every query term occurs once, below
+  // the embedder's minimum frequency.
+  let search = |config: &Config| {
+    search_hits(
+      &TfEmbedder::new(2),
+      "X7F3",
+      3,
+      &SearchFilter::default(),
+      config,
+      &state,
+      &index
+    )
+    .unwrap()
+  };
+  assert!(search(&config).is_empty());
+  config.stage1.search.grep_fallback =
+    true;
+  let hits = search(&config);
+  assert_eq!(hit_ids(&hits), vec![
+    "c1"
+  ]);
+  assert_eq!(
+    hits[0].score,
+    GREP_FALLBACK_SCORE
+  );
+  config.stage1.search.min_doc_tokens =
+    5;
+  assert!(search(&config).is_empty());
+}
+
+#[test]
+fn diagnose_reports_missing_overlap() {
+  let (state, index) = corpus(&[
+    "whale ship sea",
+    "sand dunes heat"
+  ]);
+  let config = Config::default();
+  let diagnosis = diagnose(
+    &TfEmbedder::new(1),
+    "whale harpoon",
+    "c1",
+    5,
+    &config,
+    &state,
+    &index
+  )
+  .unwrap();
+  assert_eq!(
+    diagnosis.verdict,
+    Verdict::NoSharedTerms
+  );
+  assert_eq!(diagnosis.cosine, 0.0);
+  assert!(!diagnosis.passes_threshold);
+  assert_eq!(
+    diagnosis.candidate_rank,
+    None
+  );
+  assert_eq!(
+    diagnosis.missing_terms,
+    vec!["harpoon", "whale"]
+  );
+  let found = diagnose(
+    &TfEmbedder::new(1),
+    "whale harpoon",
+    "c0",
+    5,
+    &config,
+    &state,
+    &index
+  )
+  .unwrap();
+  assert_eq!(
+    found.verdict,
+    Verdict::Returned
+  );
+  assert_eq!(found.hit_rank, Some(1));
+  assert_eq!(
+    found.missing_terms,
+    vec!["harpoon"]
+  );
+}
+
+#[test]
+fn proximity_boost_prefers_clustered_terms()
+ {
+  let (state, index) = corpus(&[
+    "whale sea sand dunes heat harpoon",
+    "sea sand whale harpoon dunes heat"
+  ]);
+  let mut config = Config::default();
+  let plain = run_search(
+    "whale harpoon",
+    2,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(
+    plain[0].score,
+    plain[1].score
+  );
+  assert_eq!(hit_ids(&plain), vec![
+    "c0", "c1"
+  ]);
+  config
+    .stage1
+    .search
+    .proximity_boost = 0.5;
+  let boosted = run_search(
+    "whale harpoon",
+    2,
+    &config,
+    &state,
+    &index
+  );
+  assert_eq!(hit_ids(&boosted), vec![
+    "c1", "c0"
+  ]);
+  assert!(
+    boosted[0].score > boosted[1].score
+  );
+}
+
+#[test]
+fn spell_correction_retrieves_intended_chunk()
+ {
+  let (mut state, index) = corpus(&[
+    "whale ship sea",
+    "sand dunes heat"
+  ]);
+  state.stats = CorpusStats::recompute(
+    &state.documents,
+    &state.chunks,
+    &Tokenizer::default()
+  );
+  let mut config = Config::default();
+  assert!(
+    run_search(
+      "dunnes", 2, &config, &state,
+      &index
+    )
+    .is_empty()
+  );
+  config.stage1.search.spell_correct =
+    true;
+  let (hits, trace) = trace_search(
+    &TfEmbedder::new(1),
+    "dunnes",
+    2,
+    &SearchFilter::default(),
+    &config,
+    &state,
+    &index
+  )
+  .unwrap();
+  assert_eq!(hit_ids(&hits), vec![
+    "c1"
+  ]);
+  assert_eq!(trace.corrections, vec![
+    (
+      "dunnes".to_string(),
+      "dunes".to_string()
+    )
+  ]);
+}
+
+#[test]
+fn short_documents_are_dropped() {
+  let (state, index) = corpus(&[
+    "whale",
+    "whale ship harpoon",
+    "whale sea voyage"
+  ]);
+  let mut config = Config::default();
+  assert_eq!(
+    run_search(
+      "whale", 3, &config, &state,
+      &index
+    )
+    .len(),
+    3
+  );
+  config.stage1.search.min_doc_tokens =
+    2;
+  let mut ids: Vec<String> =
+    run_search(
+      "whale", 3, &config, &state,
+      &index
+    )
+    .iter()
+    .map(|hit| hit.chunk.id.clone())
+    .collect();
+  ids.sort();
+  assert_eq!(ids, vec!["c1", "c2"]);
+}
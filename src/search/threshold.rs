@@ -0,0 +1,51 @@
+use crate::config::{
+  Config,
+  ThresholdMode
+};
+
+/// Applies `score_threshold`: as a
+/// score floor in absolute mode, or in
+/// percentile mode as the share `p` of
+/// `matches` to cut, keeping the scores
+/// above the nearest-rank `p`-th
+/// percentile of this query's
+/// candidates.
+pub(super) fn above_threshold(
+  mut matches: Vec<(usize, f32)>,
+  config: &Config
+) -> Vec<(usize, f32)> {
+  let search_cfg =
+    &config.stage1.search;
+  let threshold =
+    search_cfg.score_threshold;
+  match search_cfg.threshold_mode {
+    | ThresholdMode::Absolute => {
+      matches.retain(|(_, score)| {
+        *score >= threshold
+      });
+    }
+    | ThresholdMode::Percentile => {
+      if threshold <= 0.0
+        || matches.is_empty()
+      {
+        return matches;
+      }
+      let mut scores: Vec<f32> =
+        matches
+          .iter()
+          .map(|(_, score)| *score)
+          .collect();
+      scores.sort_by(f32::total_cmp);
+      let rank = (threshold.min(1.0)
+        * scores.len() as f32)
+        .ceil()
+        as usize;
+      let cutoff =
+        scores[rank.max(1) - 1];
+      matches.retain(|(_, score)| {
+        *score > cutoff
+      });
+    }
+  }
+  matches
+}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+
+use crate::embedder::SparseVector;
+use crate::index::VectorIndex;
+
+/// Intermediate scoring data of one
+/// query, for debugging rankings.
+#[derive(Debug, Default, Serialize)]
+pub struct SearchTrace {
+  pub query:            String,
+  pub normalized_query: String,
+  /// Misspelled query terms and their
+  /// corrections
+  #[serde(
+    skip_serializing_if = "Vec::is_empty"
+  )]
+  pub corrections:
+    Vec<(String, String)>,
+  pub query_vector:     SparseVector,
+  /// Raw cosine scores of the fetched
+  /// candidates, before adjustments
+  /// and the threshold
+  pub candidates: Vec<TracedScore>,
+  pub score_threshold:  f32,
+  pub hits: Vec<TracedScore>
+}
+
+#[derive(Debug, Serialize)]
+pub struct TracedScore {
+  pub chunk_id: String,
+  pub doc_id:   String,
+  pub score:    f32
+}
+
+impl TracedScore {
+  pub(super) fn from_matches(
+    matches: &[(usize, f32)],
+    index: &VectorIndex
+  ) -> Vec<Self> {
+    matches
+      .iter()
+      .map(|(idx, score)| {
+        let entry =
+          &index.entries()[*idx];
+        Self {
+          chunk_id: entry
+            .chunk_id
+            .clone(),
+          doc_id:   entry
+            .doc_id
+            .clone(),
+          score:    *score
+        }
+      })
+      .collect()
+  }
+}
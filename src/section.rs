@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::chunk::Chunk;
+use crate::normalization::{
+  code_blocks,
+  is_markdown
+};
+use crate::search::SearchHit;
+use crate::state::State;
+
+/// Deepest ATX heading level.
+const MAX_HEADING_LEVEL: usize = 6;
+
+/// Tags each chunk of a Markdown file
+/// with the last heading at or before
+/// its start in `text`. Lines inside
+/// fenced code blocks are not headings,
+/// and other files are left untagged.
+pub fn assign_headings(
+  chunks: &mut [Chunk],
+  text: &str,
+  path: &Path
+) {
+  if !is_markdown(path) {
+    return;
+  }
+  let headings = headings(text);
+  for chunk in chunks {
+    chunk.heading = headings
+      .iter()
+      .take_while(|(start, _)| {
+        *start <= chunk.start
+      })
+      .last()
+      .map(|(_, title)| title.clone());
+  }
+}
+
+/// Replaces each hit's chunk text with
+/// its whole section: the span of the
+/// chunks of its document sharing its
+/// heading, read from the document's
+/// stored normalized text or else
+/// merged from the chunks. Hits without
+/// a heading are kept as they are;
+/// later hits in an already returned
+/// section are dropped.
+pub fn expand_sections(
+  hits: Vec<SearchHit>,
+  state: &State
+) -> Vec<SearchHit> {
+  let mut returned = HashSet::new();
+  hits
+    .into_iter()
+    .filter_map(|mut hit| {
+      let Some(heading) =
+        hit.chunk.heading.clone()
+      else {
+        return Some(hit);
+      };
+      if !returned.insert((
+        hit.chunk.doc_id.clone(),
+        heading.clone()
+      )) {
+        return None;
+      }
+      let mut section: Vec<&Chunk> =
+        state
+          .chunks
+          .iter()
+          .filter(|chunk| {
+            chunk.doc_id
+              == hit.chunk.doc_id
+              && chunk.heading.as_ref()
+                == Some(&heading)
+          })
+          .collect();
+      if section.len() < 2 {
+        return Some(hit);
+      }
+      section.sort_by_key(|chunk| {
+        chunk.start
+      });
+      let first = section[0];
+      hit.chunk.start = first.start;
+      hit.chunk.raw_start =
+        first.raw_start;
+      hit.chunk.end = section
+        .iter()
+        .map(|chunk| chunk.end)
+        .max()
+        .unwrap_or(first.end);
+      hit.chunk.raw_end = section
+        .iter()
+        .filter_map(|chunk| {
+          chunk.raw_end
+        })
+        .max();
+      hit.chunk.text = hit
+        .document
+        .normalized
+        .as_deref()
+        .and_then(|text| {
+          text.get(
+            hit.chunk.start
+              ..hit.chunk.end
+          )
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+          merge_chunks(&section)
+        });
+      Some(hit)
+    })
+    .collect()
+}
+
+/// Concatenates `chunks` (sorted by
+/// `start`), skipping the parts of
+/// overlapping chunks already emitted.
+pub fn merge_chunks(
+  chunks: &[&Chunk]
+) -> String {
+  let mut text = String::new();
+  let mut covered = 0;
+  for chunk in chunks {
+    if chunk.start >= covered {
+      if !text.is_empty() {
+        text.push_str("\n\n");
+      }
+      text.push_str(&chunk.text);
+    } else if let Some(tail) = chunk
+      .text
+      .get(covered - chunk.start..)
+    {
+      text.push_str(tail);
+    }
+    covered = covered.max(chunk.end);
+  }
+  text
+}
+
+/// Start offsets and titles of the ATX
+/// headings in `text`.
+fn headings(
+  text: &str
+) -> Vec<(usize, String)> {
+  let fenced = code_blocks(text);
+  let mut headings = Vec::new();
+  let mut offset = 0;
+  for line in text.split_inclusive('\n')
+  {
+    let start = offset;
+    offset += line.len();
+    if fenced.iter().any(|block| {
+      block.contains(&start)
+    }) {
+      continue;
+    }
+    let line = line.trim();
+    let level = line.len()
+      - line
+        .trim_start_matches('#')
+        .len();
+    let rest = &line[level..];
+    let title = rest.trim();
+    if (1..=MAX_HEADING_LEVEL)
+      .contains(&level)
+      && rest.starts_with(' ')
+      && !title.is_empty()
+    {
+      headings.push((
+        start,
+        title.to_string()
+      ));
+    }
+  }
+  headings
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn headings_skip_fenced_comments() {
+    let text = [
+      "# Intro",
+      "hello",
+      "```sh",
+      "# not a heading",
+      "```",
+      "## Usage",
+      "#hashtag"
+    ]
+    .join("\n");
+    assert_eq!(headings(&text), vec![
+      (0, "Intro".to_string()),
+      (40, "Usage".to_string())
+    ]);
+  }
+}
@@ -0,0 +1,185 @@
+mod prompt;
+mod rerank;
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use self::prompt::{
+  build_context,
+  fit_prompt,
+  validate_template
+};
+use self::rerank::{
+  RerankedHit,
+  fuse_rankings,
+  rerank_hits
+};
+use crate::config::{
+  Config,
+  Stage3Ensemble
+};
+use crate::embedder::Embedder;
+use crate::index::VectorIndex;
+use crate::search::{
+  SearchFilter,
+  SearchHit,
+  log_query,
+  search_hits
+};
+use crate::state::State;
+
+/// Reranked hits and the assembled
+/// prompt for one strategy (or the
+/// fused ensemble). Hit scores are the
+/// reranked scores.
+#[derive(Debug)]
+pub struct RagRun {
+  pub strategy: String,
+  pub hits:     Vec<SearchHit>,
+  /// Hit text packed into the context
+  /// budget, before any prompt
+  /// trimming
+  pub context:  Option<String>,
+  pub prompt:   Option<String>
+}
+
+pub fn run_stage3(
+  query: &str,
+  top_k: usize,
+  filter: &SearchFilter,
+  config: &Config,
+  state: &State,
+  index: &VectorIndex,
+  embedder: &dyn Embedder
+) -> Result<Vec<RagRun>> {
+  if !config.stage3.enabled {
+    println!(
+      "Stage 3 is disabled in config."
+    );
+    return Ok(Vec::new());
+  }
+  validate_template(
+    &config.stage3.prompt_template,
+    config.stage3.strict_template
+  )?;
+  let hits = search_hits(
+    embedder, query, top_k, filter,
+    config, state, index
+  )?;
+  log_query(
+    query, top_k, &hits, config
+  );
+  if hits.is_empty() {
+    println!(
+      "No hits found for query."
+    );
+    return Ok(Vec::new());
+  }
+  let deduped = dedupe_hits(hits);
+  let reranker =
+    &config.stage3.reranker;
+  if reranker.ensemble
+    != Stage3Ensemble::None
+  {
+    let rankings: Vec<_> = reranker
+      .strategies
+      .iter()
+      .map(|strategy| {
+        (
+          strategy.weight,
+          rerank_hits(
+            &deduped, strategy
+          )
+        )
+      })
+      .collect();
+    let fused = fuse_rankings(
+      rankings,
+      reranker.ensemble,
+      reranker.rrf_k
+    );
+    let run = assemble_run(
+      format!(
+        "ensemble ({:?})",
+        reranker.ensemble
+      ),
+      &fused,
+      query,
+      config
+    )?;
+    return Ok(vec![run]);
+  }
+  reranker
+    .strategies
+    .iter()
+    .map(|strategy| {
+      let reranked =
+        rerank_hits(&deduped, strategy);
+      assemble_run(
+        strategy.name.clone(),
+        &reranked,
+        query,
+        config
+      )
+    })
+    .collect()
+}
+
+fn assemble_run(
+  strategy: String,
+  reranked: &[RerankedHit],
+  query: &str,
+  config: &Config
+) -> Result<RagRun> {
+  let hits = reranked
+    .iter()
+    .map(|entry| {
+      SearchHit {
+        score: entry.score,
+        ..(*entry.hit).clone()
+      }
+    })
+    .collect();
+  if reranked.is_empty() {
+    return Ok(RagRun {
+      strategy,
+      hits,
+      context: None,
+      prompt: None
+    });
+  }
+  let context = build_context(
+    reranked,
+    config.stage3.context_budget
+  );
+  let prompt = fit_prompt(
+    &config.stage3.prompt_template,
+    query,
+    &context,
+    config.stage3.max_prompt_chars
+  )?;
+  Ok(RagRun {
+    strategy,
+    hits,
+    context: Some(context),
+    prompt: Some(prompt)
+  })
+}
+
+fn dedupe_hits(
+  hits: Vec<SearchHit>
+) -> Vec<SearchHit> {
+  let mut seen = HashSet::new();
+  hits
+    .into_iter()
+    .filter(|hit| {
+      let fingerprint =
+        hit.chunk.text.to_lowercase();
+      seen.insert(fingerprint)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,221 @@
+use anyhow::Result;
+
+use super::rerank::RerankedHit;
+
+pub(super) fn build_context(
+  hits: &[RerankedHit],
+  budget: usize
+) -> String {
+  let mut context = String::new();
+  for entry in hits {
+    if context.len() >= budget {
+      break;
+    }
+    let addition =
+      entry.hit.chunk.text.trim();
+    if addition.is_empty() {
+      continue;
+    }
+    if !context.is_empty() {
+      context.push_str("\n---\n");
+    }
+    context.push_str(&truncate(
+      addition,
+      budget - context.len()
+    ));
+  }
+  context
+}
+
+fn truncate(
+  text: &str,
+  max: usize
+) -> String {
+  if max == 0 {
+    return String::new();
+  }
+  text.chars().take(max).collect()
+}
+
+const TEMPLATE_PLACEHOLDERS: [&str; 2] =
+  ["{query}", "{context}"];
+
+pub(super) fn validate_template(
+  template: &str,
+  strict: bool
+) -> Result<Vec<&'static str>> {
+  let missing: Vec<_> =
+    TEMPLATE_PLACEHOLDERS
+      .into_iter()
+      .filter(|placeholder| {
+        placeholder_count(
+          template,
+          placeholder
+        ) == 0
+      })
+      .collect();
+  if missing.is_empty() {
+    return Ok(missing);
+  }
+  if strict {
+    anyhow::bail!(
+      "prompt template is missing {}",
+      missing.join(", ")
+    );
+  }
+  for placeholder in &missing {
+    eprintln!(
+      "Warning: prompt template is \
+       missing {}",
+      placeholder
+    );
+  }
+  Ok(missing)
+}
+
+/// Copies `template` into a string,
+/// turning `{{`/`}}` into literal
+/// braces and handing each known
+/// placeholder to `fill`. Unknown
+/// `{...}` text is kept as is.
+fn expand_template(
+  template: &str,
+  mut fill: impl FnMut(
+    &'static str,
+    &mut String
+  )
+) -> String {
+  let mut out = String::with_capacity(
+    template.len()
+  );
+  let mut rest = template;
+  while let Some(pos) =
+    rest.find(['{', '}'])
+  {
+    out.push_str(&rest[..pos]);
+    rest = &rest[pos..];
+    if let Some(placeholder) =
+      TEMPLATE_PLACEHOLDERS
+        .into_iter()
+        .find(|placeholder| {
+          rest.starts_with(placeholder)
+        })
+    {
+      fill(placeholder, &mut out);
+      rest = &rest[placeholder.len()..];
+    } else if rest.starts_with("{{")
+      || rest.starts_with("}}")
+    {
+      out.push_str(&rest[..1]);
+      rest = &rest[2..];
+    } else {
+      out.push_str(&rest[..1]);
+      rest = &rest[1..];
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+fn placeholder_count(
+  template: &str,
+  placeholder: &str
+) -> usize {
+  let mut count = 0;
+  expand_template(
+    template,
+    |found, _| {
+      if found == placeholder {
+        count += 1;
+      }
+    }
+  );
+  count
+}
+
+fn format_prompt(
+  template: &str,
+  query: &str,
+  context: &str
+) -> String {
+  expand_template(
+    template,
+    |placeholder, out| {
+      out.push_str(
+        if placeholder == "{query}" {
+          query
+        } else {
+          context
+        }
+      )
+    }
+  )
+}
+
+const CONTEXT_SEPARATOR: &str =
+  "\n---\n";
+
+pub(super) fn fit_prompt(
+  template: &str,
+  query: &str,
+  context: &str,
+  max_chars: Option<usize>
+) -> Result<String> {
+  let prompt = format_prompt(
+    template, query, context
+  );
+  let Some(max_chars) = max_chars
+  else {
+    return Ok(prompt);
+  };
+  if prompt.chars().count() <= max_chars
+  {
+    return Ok(prompt);
+  }
+  let overhead =
+    format_prompt(template, query, "")
+      .chars()
+      .count();
+  if overhead > max_chars {
+    anyhow::bail!(
+      "prompt needs {} chars without \
+       context, over the {} char cap",
+      overhead,
+      max_chars
+    );
+  }
+  let occurrences = placeholder_count(
+    template,
+    "{context}"
+  )
+  .max(1);
+  let available = (max_chars
+    - overhead)
+    / occurrences;
+  let mut segments: Vec<&str> = context
+    .split(CONTEXT_SEPARATOR)
+    .collect();
+  let mut trimmed =
+    segments.join(CONTEXT_SEPARATOR);
+  while trimmed.chars().count()
+    > available
+    && segments.len() > 1
+  {
+    segments.pop();
+    trimmed =
+      segments.join(CONTEXT_SEPARATOR);
+  }
+  let trimmed =
+    truncate(&trimmed, available);
+  eprintln!(
+    "Warning: prompt exceeded {} \
+     chars; trimmed context from {} \
+     to {} chars",
+    max_chars,
+    context.chars().count(),
+    trimmed.chars().count()
+  );
+  Ok(format_prompt(
+    template, query, &trimmed
+  ))
+}
@@ -0,0 +1,143 @@
+use std::cmp::Ordering;
+
+use crate::config::{
+  Stage3Ensemble,
+  Stage3RerankMode,
+  Stage3RerankerStrategyConfig
+};
+use crate::search::SearchHit;
+
+pub(super) struct RerankedHit<'a> {
+  pub(super) hit:   &'a SearchHit,
+  pub(super) score: f32
+}
+
+/// Combines per-strategy rankings into
+/// one list, either by summing each
+/// strategy's weighted scores or by
+/// reciprocal-rank fusion (`weight /
+/// (rrf_k + rank)`).
+pub(super) fn fuse_rankings<'a>(
+  rankings: Vec<(
+    f32,
+    Vec<RerankedHit<'a>>
+  )>,
+  ensemble: Stage3Ensemble,
+  rrf_k: f32
+) -> Vec<RerankedHit<'a>> {
+  let mut fused: Vec<RerankedHit<'a>> =
+    Vec::new();
+  for (weight, ranking) in rankings {
+    for (rank, entry) in
+      ranking.into_iter().enumerate()
+    {
+      let contribution = match ensemble {
+        | Stage3Ensemble::None
+        | Stage3Ensemble::WeightedSum => {
+          weight * entry.score
+        }
+        | Stage3Ensemble::Rrf => {
+          weight
+            / (rrf_k + (rank + 1) as f32)
+        }
+      };
+      match fused.iter_mut().find(
+        |existing| {
+          existing.hit.chunk.id
+            == entry.hit.chunk.id
+        }
+      ) {
+        | Some(existing) => {
+          existing.score += contribution
+        }
+        | None => {
+          fused.push(RerankedHit {
+            hit:   entry.hit,
+            score: contribution
+          })
+        }
+      }
+    }
+  }
+  fused.sort_by(|a, b| {
+    b.score
+      .partial_cmp(&a.score)
+      .unwrap_or(Ordering::Equal)
+  });
+  fused
+}
+
+/// Scores `hits` with the strategy's
+/// boosts, best first. With a
+/// `boost_rank_cutoff`, hits ranked
+/// below it by base score keep a zero
+/// boost.
+pub(super) fn rerank_hits<'a>(
+  hits: &'a [SearchHit],
+  strategy: &Stage3RerankerStrategyConfig
+) -> Vec<RerankedHit<'a>> {
+  let lower_boost: Vec<String> =
+    strategy
+      .boost_terms
+      .iter()
+      .map(|term| term.to_lowercase())
+      .collect();
+  let mut by_base: Vec<usize> =
+    (0..hits.len()).collect();
+  by_base.sort_by(|a, b| {
+    hits[*b]
+      .score
+      .total_cmp(&hits[*a].score)
+  });
+  let mut boostable =
+    vec![false; hits.len()];
+  for idx in by_base.into_iter().take(
+    strategy
+      .boost_rank_cutoff
+      .unwrap_or(usize::MAX)
+  ) {
+    boostable[idx] = true;
+  }
+  let mut scored = Vec::new();
+  for (hit, boostable) in
+    hits.iter().zip(boostable)
+  {
+    let base = hit.score;
+    let term_score = lower_boost
+      .iter()
+      .filter(|term| {
+        hit
+          .chunk
+          .text
+          .to_lowercase()
+          .contains(term.as_str())
+      })
+      .count()
+      as f32;
+    let boost = if boostable {
+      term_score * strategy.boost_factor
+    } else {
+      0.0
+    };
+    let total = match strategy.mode {
+      Stage3RerankMode::None => base,
+      Stage3RerankMode::TermOverlap => base + boost,
+      Stage3RerankMode::Hybrid => {
+        base * (1.0 - strategy.hybrid_weight)
+          + boost * strategy.hybrid_weight
+      }
+    };
+    if total >= strategy.threshold {
+      scored.push(RerankedHit {
+        hit,
+        score: total
+      });
+    }
+  }
+  scored.sort_by(|a, b| {
+    b.score
+      .partial_cmp(&a.score)
+      .unwrap_or(Ordering::Equal)
+  });
+  scored
+}
@@ -0,0 +1,235 @@
+use super::*;
+use crate::chunk::{
+  Chunk,
+  ChunkStrategy
+};
+use crate::config::{
+  Stage3RerankMode,
+  Stage3RerankerStrategyConfig
+};
+use crate::search::SearchHit;
+use crate::state::Document;
+
+fn search_hit(
+  id: &str,
+  text: &str,
+  score: f32
+) -> SearchHit {
+  let chunk = Chunk {
+    id:        id.into(),
+    doc_id:    "d".into(),
+    text:      text.into(),
+    start:     0,
+    end:       0,
+    strategy:
+      ChunkStrategy::Structured,
+    language:  None,
+    raw_start: None,
+    raw_end:   None,
+    summary:   None,
+    heading:   None,
+    run_id:    0
+  };
+  let document = Document {
+    id:          "d".into(),
+    path:        "doc".into(),
+    hash:        "h".into(),
+    token_count: 0,
+    tags:        Default::default(),
+    aliases:     Vec::new(),
+    normalized:  None
+  };
+  SearchHit {
+    chunk,
+    document,
+    score
+  }
+}
+
+fn ranking<'a>(
+  hits: &'a [SearchHit],
+  order: &[usize]
+) -> Vec<RerankedHit<'a>> {
+  order
+    .iter()
+    .map(|&idx| {
+      RerankedHit {
+        hit:   &hits[idx],
+        score: hits[idx].score
+      }
+    })
+    .collect()
+}
+
+#[test]
+fn rrf_orders_by_combined_reciprocal_rank()
+ {
+  let hits = vec![
+    search_hit("a", "alpha", 0.9),
+    search_hit("b", "beta", 0.8),
+    search_hit("c", "gamma", 0.7),
+  ];
+  let fused = fuse_rankings(
+    vec![
+      (1.0, ranking(&hits, &[0, 1, 2])),
+      (1.0, ranking(&hits, &[1, 2, 0])),
+    ],
+    Stage3Ensemble::Rrf,
+    60.0
+  );
+  let ids: Vec<_> = fused
+    .iter()
+    .map(|entry| {
+      entry.hit.chunk.id.as_str()
+    })
+    .collect();
+  assert_eq!(ids, vec!["b", "a", "c"]);
+  let expected_b =
+    1.0 / 62.0 + 1.0 / 61.0;
+  assert!(
+    (fused[0].score - expected_b).abs()
+      < 1e-6
+  );
+}
+
+#[test]
+fn build_context_respects_budget() {
+  let hit =
+    search_hit("c", "alpha beta", 1.0);
+  let hits = vec![RerankedHit {
+    hit:   &hit,
+    score: 1.0
+  }];
+  assert_eq!(
+    build_context(&hits, 3),
+    "alp"
+  );
+}
+
+#[test]
+fn validate_template_flags_missing_context()
+ {
+  let template = "Question: {query}";
+  let missing =
+    validate_template(template, false)
+      .unwrap();
+  assert_eq!(missing, vec![
+    "{context}"
+  ]);
+  assert!(
+    validate_template(template, true)
+      .is_err()
+  );
+  assert!(
+    validate_template(
+      "{query} {context}",
+      true
+    )
+    .unwrap()
+    .is_empty()
+  );
+}
+
+#[test]
+fn fit_prompt_trims_context_tail() {
+  let template =
+    "Q: {query}\nC: {context}";
+  let context =
+    "first chunk\n---\nsecond chunk";
+  let prompt = fit_prompt(
+    template,
+    "whale",
+    context,
+    Some(30)
+  )
+  .unwrap();
+  assert!(prompt.chars().count() <= 30);
+  assert!(prompt.contains("Q: whale"));
+  assert!(
+    prompt.contains("first chunk")
+  );
+  assert!(!prompt.contains("second"));
+  assert!(
+    fit_prompt(
+      template,
+      "whale",
+      context,
+      Some(5)
+    )
+    .is_err()
+  );
+}
+
+#[test]
+fn template_braces_escape_and_unknown_placeholders_stay()
+ {
+  let template =
+    "{{not_a_placeholder}} {\"q\": \
+     \"{query}\"} {other}\n{context}";
+  let prompt = fit_prompt(
+    template,
+    "whale {context}",
+    "ctx",
+    None
+  )
+  .unwrap();
+  assert_eq!(
+    prompt,
+    "{not_a_placeholder} {\"q\": \
+     \"whale {context}\"} {other}\nctx"
+  );
+  assert!(
+    validate_template(
+      "{{query}} {context}",
+      false
+    )
+    .unwrap()
+    .contains(&"{query}")
+  );
+}
+
+#[test]
+fn boost_cutoff_leaves_the_tail_unboosted()
+ {
+  let hits = vec![
+    search_hit("a", "the whale", 0.9),
+    search_hit("b", "a ship", 0.8),
+    search_hit(
+      "c",
+      "whale ishmael ahab captain",
+      0.1
+    ),
+  ];
+  let boosting =
+    Stage3RerankerStrategyConfig {
+      mode:
+        Stage3RerankMode::TermOverlap,
+      boost_terms: vec![
+        "whale".into(),
+        "ishmael".into(),
+        "ahab".into(),
+        "captain".into(),
+      ],
+      boost_factor: 0.5,
+      ..Default::default()
+    };
+  let order = |cutoff| {
+    let strategy =
+      Stage3RerankerStrategyConfig {
+        boost_rank_cutoff: cutoff,
+        ..boosting.clone()
+      };
+    rerank_hits(&hits, &strategy)
+      .iter()
+      .map(|entry| {
+        entry.hit.chunk.id.clone()
+      })
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(order(None), vec![
+    "c", "a", "b"
+  ]);
+  assert_eq!(order(Some(2)), vec![
+    "a", "b", "c"
+  ]);
+}
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{
+  HashMap,
+  HashSet
+};
 use std::fs::{
   self,
   File
 };
 use std::io::{
   BufRead,
-  BufReader,
   BufWriter,
   Write
 };
@@ -23,11 +25,12 @@ use serde::{
   Deserialize,
   Serialize
 };
-use serde_json;
-use unicode_segmentation::UnicodeSegmentation;
 
 use crate::chunk::Chunk;
 use crate::config::Config;
+use crate::state::State;
+use crate::storage;
+use crate::tokenizer::Tokenizer;
 
 #[derive(
   Clone, Debug, Deserialize, Serialize,
@@ -61,12 +64,10 @@ pub fn train_model(
   let chunks_file = chunks_override
     .map(PathBuf::from)
     .unwrap_or_else(|| {
-      PathBuf::from(
-        &config
-          .stage1
-          .storage
-          .chunks_file
-      )
+      config
+        .stage1
+        .storage
+        .chunks_path()
     });
   let models_dir = Path::new(
     &config.stage4.models_dir
@@ -91,30 +92,42 @@ pub fn train_model(
       &training_path
     )?);
 
-  let file = File::open(&chunks_file)
-    .with_context(|| {
-    format!(
-      "open chunks file {:?}",
-      chunks_file
-    )
-  })?;
-  let reader = BufReader::new(file);
+  let state = State::load_from(
+    config.stage1.storage.state_path()
+  )?;
+  let doc_weights = document_weights(
+    &state,
+    &config.stage4.training.weight_tag
+  );
+  let (chunks, source) = load_chunks(
+    config,
+    state,
+    chunks_file,
+    chunks_override.is_none()
+  )?;
   let limit =
     config.stage4.training.sample_limit;
+  let tokenizer =
+    Tokenizer::from_config(
+      &config.stage1.embedder
+    );
   let mut counts = HashMap::new();
+  let mut document_frequency =
+    HashMap::new();
+  let mut documents = 0usize;
   let mut examples = 0usize;
-  for line in reader.lines() {
-    let line = line?;
-    if line.trim().is_empty() {
-      continue;
-    }
-    let chunk: Chunk =
-      serde_json::from_str(&line)
-        .context("parse chunk json")?;
+  for chunk in chunks {
     accumulate_counts(
+      &tokenizer,
       &chunk.text,
-      &mut counts
+      doc_weights
+        .get(&chunk.doc_id)
+        .copied()
+        .unwrap_or(1.0),
+      &mut counts,
+      &mut document_frequency
     );
+    documents += 1;
     if examples < limit {
       serde_json::to_writer(
         &mut training_writer,
@@ -127,19 +140,25 @@ pub fn train_model(
   if examples == 0 {
     anyhow::bail!(
       "no chunks read from {:?}",
-      chunks_file
+      source
     );
   }
-  let total: f32 = counts
-    .values()
-    .map(|count| *count as f32)
-    .sum();
+  let total: f32 =
+    counts.values().sum();
+  let smoothing = config
+    .stage1
+    .embedder
+    .idf_smoothing;
   let mut weights = HashMap::new();
   if total > 0.0 {
     for (token, count) in counts {
+      let idf = smoothing.idf(
+        documents,
+        document_frequency[&token]
+      );
       weights.insert(
         token,
-        count as f32 / total
+        count / total * idf
       );
     }
   }
@@ -167,21 +186,124 @@ pub fn train_model(
   })
 }
 
+/// The chunks in `chunks_file` and the
+/// path they were read from. When the
+/// file (or its compressed sibling) is
+/// missing and `state_fallback` is set,
+/// the chunks of `state` are used
+/// instead.
+fn load_chunks(
+  config: &Config,
+  state: State,
+  chunks_file: PathBuf,
+  state_fallback: bool
+) -> Result<(Vec<Chunk>, PathBuf)> {
+  if state_fallback
+    && !storage::resolve(&chunks_file)
+      .exists()
+  {
+    let state_path = config
+      .stage1
+      .storage
+      .state_path();
+    println!(
+      "Chunks file {:?} not found; \
+       training from the chunks in \
+       {:?}",
+      chunks_file, state_path
+    );
+    return Ok((
+      state.chunks,
+      state_path
+    ));
+  }
+  let reader =
+    storage::open(&chunks_file)
+      .with_context(|| {
+        format!(
+          "open chunks file {:?}",
+          chunks_file
+        )
+      })?;
+  let mut chunks = Vec::new();
+  for line in reader.lines() {
+    let line = line?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    chunks.push(
+      serde_json::from_str(&line)
+        .context("parse chunk json")?
+    );
+  }
+  Ok((chunks, chunks_file))
+}
+
+/// Training weight of each document
+/// carrying a `weight_tag` tag. Values
+/// that are not positive numbers are
+/// ignored with a warning.
+fn document_weights(
+  state: &State,
+  weight_tag: &str
+) -> HashMap<String, f32> {
+  let mut weights = HashMap::new();
+  for document in &state.documents {
+    let Some(value) =
+      document.tags.get(weight_tag)
+    else {
+      continue;
+    };
+    match value.trim().parse::<f32>() {
+      | Ok(weight)
+        if weight.is_finite()
+          && weight > 0.0 =>
+      {
+        weights.insert(
+          document.id.clone(),
+          weight
+        );
+      }
+      | _ => {
+        eprintln!(
+          "Warning: ignoring {} = \
+           {:?} on {}",
+          weight_tag,
+          value,
+          document.path
+        );
+      }
+    }
+  }
+  weights
+}
+
 fn accumulate_counts(
+  tokenizer: &Tokenizer,
   text: &str,
-  counts: &mut HashMap<String, usize>
+  weight: f32,
+  counts: &mut HashMap<String, f32>,
+  document_frequency: &mut HashMap<
+    String,
+    usize
+  >
 ) {
-  for word in text
-    .unicode_words()
-    .map(|word| word.to_lowercase())
-  {
-    *counts.entry(word).or_insert(0) +=
-      1;
+  let mut seen = HashSet::new();
+  for word in tokenizer.tokenize(text) {
+    if seen.insert(word.clone()) {
+      *document_frequency
+        .entry(word.clone())
+        .or_insert(0) += 1;
+    }
+    *counts
+      .entry(word)
+      .or_insert(0.0) += weight;
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::BTreeMap;
   use std::fs::File;
   use std::io::Write;
 
@@ -189,7 +311,13 @@ mod tests {
 
   use super::*;
   use crate::chunk::Chunk;
-  use crate::config::Config;
+  use crate::config::{
+    Config,
+    EmbedderKind,
+    IdfSmoothing
+  };
+  use crate::embedder::build_embedder;
+  use crate::state::Document;
 
   #[test]
   fn train_model_writes_manifest()
@@ -208,7 +336,13 @@ mod tests {
       text: "alpha beta".into(),
       start: 0,
       end: 0,
-      strategy: crate::chunk::ChunkStrategy::Structured
+      strategy: crate::chunk::ChunkStrategy::Structured,
+      language: None,
+      raw_start: None,
+      raw_end: None,
+      summary: None,
+      heading: None,
+      run_id: 0
     };
     serde_json::to_writer(
       &mut file, &chunk
@@ -249,4 +383,234 @@ mod tests {
     );
     Ok(())
   }
+
+  #[test]
+  fn idf_smoothing_shapes_stored_and_query_vectors()
+  -> Result<()> {
+    let temp = TempDir::new()?;
+    let chunk_file =
+      temp.path().join("chunks.jsonl");
+    fs::write(
+      &chunk_file,
+      concat!(
+        r#"{"id":"a","doc_id":"a","text":"whale ship","start":0,"end":10,"strategy":"structured"}"#,
+        "\n",
+        r#"{"id":"b","doc_id":"b","text":"whale ahab","start":0,"end":10,"strategy":"structured"}"#,
+        "\n"
+      )
+    )?;
+    let mut config = Config::default();
+    config.stage1.storage.chunks_file =
+      chunk_file
+        .to_string_lossy()
+        .into();
+    config.stage4.models_dir = temp
+      .path()
+      .join("models")
+      .to_string_lossy()
+      .into();
+    let mut vectors =
+      |smoothing,
+       version: &str|
+       -> Result<_> {
+        config
+          .stage1
+          .embedder
+          .idf_smoothing = smoothing;
+        train_model(
+          &config,
+          "idf",
+          Some(version),
+          None
+        )?;
+        let embedder = build_embedder(
+          EmbedderKind::Custom {
+            name:    "idf".into(),
+            version: Some(
+              version.into()
+            )
+          },
+          &config
+        )?;
+        Ok((
+          embedder.embed("whale ship"),
+          embedder.embed("whale")
+        ))
+      };
+    let (plain_stored, plain_query) =
+      vectors(
+        IdfSmoothing::None,
+        "v1"
+      )?;
+    let (smooth_stored, smooth_query) =
+      vectors(
+        IdfSmoothing::PlusOne,
+        "v2"
+      )?;
+    // Every chunk has "whale", so
+    // unsmoothed IDF zeroes it out.
+    assert_eq!(
+      plain_stored["whale"],
+      0.0
+    );
+    assert!(
+      smooth_stored["whale"] > 0.0
+    );
+    assert!(
+      smooth_stored["whale"]
+        .is_finite()
+    );
+    assert_ne!(
+      plain_stored,
+      smooth_stored
+    );
+    assert_ne!(
+      plain_query,
+      smooth_query
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn training_falls_back_to_state_chunks()
+  -> Result<()> {
+    let temp = TempDir::new()?;
+    let mut config = Config::default();
+    config.stage1.storage.chunks_file =
+      temp
+        .path()
+        .join("chunks.jsonl")
+        .to_string_lossy()
+        .into();
+    config.stage1.storage.state_file =
+      temp
+        .path()
+        .join("state.json")
+        .to_string_lossy()
+        .into();
+    config.stage4.models_dir = temp
+      .path()
+      .join("models")
+      .to_string_lossy()
+      .into();
+    let mut state = State {
+      chunks: vec![Chunk {
+        id: "c".into(),
+        doc_id: "d".into(),
+        text: "gamma delta".into(),
+        start: 0,
+        end: 11,
+        strategy: crate::chunk::ChunkStrategy::Structured,
+        language: None,
+        raw_start: None,
+        raw_end: None,
+        summary: None,
+        heading: None,
+        run_id: 0
+      }],
+      ..State::default()
+    };
+    state.save_to(
+      &config.stage1.storage.state_file
+    )?;
+    let result = train_model(
+      &config,
+      "from-state",
+      Some("v1"),
+      None
+    )?;
+    assert_eq!(
+      result.manifest.example_count,
+      1
+    );
+    assert!(
+      result
+        .manifest
+        .token_weights
+        .contains_key("gamma")
+    );
+    assert!(
+      result.manifest_path.exists()
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn tagged_documents_weigh_more()
+  -> Result<()> {
+    let temp = TempDir::new()?;
+    let mut config = Config::default();
+    config.stage1.storage.state_file =
+      temp
+        .path()
+        .join("state.json")
+        .to_string_lossy()
+        .into();
+    config.stage4.models_dir = temp
+      .path()
+      .join("models")
+      .to_string_lossy()
+      .into();
+    let mut state = State::default();
+    for (doc_id, text, weight) in [
+      (
+        "a",
+        "alpha shared",
+        Some("2.0")
+      ),
+      ("b", "beta shared", None)
+    ] {
+      state.documents.push(Document {
+        id:          doc_id.into(),
+        path:        format!(
+          "{}.txt",
+          doc_id
+        ),
+        hash:        doc_id.into(),
+        token_count: 2,
+        tags:        weight
+          .map(|weight| {
+            BTreeMap::from([(
+              "train_weight".into(),
+              weight.into()
+            )])
+          })
+          .unwrap_or_default(),
+        aliases:     Vec::new(),
+        normalized:  None
+      });
+      state.chunks.push(Chunk {
+        id: format!("{}1", doc_id),
+        doc_id: doc_id.into(),
+        text: text.into(),
+        start: 0,
+        end: text.len(),
+        strategy: crate::chunk::ChunkStrategy::Structured,
+        language: None,
+        raw_start: None,
+        raw_end: None,
+        summary: None,
+        heading: None,
+        run_id: 0
+      });
+    }
+    state.save_to(
+      &config.stage1.storage.state_file
+    )?;
+    let weights = train_model(
+      &config,
+      "weighted",
+      Some("v1"),
+      None
+    )?
+    .manifest
+    .token_weights;
+    assert!(
+      (weights["alpha"]
+        - 2.0 * weights["beta"])
+        .abs()
+        < 1e-6
+    );
+    Ok(())
+  }
 }
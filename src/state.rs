@@ -1,12 +1,14 @@
 #![allow(dead_code)]
 
+use std::collections::BTreeMap;
 use std::path::{
   Path,
   PathBuf
 };
 use std::{
   env,
-  fs
+  fs,
+  mem
 };
 
 use anyhow::Context;
@@ -14,9 +16,18 @@ use serde::{
   Deserialize,
   Serialize
 };
+use sha2::{
+  Digest,
+  Sha256
+};
 
 use crate::chunk::Chunk;
-use crate::index::IndexEntry;
+use crate::corpus_stats::CorpusStats;
+use crate::index::{
+  IndexEntry,
+  VectorIndex
+};
+use crate::storage;
 
 pub fn data_dir() -> PathBuf {
   if let Ok(override_dir) =
@@ -29,12 +40,57 @@ pub fn data_dir() -> PathBuf {
 }
 
 #[derive(
-  Debug, Serialize, Deserialize, Default,
+  Clone,
+  Debug,
+  Serialize,
+  Deserialize,
+  Default,
 )]
 pub struct State {
-  pub documents:     Vec<Document>,
-  pub chunks:        Vec<Chunk>,
-  pub index_entries: Vec<IndexEntry>
+  pub documents:      Vec<Document>,
+  pub chunks:         Vec<Chunk>,
+  /// Index entries of states written
+  /// before the index file existed;
+  /// see [`State::load_index`]
+  #[serde(
+    default,
+    rename = "index_entries",
+    skip_serializing
+  )]
+  pub inline_index:   Vec<IndexEntry>,
+  /// Checksum of the index file saved
+  /// with the state, when the index
+  /// has any entries
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub index_checksum: Option<u32>,
+  #[serde(default)]
+  pub stats:          CorpusStats,
+  /// Saved search results, by name
+  #[serde(
+    default,
+    skip_serializing_if = "BTreeMap::is_empty"
+  )]
+  pub collections:
+    BTreeMap<String, Collection>,
+  /// ID of the latest ingest run;
+  /// increases with every ingest that
+  /// stores chunks
+  #[serde(default)]
+  pub ingest_run_id:  u64
+}
+
+/// The chunk IDs a query returned when
+/// it was saved.
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+pub struct Collection {
+  pub query:     String,
+  pub saved_at:  String,
+  pub chunk_ids: Vec<String>
 }
 
 #[derive(
@@ -44,7 +100,28 @@ pub struct Document {
   pub id:          String,
   pub path:        String,
   pub hash:        String,
-  pub token_count: usize
+  pub token_count: usize,
+  /// Metadata from the file's
+  /// front-matter, when parsed
+  #[serde(
+    default,
+    skip_serializing_if = "BTreeMap::is_empty"
+  )]
+  pub tags: BTreeMap<String, String>,
+  /// Other paths ingested with the
+  /// same content
+  #[serde(
+    default,
+    skip_serializing_if = "Vec::is_empty"
+  )]
+  pub aliases:     Vec<String>,
+  /// Full normalized text, when
+  /// `keep_normalized` is set
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub normalized:  Option<String>
 }
 
 impl State {
@@ -53,36 +130,52 @@ impl State {
     Self::load_from(Self::path())
   }
 
+  /// Loads the state at `path` or, when
+  /// missing, at its gzipped (or
+  /// uncompressed) sibling. Gzipped
+  /// contents are detected by their
+  /// magic bytes. The vector index is
+  /// loaded separately, by
+  /// [`State::load_index`].
   pub fn load_from(
     path: impl AsRef<Path>
   ) -> anyhow::Result<Self> {
-    let path = path.as_ref();
+    let path = storage::resolve(path);
     if path.exists() {
       let contents =
-        fs::read_to_string(&path)
+        storage::read(&path)
           .with_context(|| {
             format!(
               "read state from {:?}",
               path
             )
           })?;
-      serde_json::from_str(&contents)
+      let state: Self =
+        serde_json::from_slice(
+          &contents
+        )
         .context(
           "parse saved Oxbed state"
-        )
+        )?;
+      Ok(state)
     } else {
       Ok(Self::default())
     }
   }
 
   pub fn save(
-    &self
+    &mut self
   ) -> anyhow::Result<()> {
     self.save_to(Self::path())
   }
 
+  /// Writes the state to `path`,
+  /// gzipped when it ends in `.gz`.
+  /// The vector index is saved
+  /// separately, by
+  /// [`State::save_index`].
   pub fn save_to(
-    &self,
+    &mut self,
     path: impl AsRef<Path>
   ) -> anyhow::Result<()> {
     let path = path.as_ref();
@@ -104,13 +197,16 @@ impl State {
       .context(
         "serialize Oxbed corpus state"
       )?;
-    fs::write(path, serialized)
-      .with_context(|| {
-        format!(
-          "write state to {:?}",
-          path
-        )
-      })?;
+    storage::write(
+      path,
+      serialized.as_bytes()
+    )
+    .with_context(|| {
+      format!(
+        "write state to {:?}",
+        path
+      )
+    })?;
     Ok(())
   }
 
@@ -118,6 +214,103 @@ impl State {
     data_dir().join("state.json")
   }
 
+  /// Loads the vector index saved with
+  /// the state at `path`, or the
+  /// entries carried inline by states
+  /// written before the index file
+  /// existed. An index file that
+  /// cannot be read or does not match
+  /// `index_checksum` is dropped with a
+  /// warning, leaving the chunks for
+  /// `oxbed reindex` to embed again.
+  pub fn load_index(
+    &mut self,
+    path: impl AsRef<Path>
+  ) -> VectorIndex {
+    let legacy =
+      mem::take(&mut self.inline_index);
+    let Some(checksum) =
+      self.index_checksum
+    else {
+      return VectorIndex::from_entries(
+        legacy
+      );
+    };
+    let index_path = Self::index_path(
+      &storage::resolve(path)
+    );
+    let error =
+      match VectorIndex::load_from(
+        &index_path
+      ) {
+        | Ok(index)
+          if index.checksum()
+            == Some(checksum) =>
+        {
+          return index;
+        }
+        | Ok(_) => {
+          anyhow::anyhow!(
+            "index file {:?} does not \
+             match its state file; \
+             run `oxbed reindex` or \
+             re-ingest",
+            index_path
+          )
+        }
+        | Err(err) => err
+      };
+    eprintln!(
+      "Warning: {:#}; loading without \
+       stored vectors",
+      error
+    );
+    self.index_checksum = None;
+    VectorIndex::from_entries(Vec::new())
+  }
+
+  /// Saves `index` beside the state at
+  /// `path` and records its checksum;
+  /// call before [`State::save_to`].
+  pub fn save_index(
+    &mut self,
+    index: &mut VectorIndex,
+    path: impl AsRef<Path>
+  ) -> anyhow::Result<()> {
+    self.index_checksum = index
+      .save_to(&Self::index_path(
+        path.as_ref()
+      ))?;
+    Ok(())
+  }
+
+  /// The index file kept beside the
+  /// state file at `state_path`:
+  /// `state.json` or `state.json.gz`
+  /// store theirs in `state.index.bin`.
+  pub fn index_path(
+    state_path: &Path
+  ) -> PathBuf {
+    let name = state_path
+      .file_name()
+      .map(|name| {
+        name
+          .to_string_lossy()
+          .into_owned()
+      })
+      .unwrap_or_default();
+    let name = name
+      .strip_suffix(".gz")
+      .unwrap_or(&name);
+    let stem = name
+      .strip_suffix(".json")
+      .unwrap_or(name);
+    state_path.with_file_name(format!(
+      "{}.index.bin",
+      stem
+    ))
+  }
+
   pub fn has_document(
     &self,
     hash: &str
@@ -128,6 +321,82 @@ impl State {
       .any(|doc| doc.hash == hash)
   }
 
+  /// A digest of every stored document
+  /// and chunk ID. Mixed into
+  /// `stage1.seed` so that seeded runs
+  /// over a grown corpus draw fresh IDs
+  /// instead of repeating the previous
+  /// run's.
+  pub fn id_digest(&self) -> u64 {
+    let mut hasher = Sha256::new();
+    for id in self
+      .documents
+      .iter()
+      .map(|doc| &doc.id)
+      .chain(
+        self
+          .chunks
+          .iter()
+          .map(|chunk| &chunk.id)
+      )
+    {
+      hasher.update(id.as_bytes());
+      hasher.update([0]);
+    }
+    let digest = hasher.finalize();
+    u64::from_le_bytes(
+      digest[..8]
+        .try_into()
+        .expect("sha256 digest")
+    )
+  }
+
+  /// The ID the next ingest run stamps
+  /// its chunks with; see
+  /// [`State::record_ingest_run`].
+  pub fn next_ingest_run(&self) -> u64 {
+    self.ingest_run_id + 1
+  }
+
+  /// Records `run_id` as the latest
+  /// ingest run, once it has stored a
+  /// chunk.
+  pub fn record_ingest_run(
+    &mut self,
+    run_id: u64
+  ) {
+    self.ingest_run_id = run_id;
+  }
+
+  /// The highest run ID among the
+  /// stored chunks, ignoring chunks
+  /// stored before runs were recorded.
+  pub fn latest_run(
+    &self
+  ) -> Option<u64> {
+    self
+      .chunks
+      .iter()
+      .map(|chunk| chunk.run_id)
+      .filter(|run_id| *run_id > 0)
+      .max()
+  }
+
+  /// Drops a document and its chunks;
+  /// the caller keeps the vector index
+  /// in sync.
+  pub fn remove_document(
+    &mut self,
+    doc_id: &str
+  ) {
+    self
+      .documents
+      .retain(|doc| doc.id != doc_id);
+    self.chunks.retain(|chunk| {
+      chunk.doc_id != doc_id
+    });
+  }
+
   pub fn find_chunk(
     &self,
     chunk_id: &str
@@ -147,3 +416,135 @@ impl State {
       .find(|doc| doc.id == doc_id)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use tempfile::TempDir;
+
+  use super::*;
+  use crate::storage::storage_path;
+
+  #[test]
+  fn compressed_state_reloads_identically()
+   {
+    let dir = TempDir::new().unwrap();
+    let plain =
+      dir.path().join("state.json");
+    let compressed =
+      storage_path(&plain, true);
+    let mut state = State {
+      documents: vec![Document {
+        id:          "d1".into(),
+        path:        "moby.txt".into(),
+        hash:        "abc".into(),
+        token_count: 3,
+        tags:        BTreeMap::from([
+          (
+            "author".into(),
+            "Melville".into()
+          )
+        ]),
+        aliases:     Vec::new(),
+        normalized:  None
+      }],
+      ..State::default()
+    };
+    state.save_to(&compressed).unwrap();
+    assert!(!plain.exists());
+    assert!(
+      fs::read(&compressed)
+        .unwrap()
+        .starts_with(&[0x1f, 0x8b])
+    );
+    for path in [&compressed, &plain] {
+      let reloaded =
+        State::load_from(path).unwrap();
+      assert_eq!(
+        serde_json::to_value(&reloaded)
+          .unwrap(),
+        serde_json::to_value(&state)
+          .unwrap()
+      );
+    }
+  }
+
+  #[test]
+  fn index_entries_live_in_their_own_file()
+   {
+    let dir = TempDir::new().unwrap();
+    let path =
+      dir.path().join("state.json");
+    fs::write(
+      &path,
+      r#"{"documents": [], "chunks": [],
+        "index_entries": [{
+          "chunk_id": "c1",
+          "doc_id": "d1",
+          "vector": {"whale": 1.0}
+        }]}"#
+    )
+    .unwrap();
+    let mut state =
+      State::load_from(&path).unwrap();
+    let mut index =
+      state.load_index(&path);
+    assert_eq!(
+      index.entries().len(),
+      1
+    );
+    state
+      .save_index(&mut index, &path)
+      .unwrap();
+    state.save_to(&path).unwrap();
+    let index_path = dir
+      .path()
+      .join("state.index.bin");
+    assert_eq!(
+      State::index_path(&path),
+      index_path
+    );
+    assert!(
+      !fs::read_to_string(&path)
+        .unwrap()
+        .contains("whale")
+    );
+    let mut reloaded =
+      State::load_from(&path).unwrap();
+    assert_eq!(
+      reloaded
+        .load_index(&path)
+        .entries()[0]
+        .vector,
+      index.entries()[0].vector
+    );
+    assert!(
+      !dir
+        .path()
+        .join("state.index.bin.tmp")
+        .exists()
+    );
+    fs::write(&index_path, b"garbage")
+      .unwrap();
+    let mut reloaded =
+      State::load_from(&path).unwrap();
+    assert!(
+      reloaded
+        .load_index(&path)
+        .entries()
+        .is_empty()
+    );
+    assert_eq!(
+      reloaded.index_checksum,
+      None
+    );
+    index.remove_document("d1");
+    state
+      .save_index(&mut index, &path)
+      .unwrap();
+    assert!(!index_path.exists());
+    assert_eq!(
+      state.index_checksum,
+      None
+    );
+  }
+}
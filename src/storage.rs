@@ -0,0 +1,236 @@
+use std::fs::{
+  self,
+  File
+};
+use std::io::{
+  BufRead,
+  BufReader,
+  Read,
+  Write
+};
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use anyhow::{
+  Context,
+  Result
+};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+const GZIP_MAGIC: [u8; 2] =
+  [0x1f, 0x8b];
+const GZIP_SUFFIX: &str = "gz";
+
+/// `path` with a `.gz` suffix appended
+/// when `compress` is set.
+pub fn storage_path(
+  path: impl AsRef<Path>,
+  compress: bool
+) -> PathBuf {
+  let path = path.as_ref();
+  if compress && !is_gz_path(path) {
+    sibling(path)
+  } else {
+    path.to_path_buf()
+  }
+}
+
+/// `path` when it exists, otherwise its
+/// compressed or uncompressed sibling
+/// when that exists, so files written
+/// with the other `compress` setting
+/// are still found.
+pub fn resolve(
+  path: impl AsRef<Path>
+) -> PathBuf {
+  let path = path.as_ref();
+  if path.exists() {
+    return path.to_path_buf();
+  }
+  let other = sibling(path);
+  if other.exists() {
+    other
+  } else {
+    path.to_path_buf()
+  }
+}
+
+/// Opens the resolved `path` for
+/// buffered reading, decompressing it
+/// as it is read when it starts with
+/// the gzip magic bytes.
+pub fn open(
+  path: impl AsRef<Path>
+) -> Result<Box<dyn BufRead>> {
+  let path = resolve(path);
+  let mut reader = BufReader::new(
+    File::open(&path).with_context(
+      || format!("open {:?}", path)
+    )?
+  );
+  let compressed = reader
+    .fill_buf()
+    .with_context(|| {
+      format!("read {:?}", path)
+    })?
+    .starts_with(&GZIP_MAGIC);
+  if compressed {
+    Ok(Box::new(BufReader::new(
+      MultiGzDecoder::new(reader)
+    )))
+  } else {
+    Ok(Box::new(reader))
+  }
+}
+
+/// Reads the whole resolved `path`
+/// through [`open`].
+pub fn read(
+  path: impl AsRef<Path>
+) -> Result<Vec<u8>> {
+  let path = resolve(path);
+  let mut bytes = Vec::new();
+  open(&path)?
+    .read_to_end(&mut bytes)
+    .with_context(|| {
+      format!("read {:?}", path)
+    })?;
+  Ok(bytes)
+}
+
+/// Writes `bytes` to `path`, gzipped
+/// when it ends in `.gz`, and removes
+/// a stale sibling written with the
+/// other `compress` setting.
+pub fn write(
+  path: impl AsRef<Path>,
+  bytes: &[u8]
+) -> Result<()> {
+  let path = path.as_ref();
+  if is_gz_path(path) {
+    replace(path, &gzip(bytes)?)
+  } else {
+    replace(path, bytes)
+  }?;
+  let other = sibling(path);
+  if other.exists() {
+    fs::remove_file(&other)
+      .with_context(|| {
+        format!(
+          "remove stale {:?}",
+          other
+        )
+      })?;
+  }
+  Ok(())
+}
+
+/// Writes `bytes` to a temporary file
+/// beside `path` and renames it over
+/// `path`, so a failed or interrupted
+/// write leaves the old contents in
+/// place.
+pub fn replace(
+  path: &Path,
+  bytes: &[u8]
+) -> Result<()> {
+  let mut temp =
+    path.as_os_str().to_owned();
+  temp.push(".tmp");
+  let temp = PathBuf::from(temp);
+  fs::write(&temp, bytes)
+    .with_context(|| {
+      format!("write {:?}", temp)
+    })?;
+  fs::rename(&temp, path).with_context(
+    || {
+      format!(
+        "move {:?} to {:?}",
+        temp, path
+      )
+    }
+  )
+}
+
+fn is_gz_path(path: &Path) -> bool {
+  path.extension().is_some_and(|ext| {
+    ext == GZIP_SUFFIX
+  })
+}
+
+/// Toggles the `.gz` suffix.
+fn sibling(path: &Path) -> PathBuf {
+  if is_gz_path(path) {
+    path.with_extension("")
+  } else {
+    let mut name =
+      path.as_os_str().to_owned();
+    name.push(".");
+    name.push(GZIP_SUFFIX);
+    PathBuf::from(name)
+  }
+}
+
+fn gzip(
+  bytes: &[u8]
+) -> Result<Vec<u8>> {
+  let mut encoder = GzEncoder::new(
+    Vec::new(),
+    Compression::default()
+  );
+  encoder.write_all(bytes)?;
+  Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use tempfile::TempDir;
+
+  use super::*;
+
+  #[test]
+  fn compressed_files_are_found_from_either_name()
+   {
+    let dir = TempDir::new().unwrap();
+    let plain =
+      dir.path().join("chunks.jsonl");
+    let compressed =
+      storage_path(&plain, true);
+    assert_eq!(
+      compressed,
+      dir
+        .path()
+        .join("chunks.jsonl.gz")
+    );
+    let contents =
+      "{\"id\":\"a\"}\n".repeat(100);
+    write(&plain, contents.as_bytes())
+      .unwrap();
+    write(
+      &compressed,
+      contents.as_bytes()
+    )
+    .unwrap();
+    assert!(!plain.exists());
+    let raw =
+      fs::read(&compressed).unwrap();
+    assert!(
+      raw.starts_with(&GZIP_MAGIC)
+    );
+    assert!(raw.len() < contents.len());
+    assert_eq!(
+      read(&plain).unwrap(),
+      contents.as_bytes()
+    );
+    let mut corrupt = raw.clone();
+    let last = corrupt.len() - 1;
+    corrupt[last] ^= 1;
+    fs::write(&compressed, corrupt)
+      .unwrap();
+    assert!(read(&plain).is_err());
+  }
+}
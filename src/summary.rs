@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::Summarizer;
+use crate::tokenizer::Tokenizer;
+
+/// Summarizes `text` with the
+/// configured summarizer, or returns
+/// `None` when summaries are off.
+pub fn summarize(
+  summarizer: Summarizer,
+  text: &str,
+  tokenizer: &Tokenizer
+) -> Option<String> {
+  match summarizer {
+    | Summarizer::None => None,
+    | Summarizer::Extractive => {
+      extractive(text, tokenizer)
+    }
+  }
+}
+
+/// The first sentence followed by the
+/// remaining sentence whose tokens have
+/// the highest mean frequency in the
+/// whole text (the earliest one on
+/// ties). `None` for text without a
+/// sentence.
+fn extractive(
+  text: &str,
+  tokenizer: &Tokenizer
+) -> Option<String> {
+  let sentences: Vec<&str> = text
+    .split_sentence_bounds()
+    .map(str::trim)
+    .filter(|sentence| {
+      !sentence.is_empty()
+    })
+    .collect();
+  let (first, rest) =
+    sentences.split_first()?;
+  let mut counts = HashMap::new();
+  for token in tokenizer.tokenize(text)
+  {
+    *counts
+      .entry(token)
+      .or_insert(0usize) += 1;
+  }
+  let mut best: Option<(&str, f32)> =
+    None;
+  for sentence in rest {
+    let tokens =
+      tokenizer.tokenize(sentence);
+    if tokens.is_empty() {
+      continue;
+    }
+    let score = tokens
+      .iter()
+      .map(|token| {
+        counts
+          .get(token)
+          .copied()
+          .unwrap_or(0) as f32
+      })
+      .sum::<f32>()
+      / tokens.len() as f32;
+    if best.is_none_or(|(_, top)| {
+      score > top
+    }) {
+      best = Some((sentence, score));
+    }
+  }
+  Some(match best {
+    | Some((top, _)) => {
+      format!("{} {}", first, top)
+    }
+    | None => first.to_string()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn extractive_keeps_first_and_most_typical_sentence()
+   {
+    let text = "Whales sing. The ship \
+                sailed on. Whales \
+                sing to whales at \
+                dawn.";
+    assert_eq!(
+      summarize(
+        Summarizer::Extractive,
+        text,
+        &Tokenizer::default()
+      )
+      .as_deref(),
+      Some(
+        "Whales sing. Whales sing to \
+         whales at dawn."
+      )
+    );
+    assert_eq!(
+      summarize(
+        Summarizer::None,
+        text,
+        &Tokenizer::default()
+      ),
+      None
+    );
+  }
+}
@@ -0,0 +1,238 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::{
+  Stage1Embedder,
+  Stemmer
+};
+use crate::language;
+use crate::lemma::lemmatize;
+use crate::normalization::{
+  EMAIL_TOKEN,
+  URL_TOKEN
+};
+
+/// Sentinel emitted in place of numeric
+/// tokens when number bucketing is on.
+pub const NUMBER_TOKEN: &str = "<num>";
+
+/// Sentinels the normalizer writes into
+/// text, kept whole as tokens since
+/// word segmentation would strip their
+/// brackets.
+const SENTINELS: [&str; 2] =
+  [URL_TOKEN, EMAIL_TOKEN];
+
+/// Splits text into the word tokens
+/// shared by every embedder so chunks
+/// and queries always land in the same
+/// token space. Tokens are lowercased
+/// unless `lowercase_tokens` is off,
+/// then reduced by the configured
+/// `stemmer`. Tokenizers from
+/// [`Tokenizer::for_language`] also
+/// drop that language's stopwords.
+#[derive(Clone, Debug)]
+pub struct Tokenizer {
+  number_bucketing: bool,
+  lowercase:        bool,
+  stemmer:          Stemmer,
+  stopwords: &'static [&'static str]
+}
+
+impl Default for Tokenizer {
+  fn default() -> Self {
+    Self {
+      number_bucketing: false,
+      lowercase:        true,
+      stemmer:          Stemmer::None,
+      stopwords:        &[]
+    }
+  }
+}
+
+impl Tokenizer {
+  pub fn from_config(
+    config: &Stage1Embedder
+  ) -> Self {
+    Self {
+      number_bucketing: config
+        .number_bucketing,
+      lowercase:        config
+        .lowercase_tokens,
+      stemmer:          config.stemmer,
+      stopwords:        &[]
+    }
+  }
+
+  /// This tokenizer set up for text in
+  /// `language` (an ISO 639-3 code, as
+  /// tagged on chunks): it drops the
+  /// language's stopwords, and keeps
+  /// the English-only `stemmer` for
+  /// English alone. `None` leaves it
+  /// unchanged.
+  pub fn for_language(
+    &self,
+    language: Option<&str>
+  ) -> Self {
+    let Some(language) = language
+    else {
+      return self.clone();
+    };
+    Self {
+      stemmer: if language == "eng" {
+        self.stemmer
+      } else {
+        Stemmer::None
+      },
+      stopwords: language::stopwords(
+        language
+      ),
+      ..self.clone()
+    }
+  }
+
+  pub fn tokenize(
+    &self,
+    text: &str
+  ) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some((at, sentinel)) =
+      next_sentinel(rest)
+    {
+      self.push_words(
+        &rest[..at],
+        &mut tokens
+      );
+      tokens.push(sentinel.into());
+      rest =
+        &rest[at + sentinel.len()..];
+    }
+    self.push_words(rest, &mut tokens);
+    tokens
+  }
+
+  fn push_words(
+    &self,
+    text: &str,
+    tokens: &mut Vec<String>
+  ) {
+    tokens.extend(
+      text
+        .unicode_words()
+        .filter(|word| {
+          !self.is_stopword(word)
+        })
+        .map(|word| self.token(word))
+    );
+  }
+
+  fn is_stopword(
+    &self,
+    word: &str
+  ) -> bool {
+    !self.stopwords.is_empty()
+      && self
+        .stopwords
+        .binary_search(
+          &word.to_lowercase().as_str()
+        )
+        .is_ok()
+  }
+
+  fn token(
+    &self,
+    word: &str
+  ) -> String {
+    if self.number_bucketing
+      && is_numeric(word)
+    {
+      return NUMBER_TOKEN.into();
+    }
+    let token = if self.lowercase {
+      word.to_lowercase()
+    } else {
+      word.to_string()
+    };
+    match self.stemmer {
+      | Stemmer::None => token,
+      | Stemmer::Lemmatize => {
+        lemmatize(&token)
+      }
+    }
+  }
+}
+
+/// The first sentinel in `text` and
+/// its byte offset.
+fn next_sentinel(
+  text: &str
+) -> Option<(usize, &'static str)> {
+  text.match_indices('<').find_map(
+    |(at, _)| {
+      SENTINELS
+        .into_iter()
+        .find(|sentinel| {
+          text[at..]
+            .starts_with(sentinel)
+        })
+        .map(|sentinel| (at, sentinel))
+    }
+  )
+}
+
+fn is_numeric(word: &str) -> bool {
+  word.chars().any(|c| c.is_numeric())
+    && word.chars().all(|c| {
+      c.is_numeric()
+        || c == '.'
+        || c == ','
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn language_routing_picks_stopwords_and_stemming()
+   {
+    let tokenizer = Tokenizer {
+      stemmer: Stemmer::Lemmatize,
+      ..Tokenizer::default()
+    };
+    assert_eq!(
+      tokenizer
+        .for_language(Some("eng"))
+        .tokenize("The mice ran"),
+      vec!["mouse", "run"]
+    );
+    assert_eq!(
+      tokenizer
+        .for_language(Some("spa"))
+        .tokenize("La casa de mice"),
+      vec!["casa", "mice"]
+    );
+    assert_eq!(
+      tokenizer
+        .for_language(None)
+        .tokenize("The mice"),
+      vec!["the", "mouse"]
+    );
+  }
+
+  #[test]
+  fn pattern_sentinels_stay_distinct_from_words()
+   {
+    assert_eq!(
+      Tokenizer::default().tokenize(
+        "URL <url>, email <email>."
+      ),
+      vec![
+        "url", "<url>", "email",
+        "<email>"
+      ]
+    );
+  }
+}